@@ -0,0 +1,146 @@
+use crate::hash::HashFunction;
+
+// Base58Check, the encoding of bitcoin addresses and extended keys
+// the bytes are read as one big endian number and written out in base 58,
+// with an alphabet that leaves out 0, O, I and l so they can't be mixed up
+// every leading zero byte becomes a leading '1', and 4 bytes of double
+// SHA-256 are appended as a checksum against typos
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Errors decoding Base58Check
+#[derive(Debug, PartialEq, Eq)]
+pub enum Base58Error {
+    /// A character outside the alphabet
+    InvalidCharacter,
+    /// Too short to hold a checksum, or the checksum doesn't match
+    InvalidChecksum,
+}
+
+/// First 4 bytes of SHA-256(SHA-256(data))
+fn checksum(data: &[u8]) -> [u8; 4] {
+    let hash = HashFunction::Sha256.digest(&HashFunction::Sha256.digest(data));
+    hash[..4].try_into().unwrap()
+}
+
+/// Base58 encoding of bytes, without a checksum
+pub(crate) fn encode(data: &[u8]) -> String {
+    // repeated division of the whole number by 58, schoolbook style
+    // the digits come out least significant first
+    let mut digits: Vec<u8> = vec![];
+    for byte in data {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let zeros = data.iter().take_while(|byte| **byte == 0).count();
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|digit| ALPHABET[*digit as usize] as char),
+        )
+        .collect()
+}
+
+/// Bytes of a Base58 string, without a checksum
+pub(crate) fn decode(string: &str) -> Result<Vec<u8>, Base58Error> {
+    // the same in reverse, multiplying by 58 and adding every digit
+    // the bytes come out least significant first
+    let mut bytes: Vec<u8> = vec![];
+    for character in string.bytes() {
+        let mut carry = ALPHABET
+            .iter()
+            .position(|c| *c == character)
+            .ok_or(Base58Error::InvalidCharacter)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let zeros = string.bytes().take_while(|c| *c == b'1').count();
+    Ok(std::iter::repeat_n(0, zeros)
+        .chain(bytes.into_iter().rev())
+        .collect())
+}
+
+/// Base58Check encoding, the data followed by its checksum
+pub(crate) fn encode_check(data: &[u8]) -> String {
+    encode(&[data, &checksum(data)].concat())
+}
+
+/// Data of a Base58Check string, with the checksum verified and removed
+pub(crate) fn decode_check(string: &str) -> Result<Vec<u8>, Base58Error> {
+    let mut data = decode(string)?;
+    if data.len() < 4 {
+        return Err(Base58Error::InvalidChecksum);
+    }
+    let expected = data.split_off(data.len() - 4);
+    if checksum(&data)[..] != expected[..] {
+        return Err(Base58Error::InvalidChecksum);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base58::{decode, decode_check, encode, encode_check, Base58Error};
+
+    #[test]
+    fn base58_encoding() {
+        // see: https://github.com/bitcoin/bitcoin/blob/master/src/test/data/base58_encode_decode.json
+        let cases = [
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            (
+                "73696d706c792061206c6f6e6720737472696e67",
+                "2cFupjhnEsSn59qHXstmK2ffpLv2",
+            ),
+            (
+                "00eb15231dfceb60925886b67d065299925915aeb172c06647",
+                "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L",
+            ),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("572e4794", "3EFU7m"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("10c8511e", "Rt5zm"),
+            ("00000000000000000000", "1111111111"),
+        ];
+        for (data, string) in cases {
+            let data = hex::decode(data).unwrap();
+            assert_eq!(encode(&data), string);
+            assert_eq!(decode(string).unwrap(), data);
+        }
+
+        // the address of the genesis block coinbase, with its checksum
+        let data = hex::decode("0062e907b15cbf27d5425399ebf6f0fb50ebb88f18").unwrap();
+        assert_eq!(
+            decode_check("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa").unwrap(),
+            data
+        );
+        assert_eq!(encode_check(&data), "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa");
+        assert_eq!(
+            decode_check("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb"),
+            Err(Base58Error::InvalidChecksum)
+        );
+        assert_eq!(decode("0OIl"), Err(Base58Error::InvalidCharacter));
+    }
+}
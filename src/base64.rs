@@ -0,0 +1,124 @@
+// Base64, see: https://www.rfc-editor.org/rfc/rfc4648#section-4
+// every 3 bytes become 4 characters of 6 bits each, a last group of 1 or 2
+// bytes is padded with '=' to 4 characters
+// base64url (section 5) swaps + and / for - and _ so it fits in URLs and JSON,
+// and JOSE leaves the padding out, see: https://www.rfc-editor.org/rfc/rfc7515#appendix-C
+//
+// decoding is strict like RFC 4648 section 3.5 asks for: no characters outside
+// the alphabet, no missing or extra padding, and the bits the padding leaves
+// over must be zero, so every byte string has exactly one encoding
+
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Base64 encoding of bytes, with padding
+pub(crate) fn encode(data: &[u8]) -> String {
+    encode_with(data, STANDARD, true)
+}
+
+/// Bytes of a padded Base64 string
+/// returns None unless the string is the one canonical encoding of some bytes
+pub(crate) fn decode(string: &str) -> Option<Vec<u8>> {
+    decode_with(string, STANDARD, true)
+}
+
+/// base64url encoding of bytes, without padding
+pub(crate) fn encode_url(data: &[u8]) -> String {
+    encode_with(data, URL_SAFE, false)
+}
+
+/// Bytes of an unpadded base64url string, strict like decode
+pub(crate) fn decode_url(string: &str) -> Option<Vec<u8>> {
+    decode_with(string, URL_SAFE, false)
+}
+
+fn encode_with(data: &[u8], alphabet: &[u8; 64], padded: bool) -> String {
+    let mut string = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                string.push(alphabet[(group >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else if padded {
+                string.push('=');
+            }
+        }
+    }
+    string
+}
+
+fn decode_with(string: &str, alphabet: &[u8; 64], padded: bool) -> Option<Vec<u8>> {
+    let mut string = string.as_bytes();
+    if padded {
+        // one or two '=' fill the last group up to 4 characters
+        if !string.len().is_multiple_of(4) {
+            return None;
+        }
+        let padding = string.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        string = &string[..string.len() - padding];
+    }
+    // a single character of a last group is less than a byte
+    if string.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(string.len() / 4 * 3 + 2);
+    for chunk in string.chunks(4) {
+        let mut group = 0u32;
+        for (i, character) in chunk.iter().enumerate() {
+            let digit = alphabet.iter().position(|c| c == character)? as u32;
+            group |= digit << (18 - 6 * i);
+        }
+        let length = chunk.len() - 1;
+        if group & (0xffffff >> (8 * length)) != 0 {
+            return None;
+        }
+        bytes.extend_from_slice(&group.to_be_bytes()[1..1 + length]);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base64::{decode, decode_url, encode, encode_url};
+
+    #[test]
+    fn base64_encoding() {
+        // see: https://www.rfc-editor.org/rfc/rfc4648#section-10
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (data, string) in cases {
+            assert_eq!(encode(data.as_bytes()), string);
+            assert_eq!(decode(string).unwrap(), data.as_bytes());
+        }
+        assert_eq!(encode(&[0xfb, 0xff, 0xbf]), "+/+/");
+        assert_eq!(decode("+/+/").unwrap(), [0xfb, 0xff, 0xbf]);
+
+        // only the canonical encoding decodes
+        for string in [
+            "Zg", "Zg=", "Zh==", "Zm9=", "Zg==Zg==", "Zg===", "Zm9v\n", "Zm-v",
+        ] {
+            assert_eq!(decode(string), None, "{}", string);
+        }
+
+        // base64url, unpadded
+        assert_eq!(encode_url(&[0xfb, 0xff, 0xbf, 0x66]), "-_-_Zg");
+        assert_eq!(decode_url("-_-_Zg").unwrap(), [0xfb, 0xff, 0xbf, 0x66]);
+        assert_eq!(encode_url(b"fo"), "Zm8");
+        for string in ["Zg==", "Zm8=", "Z", "Zh", "+/+/", "Zm 8"] {
+            assert_eq!(decode_url(string), None, "{}", string);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+// bech32 and bech32m, the encodings of segwit addresses
+// see: https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki
+// and: https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki
+//
+// an address is a human readable part like "bc", the separator '1', and the
+// witness version and program in 5 bit groups followed by a 6 character BCH
+// checksum; version 0 uses the original bech32 checksum constant and every
+// later version, Taproot's 1 among them, the bech32m one
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc830a3;
+const MAX_LENGTH: usize = 90;
+
+/// Errors decoding segwit addresses
+#[derive(Debug, PartialEq, Eq)]
+pub enum Bech32Error {
+    /// Mixed case, a character outside the charset or outside 33-126 in the
+    /// human readable part, or no separator
+    InvalidCharacter,
+    /// Longer than the 90 characters BIP-173 allows
+    InvalidLength,
+    /// The checksum doesn't match, or is the wrong variant for the version
+    InvalidChecksum,
+    /// Another human readable part than the expected one
+    UnexpectedHrp,
+    /// A witness version above 16, or a program of the wrong length
+    InvalidProgram,
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x1ffffff) << 5 ^ *value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+/// The human readable part spread out for the checksum, high bits then low bits
+fn expand_hrp(hrp: &str) -> Vec<u8> {
+    let mut values: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 31));
+    values
+}
+
+/// Regroup bits, 8 to 5 pads the last group with zeros, 5 to 8 rejects padding
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let mut result = vec![];
+    for value in data {
+        acc = acc << from | *value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push((acc >> bits & ((1 << to) - 1)) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            result.push((acc << (to - bits) & ((1 << to) - 1)) as u8);
+        }
+    } else if bits >= from || acc << (to - bits) & ((1 << to) - 1) != 0 {
+        return None;
+    }
+    Some(result)
+}
+
+/// Segwit address of a witness program, bech32 for version 0 and bech32m above
+pub fn encode_segwit_address(hrp: &str, version: u8, program: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend(convert_bits(program, 8, 5, true).unwrap());
+
+    let constant = if version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let checksum = polymod(&[expand_hrp(hrp), data.clone(), vec![0; 6]].concat()) ^ constant;
+    data.extend((0..6).map(|i| (checksum >> (5 * (5 - i)) & 31) as u8));
+
+    let mut address = format!("{}1", hrp);
+    address.extend(data.iter().map(|value| CHARSET[*value as usize] as char));
+    address
+}
+
+/// Witness version and program of a segwit address with the given human readable part
+pub fn decode_segwit_address(hrp: &str, address: &str) -> Result<(u8, Vec<u8>), Bech32Error> {
+    // printable US-ASCII only, the data part is further limited to the charset
+    if !address.bytes().all(|c| (33..=126).contains(&c)) {
+        return Err(Bech32Error::InvalidCharacter);
+    }
+    if address.len() > MAX_LENGTH {
+        return Err(Bech32Error::InvalidLength);
+    }
+    if address.to_lowercase() != address && address.to_uppercase() != address {
+        return Err(Bech32Error::InvalidCharacter);
+    }
+    let address = address.to_lowercase();
+    let (prefix, data) = address
+        .rsplit_once('1')
+        .ok_or(Bech32Error::InvalidCharacter)?;
+    if prefix != hrp {
+        return Err(Bech32Error::UnexpectedHrp);
+    }
+    let data = data
+        .bytes()
+        .map(|c| CHARSET.iter().position(|d| *d == c).map(|i| i as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or(Bech32Error::InvalidCharacter)?;
+    if data.len() < 7 {
+        return Err(Bech32Error::InvalidChecksum);
+    }
+
+    let version = data[0];
+    let constant = if version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    if polymod(&[expand_hrp(hrp), data.clone()].concat()) != constant {
+        return Err(Bech32Error::InvalidChecksum);
+    }
+
+    let program =
+        convert_bits(&data[1..data.len() - 6], 5, 8, false).ok_or(Bech32Error::InvalidProgram)?;
+    if version > 16
+        || !(2..=40).contains(&program.len())
+        || (version == 0 && program.len() != 20 && program.len() != 32)
+    {
+        return Err(Bech32Error::InvalidProgram);
+    }
+    Ok((version, program))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bech32::{decode_segwit_address, encode_segwit_address, Bech32Error};
+
+    #[test]
+    fn segwit_address_vectors() {
+        // see: https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki#test-vectors-for-v0-v16-native-segregated-witness-addresses
+        let valid = [
+            (
+                "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+                "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            (
+                "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+                "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            ("BC1SW50QGDZ25J", "6002751e"),
+            (
+                "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+                "5210751e76e8199196d454941c45d1b3a323",
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            ),
+        ];
+        for (address, script) in valid {
+            let script = hex::decode(script).unwrap();
+            // the script is OP_n then the program length, OP_n is 0x50 + n from 1 up
+            let version = if script[0] == 0 { 0 } else { script[0] - 0x50 };
+            let program = &script[2..];
+            assert_eq!(
+                decode_segwit_address("bc", address),
+                Ok((version, program.to_vec()))
+            );
+            assert_eq!(
+                encode_segwit_address("bc", version, program),
+                address.to_lowercase()
+            );
+        }
+
+        let invalid = [
+            // version 1 with a bech32 checksum, version 0 with a bech32m one
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+                Bech32Error::InvalidChecksum,
+            ),
+            (
+                "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+                Bech32Error::InvalidChecksum,
+            ),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+                Bech32Error::InvalidChecksum,
+            ),
+            (
+                "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
+                Bech32Error::UnexpectedHrp,
+            ),
+            (
+                "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
+                Bech32Error::InvalidCharacter,
+            ),
+            (
+                "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R",
+                Bech32Error::InvalidProgram,
+            ),
+            ("bc1pw5dgrnzv", Bech32Error::InvalidProgram),
+            (
+                "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+                Bech32Error::InvalidProgram,
+            ),
+            (
+                "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+                Bech32Error::UnexpectedHrp,
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+                Bech32Error::InvalidProgram,
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf",
+                Bech32Error::InvalidProgram,
+            ),
+            (
+                "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
+                Bech32Error::InvalidCharacter,
+            ),
+            // see: https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki#test-vectors
+            // human readable part characters out of range
+            ("\x201nwldj5", Bech32Error::InvalidCharacter),
+            ("\x7f1axkwrx", Bech32Error::InvalidCharacter),
+            ("\u{80}1eym55h", Bech32Error::InvalidCharacter),
+            // overall max length exceeded
+            (
+                "an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+                Bech32Error::InvalidLength,
+            ),
+        ];
+        for (address, error) in invalid {
+            assert_eq!(
+                decode_segwit_address("bc", address),
+                Err(error),
+                "{}",
+                address
+            );
+        }
+    }
+}
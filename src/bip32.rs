@@ -0,0 +1,526 @@
+use crate::base58::{decode_check, encode_check, Base58Error};
+use crate::curve::{Curve, Point};
+use crate::field::Scalar;
+use crate::hash::hash160;
+use crate::ru256::RU256;
+use crate::secp256k1::SECP256K1;
+use crate::secret::{wipe, wipe_bytes, PrivateKey};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+
+// BIP-32 hierarchical deterministic keys, see: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+// a tree of keys grows from one seed: every key carries a 32 byte chain code,
+// and HMAC-SHA512(chain code, key || index) gives a tweak IL added to the key
+// and the chain code IR of the child
+//
+// non-hardened children (index < 2^31) hash the parent's public key, so the
+// child public keys can be derived from the parent public key alone as
+// K + IL * G, while hardened children hash the private key and can't be
+// derived without it
+
+/// Indices from this one up are hardened
+pub const HARDENED: u32 = 1 << 31;
+
+// serialization versions, mainnet xprv and xpub
+const VERSION_PRIVATE: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+const VERSION_PUBLIC: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// Errors deriving, parsing and serializing extended keys
+#[derive(Debug, PartialEq, Eq)]
+pub enum Bip32Error {
+    /// The seed is not between 16 and 64 bytes, or gives an invalid master key
+    InvalidSeed,
+    /// IL >= n or the child key is zero / the identity, proceed with the next index
+    InvalidChild,
+    /// Hardened children can't be derived from a public key
+    HardenedFromPublic,
+    /// A derivation path that doesn't look like m/44'/0'/0'/0/5
+    InvalidPath,
+    /// Not Base58Check, or not 78 bytes once decoded
+    InvalidEncoding(Base58Error),
+    /// A version other than xprv for private keys and xpub for public keys
+    InvalidVersion,
+    /// A depth 0 key with a parent fingerprint or child number
+    InvalidDepth,
+    /// A private key outside 1..n-1 or a public key not on the curve
+    InvalidKey,
+}
+
+impl From<Base58Error> for Bip32Error {
+    fn from(error: Base58Error) -> Self {
+        Bip32Error::InvalidEncoding(error)
+    }
+}
+
+/// Path of child indices from the master key, like m/44'/0'/0'/0/5
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DerivationPath(pub Vec<u32>);
+
+impl FromStr for DerivationPath {
+    type Err = Bip32Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(Bip32Error::InvalidPath);
+        }
+        parts
+            .map(|part| {
+                // hardened indices are marked with ' or h
+                let (number, hardened) = match part.strip_suffix(['\'', 'h', 'H']) {
+                    Some(number) => (number, true),
+                    None => (part, false),
+                };
+                if number.is_empty() || !number.bytes().all(|c| c.is_ascii_digit()) {
+                    return Err(Bip32Error::InvalidPath);
+                }
+                match number.parse::<u32>() {
+                    Ok(index) if index < HARDENED => {
+                        Ok(if hardened { index + HARDENED } else { index })
+                    }
+                    _ => Err(Bip32Error::InvalidPath),
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if *index >= HARDENED {
+                write!(f, "/{}'", index - HARDENED)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Extended private key, a private key with its chain code and place in the tree
+pub struct ExtendedPrivateKey {
+    pub depth: u8,
+    pub parent_fingerprint: [u8; 4],
+    pub child_number: u32,
+    chain_code: [u8; 32],
+    private_key: PrivateKey,
+}
+
+/// Extended public key, a public key with its chain code and place in the tree
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtendedPublicKey {
+    pub depth: u8,
+    pub parent_fingerprint: [u8; 4],
+    pub child_number: u32,
+    pub chain_code: [u8; 32],
+    pub public_key: Point,
+}
+
+/// HMAC-SHA512 of the parts under the key
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
+    for part in data {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// IL as a scalar, or None if it is not below n
+fn master_key(i: &[u8; 64]) -> Option<Scalar<SECP256K1>> {
+    let mut value = RU256::from_bytes(&i[..32]);
+    let tweak = (value < SECP256K1::n()).then(|| Scalar::reduce(&value));
+    wipe(&mut value);
+    tweak
+}
+
+/// The 4 byte identifier of a key, the first bytes of hash160 of the compressed public key
+fn fingerprint(public_key: &Point) -> [u8; 4] {
+    hash160(&public_key.to_compressed())[..4]
+        .try_into()
+        .unwrap()
+}
+
+/// The 78 byte serialization shared by xprv and xpub
+fn serialize(
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: &[u8; 4],
+    child_number: u32,
+    chain_code: &[u8; 32],
+    key: &[u8],
+) -> String {
+    let mut data = [
+        &version[..],
+        &[depth],
+        parent_fingerprint,
+        &child_number.to_be_bytes(),
+        chain_code,
+        key,
+    ]
+    .concat();
+    let string = encode_check(&data);
+    wipe_bytes(&mut data);
+    string
+}
+
+/// Fields of a 78 byte serialization, the key still encoded
+struct Fields {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: [u8; 32],
+    key: Vec<u8>,
+}
+
+/// Fields of a 78 byte serialization with the given version
+fn deserialize(s: &str, version: [u8; 4]) -> Result<Fields, Bip32Error> {
+    let mut data = decode_check(s)?;
+    if data.len() != 78 {
+        // any other length is as unreadable as a bad checksum
+        return Err(Bip32Error::InvalidEncoding(Base58Error::InvalidChecksum));
+    }
+    if data[..4] != version {
+        return Err(Bip32Error::InvalidVersion);
+    }
+    let depth = data[4];
+    let parent_fingerprint: [u8; 4] = data[5..9].try_into().unwrap();
+    let child_number = u32::from_be_bytes(data[9..13].try_into().unwrap());
+    if depth == 0 && (parent_fingerprint != [0; 4] || child_number != 0) {
+        return Err(Bip32Error::InvalidDepth);
+    }
+    let chain_code = data[13..45].try_into().unwrap();
+    let key = data[45..].to_vec();
+    wipe_bytes(&mut data);
+    Ok(Fields {
+        depth,
+        parent_fingerprint,
+        child_number,
+        chain_code,
+        key,
+    })
+}
+
+impl ExtendedPrivateKey {
+    /// Master key of a 16 to 64 byte seed, HMAC-SHA512 under the key "Bitcoin seed"
+    pub fn from_seed(seed: &[u8]) -> Result<Self, Bip32Error> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(Bip32Error::InvalidSeed);
+        }
+        let mut i = hmac_sha512(b"Bitcoin seed", &[seed]);
+        let master = match master_key(&i) {
+            Some(mut key) if !key.is_zero() => {
+                let master = Self {
+                    depth: 0,
+                    parent_fingerprint: [0; 4],
+                    child_number: 0,
+                    chain_code: i[32..].try_into().unwrap(),
+                    private_key: PrivateKey::new(key.value()),
+                };
+                key.wipe();
+                Ok(master)
+            }
+            _ => Err(Bip32Error::InvalidSeed),
+        };
+        wipe_bytes(&mut i);
+        master
+    }
+
+    /// The private key
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.private_key
+    }
+
+    /// The extended public key of this key, with the same place in the tree
+    pub fn public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            public_key: SECP256K1::public_key(self.private_key.expose_secret()),
+        }
+    }
+
+    /// Identifier of this key, the parent fingerprint of its children
+    pub fn fingerprint(&self) -> [u8; 4] {
+        self.public_key().fingerprint()
+    }
+
+    /// Child key at the index, hardened from HARDENED up
+    pub fn derive_child(&self, index: u32) -> Result<Self, Bip32Error> {
+        if self.depth == u8::MAX {
+            return Err(Bip32Error::InvalidChild);
+        }
+        let public_key = SECP256K1::public_key(self.private_key.expose_secret());
+        let mut i = if index >= HARDENED {
+            let mut key = self.private_key.expose_secret().to_bytes();
+            let i = hmac_sha512(&self.chain_code, &[&[0], &key, &index.to_be_bytes()]);
+            wipe_bytes(&mut key);
+            i
+        } else {
+            hmac_sha512(
+                &self.chain_code,
+                &[&public_key.to_compressed(), &index.to_be_bytes()],
+            )
+        };
+
+        // IL >= n and a zero child key are both invalid children
+        let child = self
+            .private_key
+            .tweak_add(&i[..32])
+            .map(|private_key| Self {
+                depth: self.depth + 1,
+                parent_fingerprint: fingerprint(&public_key),
+                child_number: index,
+                chain_code: i[32..].try_into().unwrap(),
+                private_key,
+            })
+            .map_err(|_| Bip32Error::InvalidChild);
+        wipe_bytes(&mut i);
+        child
+    }
+
+    /// Descendant key at the path, taken from this key as the root
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Bip32Error> {
+        // a copy for the empty path, PrivateKey is deliberately not Clone
+        let mut key = Self {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            private_key: PrivateKey::new(self.private_key.expose_secret().value()),
+        };
+        for index in &path.0 {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    /// Base58Check xprv serialization
+    /// the result holds the private key and is not wiped, handle it with care
+    pub fn to_base58(&self) -> String {
+        let mut key = self.private_key.expose_secret().to_bytes();
+        let string = serialize(
+            VERSION_PRIVATE,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &[&[0], &key[..]].concat(),
+        );
+        wipe_bytes(&mut key);
+        string
+    }
+}
+
+impl FromStr for ExtendedPrivateKey {
+    type Err = Bip32Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Fields {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            mut key,
+        } = deserialize(s, VERSION_PRIVATE)?;
+        // 0x00 followed by a key in 1..n-1
+        let private_key = PrivateKey::from_bytes(&key[1..]);
+        let valid = key[0] == 0;
+        wipe_bytes(&mut key);
+        let private_key = match private_key {
+            Ok(private_key) if valid => private_key,
+            _ => return Err(Bip32Error::InvalidKey),
+        };
+        Ok(Self {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            private_key,
+        })
+    }
+}
+
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExtendedPrivateKey([REDACTED])")
+    }
+}
+
+impl Drop for ExtendedPrivateKey {
+    fn drop(&mut self) {
+        // the private key wipes itself, but the chain code is just as secret
+        // for non-hardened children, see the BIP's security section
+        wipe_bytes(&mut self.chain_code);
+    }
+}
+
+impl ExtendedPublicKey {
+    /// Identifier of this key, the parent fingerprint of its children
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.public_key)
+    }
+
+    /// Child public key at a non-hardened index, parent key + IL * G
+    pub fn derive_child(&self, index: u32) -> Result<Self, Bip32Error> {
+        if index >= HARDENED {
+            return Err(Bip32Error::HardenedFromPublic);
+        }
+        if self.depth == u8::MAX {
+            return Err(Bip32Error::InvalidChild);
+        }
+        let i = hmac_sha512(
+            &self.chain_code,
+            &[&self.public_key.to_compressed(), &index.to_be_bytes()],
+        );
+        let public_key = self
+            .public_key
+            .tweak_add(&i[..32])
+            .map_err(|_| Bip32Error::InvalidChild)?;
+        Ok(Self {
+            depth: self.depth + 1,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code: i[32..].try_into().unwrap(),
+            public_key,
+        })
+    }
+
+    /// Descendant public key at the path, which must not have hardened steps
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Bip32Error> {
+        path.0
+            .iter()
+            .try_fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+}
+
+impl fmt::Display for ExtendedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Base58Check xpub serialization
+        let string = serialize(
+            VERSION_PUBLIC,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &self.public_key.to_compressed(),
+        );
+        write!(f, "{}", string)
+    }
+}
+
+impl FromStr for ExtendedPublicKey {
+    type Err = Bip32Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Fields {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            key,
+        } = deserialize(s, VERSION_PUBLIC)?;
+        let public_key = Point::from_compressed(&key).ok_or(Bip32Error::InvalidKey)?;
+        Ok(Self {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            public_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bip32::{
+        Bip32Error, DerivationPath, ExtendedPrivateKey, ExtendedPublicKey, HARDENED,
+    };
+    use serde_json::Value;
+    use std::str::FromStr;
+
+    fn vectors() -> Value {
+        serde_json::from_str(include_str!("../test_vectors/bip32_vectors.json")).unwrap()
+    }
+
+    #[test]
+    fn bip32_test_vectors() {
+        // vectors 1 to 4
+        for vector in vectors()["valid"].as_array().unwrap() {
+            let seed = hex::decode(vector["seed"].as_str().unwrap()).unwrap();
+            let master = ExtendedPrivateKey::from_seed(&seed).unwrap();
+            let mut parent: Option<ExtendedPublicKey> = None;
+            for chain in vector["chains"].as_array().unwrap() {
+                let path = DerivationPath::from_str(chain["path"].as_str().unwrap()).unwrap();
+                let xprv = chain["ext_prv"].as_str().unwrap();
+                let xpub = chain["ext_pub"].as_str().unwrap();
+
+                let key = master.derive_path(&path).unwrap();
+                assert_eq!(key.to_base58(), xprv);
+                assert_eq!(key.public_key().to_string(), xpub);
+
+                // public derivation agrees wherever the step is not hardened
+                if let (Some(parent), Some(index)) = (&parent, path.0.last()) {
+                    if *index < HARDENED {
+                        assert_eq!(parent.derive_child(*index).unwrap().to_string(), xpub);
+                    } else {
+                        assert_eq!(
+                            parent.derive_child(*index),
+                            Err(Bip32Error::HardenedFromPublic)
+                        );
+                    }
+                }
+
+                // and both serializations read back
+                let parsed = ExtendedPrivateKey::from_str(xprv).unwrap();
+                assert_eq!(parsed.to_base58(), xprv);
+                assert_eq!(parsed.fingerprint(), key.fingerprint());
+                parent = Some(ExtendedPublicKey::from_str(xpub).unwrap());
+                assert_eq!(parent.as_ref().unwrap().to_string(), xpub);
+            }
+        }
+
+        // vector 5, invalid extended keys
+        for vector in vectors()["invalid"].as_array().unwrap() {
+            let key = vector["key"].as_str().unwrap();
+            assert!(
+                ExtendedPrivateKey::from_str(key).is_err()
+                    && ExtendedPublicKey::from_str(key).is_err(),
+                "{}",
+                vector["comment"]
+            );
+        }
+    }
+
+    #[test]
+    fn derivation_paths() {
+        let path = DerivationPath::from_str("m/44'/0'/0'/0/5").unwrap();
+        assert_eq!(path.0, vec![44 + HARDENED, HARDENED, HARDENED, 0, 5]);
+        assert_eq!(path.to_string(), "m/44'/0'/0'/0/5");
+        assert_eq!(
+            DerivationPath::from_str("m/44h/0H").unwrap().0,
+            vec![44 + HARDENED, HARDENED]
+        );
+        assert!(DerivationPath::from_str("m").unwrap().0.is_empty());
+
+        for path in [
+            "",
+            "44/0",
+            "m/",
+            "m//1",
+            "m/-1",
+            "m/+1",
+            "m/2147483648",
+            "m/1''",
+            "m/x",
+        ] {
+            assert_eq!(DerivationPath::from_str(path), Err(Bip32Error::InvalidPath));
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::bip32::{Bip32Error, ExtendedPrivateKey};
+use crate::hash::HashFunction;
+use crate::secret::wipe_bytes;
+use pbkdf2::pbkdf2_hmac;
+use rand::Rng;
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use unicode_normalization::UnicodeNormalization;
+
+// BIP-39 mnemonics, see: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+// 128 to 256 bits of entropy are followed by the first entropy / 32 bits of
+// their SHA-256 as a checksum, and every 11 bits of that pick one of 2048 words,
+// so 12 words carry 128 bits and 24 words carry 256
+//
+// the seed is PBKDF2-HMAC-SHA512 of the sentence, with "mnemonic" and an optional
+// passphrase as the salt, both NFKD normalized, so every passphrase gives a
+// different but equally valid wallet
+
+const ROUNDS: u32 = 2048;
+
+/// The English word list, sorted so words can be looked up by binary search
+fn english() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| include_str!("../wordlists/english.txt").lines().collect())
+}
+
+/// Errors creating and parsing mnemonics
+#[derive(Debug, PartialEq, Eq)]
+pub enum Bip39Error {
+    /// The entropy is not 16, 20, 24, 28 or 32 bytes
+    InvalidEntropyLength,
+    /// The sentence is not 12, 15, 18, 21 or 24 words
+    InvalidWordCount,
+    /// A word that is not in the word list, by position
+    UnknownWord(usize),
+    /// The checksum bits don't match the entropy
+    InvalidChecksum,
+}
+
+/// BIP-39 mnemonic sentence
+/// the entropy is wiped from memory when dropped and never printed
+pub struct Mnemonic {
+    entropy: Vec<u8>,
+}
+
+impl Mnemonic {
+    /// Mnemonic of 16 to 32 bytes of entropy, in steps of 4
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, Bip39Error> {
+        if !(16..=32).contains(&entropy.len()) || !entropy.len().is_multiple_of(4) {
+            return Err(Bip39Error::InvalidEntropyLength);
+        }
+        Ok(Self {
+            entropy: entropy.to_vec(),
+        })
+    }
+
+    /// Fresh random mnemonic of 12, 15, 18, 21 or 24 words
+    pub fn generate(word_count: usize) -> Result<Self, Bip39Error> {
+        if !(12..=24).contains(&word_count) || !word_count.is_multiple_of(3) {
+            return Err(Bip39Error::InvalidWordCount);
+        }
+        let mut entropy = [0u8; 32];
+        rand::thread_rng().fill(&mut entropy);
+        let mnemonic = Self::from_entropy(&entropy[..word_count / 3 * 4]);
+        wipe_bytes(&mut entropy);
+        mnemonic
+    }
+
+    /// The entropy behind the sentence
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    /// Word list indices of the sentence, 11 bits each of entropy || checksum
+    fn indices(&self) -> Vec<usize> {
+        let checksum = HashFunction::Sha256.digest(&self.entropy)[0];
+        let bit = |i: usize| {
+            let byte = self.entropy.get(i / 8).unwrap_or(&checksum);
+            (byte >> (7 - i % 8)) as usize & 1
+        };
+        (0..self.entropy.len() * 8 * 33 / 32 / 11)
+            .map(|word| (0..11).fold(0, |index, i| index << 1 | bit(word * 11 + i)))
+            .collect()
+    }
+
+    /// The sentence, words separated by single spaces
+    /// the result is as secret as the entropy and is not wiped, handle it with care
+    pub fn phrase(&self) -> String {
+        let words = english();
+        self.indices()
+            .into_iter()
+            .map(|index| words[index])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 64 byte seed of the sentence and a passphrase, which may be empty
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        // the English words are already NFKD normalized
+        let mut phrase = self.phrase().into_bytes();
+        let mut salt = format!("mnemonic{}", passphrase)
+            .nfkd()
+            .collect::<String>()
+            .into_bytes();
+        let mut seed = [0u8; 64];
+        pbkdf2_hmac::<Sha512>(&phrase, &salt, ROUNDS, &mut seed);
+        wipe_bytes(&mut phrase);
+        wipe_bytes(&mut salt);
+        seed
+    }
+
+    /// BIP-32 master key of the seed, which is wiped once the key is made
+    pub fn to_master_key(&self, passphrase: &str) -> Result<ExtendedPrivateKey, Bip32Error> {
+        let mut seed = self.to_seed(passphrase);
+        let master = ExtendedPrivateKey::from_seed(&seed);
+        wipe_bytes(&mut seed);
+        master
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = Bip39Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // words separated by any whitespace, including the ideographic space
+        let sentence: String = s.nfkd().collect();
+        let words = sentence.split_whitespace().collect::<Vec<_>>();
+        if !(12..=24).contains(&words.len()) || !words.len().is_multiple_of(3) {
+            return Err(Bip39Error::InvalidWordCount);
+        }
+
+        let mut bits = vec![];
+        for (position, word) in words.iter().enumerate() {
+            let index = english()
+                .binary_search(word)
+                .map_err(|_| Bip39Error::UnknownWord(position))?;
+            bits.extend((0..11).rev().map(|i| (index >> i) as u8 & 1));
+        }
+
+        // the last word holds the checksum bits after the entropy
+        let length = words.len() * 11 * 32 / 33 / 8;
+        let entropy = bits[..length * 8]
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| acc << 1 | bit))
+            .collect::<Vec<u8>>();
+        let checksum = HashFunction::Sha256.digest(&entropy)[0];
+        let valid = bits[length * 8..]
+            .iter()
+            .enumerate()
+            .all(|(i, bit)| (checksum >> (7 - i)) & 1 == *bit);
+        wipe_bytes(&mut bits);
+        drop(words);
+        wipe_bytes(&mut sentence.into_bytes());
+        let mnemonic = Self { entropy };
+        if !valid {
+            return Err(Bip39Error::InvalidChecksum);
+        }
+        Ok(mnemonic)
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mnemonic([REDACTED])")
+    }
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        wipe_bytes(&mut self.entropy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bip39::{english, Bip39Error, Mnemonic};
+    use serde_json::Value;
+    use std::str::FromStr;
+
+    #[test]
+    fn bip39_test_vectors() {
+        // see: https://github.com/trezor/python-mnemonic/blob/master/vectors.json
+        // [entropy, mnemonic, seed, xprv], all with the passphrase "TREZOR"
+        let vectors: Value =
+            serde_json::from_str(include_str!("../test_vectors/bip39_vectors.json")).unwrap();
+        for vector in vectors["english"].as_array().unwrap() {
+            let entropy = hex::decode(vector[0].as_str().unwrap()).unwrap();
+            let phrase = vector[1].as_str().unwrap();
+
+            let mnemonic = Mnemonic::from_entropy(&entropy).unwrap();
+            assert_eq!(mnemonic.phrase(), phrase);
+            assert_eq!(Mnemonic::from_str(phrase).unwrap().entropy(), &entropy[..]);
+            assert_eq!(
+                hex::encode(mnemonic.to_seed("TREZOR")),
+                vector[2].as_str().unwrap()
+            );
+            assert_eq!(
+                mnemonic.to_master_key("TREZOR").unwrap().to_base58(),
+                vector[3].as_str().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn mnemonic_validation() {
+        assert_eq!(english().len(), 2048);
+        assert!(english().windows(2).all(|pair| pair[0] < pair[1]));
+
+        let valid = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert!(Mnemonic::from_str(valid).is_ok());
+        assert!(Mnemonic::from_str(&valid.replace(' ', "  \t")).is_ok());
+        assert_eq!(
+            Mnemonic::from_str(&valid.replace("about", "abandon")).unwrap_err(),
+            Bip39Error::InvalidChecksum
+        );
+        assert_eq!(
+            Mnemonic::from_str(&valid.replace("about", "abut")).unwrap_err(),
+            Bip39Error::UnknownWord(11)
+        );
+        assert_eq!(
+            Mnemonic::from_str(&valid.replace(" about", "")).unwrap_err(),
+            Bip39Error::InvalidWordCount
+        );
+        assert_eq!(
+            Mnemonic::from_entropy(&[0; 15]).unwrap_err(),
+            Bip39Error::InvalidEntropyLength
+        );
+
+        for word_count in [12, 15, 18, 21, 24] {
+            let mnemonic = Mnemonic::generate(word_count).unwrap();
+            let phrase = mnemonic.phrase();
+            assert_eq!(phrase.split(' ').count(), word_count);
+            assert_eq!(
+                Mnemonic::from_str(&phrase).unwrap().entropy(),
+                mnemonic.entropy()
+            );
+        }
+        assert_eq!(
+            Mnemonic::generate(13).unwrap_err(),
+            Bip39Error::InvalidWordCount
+        );
+    }
+}
@@ -0,0 +1,908 @@
+use crate::field::{FieldElement, Scalar};
+use crate::hash::HashFunction;
+use crate::p256::P256;
+use crate::p384::P384;
+use crate::p521::P521;
+use crate::ru256::{batch_inv_mod, Choice};
+use crate::ruint::ModInt;
+use crate::secp256k1::SECP256K1;
+use crate::secret::wipe;
+use std::fmt;
+use std::str::FromStr;
+
+/// Represents a point on an elliptic curve
+#[derive(PartialEq, Clone, Debug)]
+pub struct Point<C: Curve = SECP256K1> {
+    pub(crate) x: FieldElement<C>,
+    pub(crate) y: FieldElement<C>,
+}
+
+impl<C: Curve> Point<C> {
+    /// Build a point from hex strings
+    pub fn from_hex_coordinates(x: &str, y: &str) -> Self {
+        Point {
+            x: FieldElement::reduce(&C::Uint::from_str(x).unwrap()),
+            y: FieldElement::reduce(&C::Uint::from_str(y).unwrap()),
+        }
+    }
+
+    /// Find the point with the given x coordinate and y parity
+    /// returns None if x isn't the coordinate of any point on the curve
+    pub fn from_x(x: &FieldElement<C>, odd: bool) -> Option<Self> {
+        // y^2 = x^3 + ax + b, pick the root with the right parity
+        let a = FieldElement::reduce(&C::a());
+        let b = FieldElement::reduce(&C::b());
+        let y = (x * x * x + &a * x + b).sqrt()?;
+        let y = if y.is_even() == odd { -y } else { y };
+
+        Some(Point { x: x.clone(), y })
+    }
+
+    /// Decode a SEC1 compressed point, 02 or 03 for the parity of y followed by x
+    /// returns None if the encoding is malformed or x is not on the curve
+    pub fn from_compressed(bytes: &[u8]) -> Option<Self> {
+        let length = C::p().bits().div_ceil(8);
+        if bytes.len() != length + 1 || !(bytes[0] == 2 || bytes[0] == 3) {
+            return None;
+        }
+
+        let x = C::Uint::from_bytes(&bytes[1..]);
+        if x >= C::p() {
+            return None;
+        }
+
+        Self::from_x(&FieldElement::reduce(&x), bytes[0] == 3)
+    }
+
+    /// SEC1 compressed encoding of a point, the identity is a single zero byte
+    pub fn to_compressed(&self) -> Vec<u8> {
+        if self.is_zero_point() {
+            return vec![0];
+        }
+
+        let mut bytes = vec![if self.y.is_even() { 2 } else { 3 }];
+        bytes.extend(self.x.to_bytes());
+        bytes
+    }
+
+    /// Decode a SEC1 uncompressed point, 04 followed by x and y
+    /// returns None if the encoding is malformed or the point is not on the curve
+    pub fn from_uncompressed(bytes: &[u8]) -> Option<Self> {
+        let length = C::p().bits().div_ceil(8);
+        if bytes.len() != 2 * length + 1 || bytes[0] != 4 {
+            return None;
+        }
+
+        let x = C::Uint::from_bytes(&bytes[1..length + 1]);
+        let y = C::Uint::from_bytes(&bytes[length + 1..]);
+        if x >= C::p() || y >= C::p() {
+            return None;
+        }
+
+        let point = Point {
+            x: FieldElement::reduce(&x),
+            y: FieldElement::reduce(&y),
+        };
+        // the identity has no uncompressed encoding
+        if point.is_zero_point() || !C::is_on_curve(&point) {
+            return None;
+        }
+        Some(point)
+    }
+
+    /// SEC1 uncompressed encoding of a point, the identity is a single zero byte
+    pub fn to_uncompressed(&self) -> Vec<u8> {
+        if self.is_zero_point() {
+            return vec![0];
+        }
+
+        let mut bytes = vec![4];
+        bytes.extend(self.x.to_bytes());
+        bytes.extend(self.y.to_bytes());
+        bytes
+    }
+
+    /// Return the uncompressed version of a point
+    pub fn to_hex_string(&self) -> String {
+        format!("04{}{}", self.x, self.y)
+    }
+
+    /// Determines if a point is the identity element
+    pub fn is_zero_point(&self) -> bool {
+        self.x.is_zero() && self.y.is_zero()
+    }
+}
+
+/// Represents a curve point in jacobian coordinates
+/// (x, y, z) stands for the affine point (x / z^2, y / z^3)
+/// this lets us add and double points without a division at every step
+#[derive(Clone, Debug)]
+pub struct JacobianPoint<C: Curve = SECP256K1> {
+    pub(crate) x: FieldElement<C>,
+    pub(crate) y: FieldElement<C>,
+    pub(crate) z: FieldElement<C>,
+}
+
+impl<C: Curve> JacobianPoint<C> {
+    /// Determines if a point is the identity element
+    pub(crate) fn is_zero_point(&self) -> bool {
+        self.z.is_zero()
+    }
+}
+
+impl<C: Curve> From<&Point<C>> for JacobianPoint<C> {
+    fn from(point: &Point<C>) -> Self {
+        if point.is_zero_point() {
+            return C::jacobian_zero_point();
+        }
+
+        // z = 1 means the coordinates are already affine
+        JacobianPoint {
+            x: point.x.clone(),
+            y: point.y.clone(),
+            z: FieldElement::one(),
+        }
+    }
+}
+
+/// Short weierstrass curve y^2 = x^3 + ax + b over the prime field p
+/// with a generator g of prime order n and cofactor h
+///
+/// a curve only has to supply its parameters, the point arithmetic
+/// below is shared by every curve
+pub trait Curve: Clone + fmt::Debug + PartialEq + Sized {
+    /// Standard name of the curve
+    const NAME: &'static str;
+
+    /// Object identifier of the curve, in dotted form
+    const OID: &'static str;
+
+    /// Hash ECDSA signatures on the curve use by default
+    /// the one matching the curve's security level
+    const HASH: HashFunction;
+
+    /// Integer type wide enough for the curve's numbers
+    type Uint: ModInt;
+
+    /// Montgomery constants of the prime p, worked out at compile time
+    const P_MONTGOMERY: <Self::Uint as ModInt>::Constants;
+
+    /// Prime value
+    fn p() -> Self::Uint {
+        Self::Uint::modulus(&Self::P_MONTGOMERY)
+    }
+
+    /// Curve coefficient a
+    fn a() -> Self::Uint;
+
+    /// Curve coefficient b
+    fn b() -> Self::Uint;
+
+    /// Generator point
+    fn g() -> Point<Self>;
+
+    /// Montgomery constants of the group order n
+    const N_MONTGOMERY: <Self::Uint as ModInt>::Constants;
+
+    /// Group order
+    fn n() -> Self::Uint {
+        Self::Uint::modulus(&Self::N_MONTGOMERY)
+    }
+
+    /// Cofactor
+    fn h() -> Self::Uint;
+
+    /// Multiply two reduced base field elements mod p
+    /// curves with a special form prime override this with a faster reduction
+    fn field_mul(a: &Self::Uint, b: &Self::Uint) -> Self::Uint {
+        a.mul_mod(b, &Self::p())
+    }
+
+    /// Zero point
+    fn zero_point() -> Point<Self> {
+        Point {
+            x: FieldElement::zero(),
+            y: FieldElement::zero(),
+        }
+    }
+
+    /// Checks that a point satisfies the curve equation
+    fn is_on_curve(point: &Point<Self>) -> bool {
+        // the identity is part of the group, even if it isn't on the curve itself
+        if point.is_zero_point() {
+            return true;
+        }
+
+        // y^2 = x^3 + ax + b
+        let a = FieldElement::reduce(&Self::a());
+        let b = FieldElement::reduce(&Self::b());
+        let lhs = &point.y * &point.y;
+        let rhs = &point.x * &point.x * &point.x + &a * &point.x + b;
+
+        lhs == rhs
+    }
+
+    /// Add two different curve points
+    fn add_points(p1: &Point<Self>, p2: &Point<Self>) -> Point<Self> {
+        // two points P = (xp, yp) and Q = (xq, yq)
+        // lambda = (yq - yp) / (xq - xp)
+        // x3 = lambda^2 - xp - xq
+        // y3 = lambda(xp - x3) - yp
+
+        // we need to make sure the points are not the same,
+        // if the same when calculating lambda, we will have
+        // a division by zero error
+        assert!(p1 != p2);
+
+        // if any of the point is the identity, we return the
+        // other point
+        // as P + O = P
+        if p1.is_zero_point() {
+            return p2.clone();
+        }
+        if p2.is_zero_point() {
+            return p1.clone();
+        }
+
+        // calculate slope
+        let lambda = (&p1.y - &p2.y) / (&p1.x - &p2.x);
+
+        // calculate point values
+        let x3 = &lambda * &lambda - &p1.x - &p2.x;
+        let y3 = (&p1.x - &x3) * &lambda - &p1.y;
+
+        Point { x: x3, y: y3 }
+    }
+
+    /// Double a curve point
+    fn double_point(p1: &Point<Self>) -> Point<Self> {
+        // only one point (x, y)
+        // lambda = (3x^2 + a) / 2y
+        // x3 = lambda^2 - x - x
+        // y3 = lambda(xp - x) - y
+
+        // doubling the identity point, returns the identity point
+        // O + O = O
+        if p1.is_zero_point() {
+            return Self::zero_point();
+        };
+
+        // if only y is zero, we are at the non-symmetrical point
+        // on the curve, drawing a tangent line from this point will
+        // lead to infinity (hence we return the identity point)
+        if p1.y.is_zero() {
+            return Self::zero_point();
+        };
+
+        // formula includes constant 2 and 3
+        // to simply formula description, we define
+        // them as here first
+        let const_2 = FieldElement::reduce(&Self::Uint::from_u64(2));
+        let const_3 = FieldElement::reduce(&Self::Uint::from_u64(3));
+        let a = FieldElement::reduce(&Self::a());
+
+        // calculate the slope
+        let three_x_square_plus_a = &p1.x * &p1.x * &const_3 + &a;
+        let two_y = &p1.y * &const_2;
+        let lambda = three_x_square_plus_a / two_y;
+
+        // calculate point values
+        let x3 = &lambda * &lambda - &p1.x - &p1.x;
+        let y3 = (&p1.x - &x3) * &lambda - &p1.y;
+
+        Point { x: x3, y: y3 }
+    }
+
+    /// Perform scalar multiplication on a curve point
+    fn scalar_multiplication(scalar: &Scalar<Self>, curve_point: &Point<Self>) -> Point<Self> {
+        // this can be seen as repeated addition, so the
+        // double-add algorithm will be useful here
+        // see: mul_mod function in ru256.rs
+        // the loop runs in jacobian coordinates, so the only
+        // division is the one converting the result back
+
+        // the result starts at the identity
+        let mut result = Self::jacobian_zero_point();
+        // base point is the given point
+        let mut adder = JacobianPoint::from(curve_point);
+
+        let scalar = scalar.value();
+        let seq_bit_size = scalar.bits();
+        for i in 0..seq_bit_size {
+            if scalar.bit(i) {
+                result = Self::add_jacobian_points(&result, &adder);
+            }
+            // we double adder after every step
+            adder = Self::double_jacobian_point(&adder);
+        }
+
+        Self::to_affine(&result)
+    }
+
+    /// Scalar multiplication in constant time, for secret scalars
+    /// scalar_multiplication branches on every bit of the scalar, which leaks
+    /// it through timing, so keys and nonces go through a Montgomery ladder
+    /// see: ladder
+    fn ct_scalar_multiplication(scalar: &Scalar<Self>, curve_point: &Point<Self>) -> Point<Self> {
+        // whether the point is the identity is public
+        if curve_point.is_zero_point() {
+            return Self::zero_point();
+        }
+        ladder(scalar, curve_point)
+    }
+
+    /// Derive the public key from a given private key
+    fn public_key(private_key: &Scalar<Self>) -> Point<Self> {
+        // We generate the public key by doing a scalar
+        // multiplication of the generator point by the
+        // private key
+        // i.e public_key = nG where n = private_key
+        // the private key is secret, so in constant time
+        Self::ct_scalar_multiplication(private_key, &Self::g())
+    }
+
+    /// Zero point in jacobian coordinates
+    fn jacobian_zero_point() -> JacobianPoint<Self> {
+        // any point with z = 0 is the identity
+        JacobianPoint {
+            x: FieldElement::one(),
+            y: FieldElement::one(),
+            z: FieldElement::zero(),
+        }
+    }
+
+    /// Add two curve points in jacobian coordinates
+    fn add_jacobian_points(
+        p1: &JacobianPoint<Self>,
+        p2: &JacobianPoint<Self>,
+    ) -> JacobianPoint<Self> {
+        // see: https://hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html#addition-add-1998-cmo-2
+        // u1 = x1 * z2^2, u2 = x2 * z1^2
+        // s1 = y1 * z2^3, s2 = y2 * z1^3
+        // h = u2 - u1, r = s2 - s1
+        // x3 = r^2 - h^3 - 2 * u1 * h^2
+        // y3 = r * (u1 * h^2 - x3) - s1 * h^3
+        // z3 = z1 * z2 * h
+        // addition doesn't depend on a, only doubling does
+
+        // P + O = P
+        if p1.is_zero_point() {
+            return p2.clone();
+        }
+        if p2.is_zero_point() {
+            return p1.clone();
+        }
+
+        let z1_square = &p1.z * &p1.z;
+        let z2_square = &p2.z * &p2.z;
+        let u1 = &p1.x * &z2_square;
+        let u2 = &p2.x * &z1_square;
+        let s1 = &p1.y * &z2_square * &p2.z;
+        let s2 = &p2.y * &z1_square * &p1.z;
+
+        let h = &u2 - &u1;
+        let r = &s2 - &s1;
+
+        // unlike the affine version, we don't need the points to be different
+        // same x means either the same point or its negation
+        if h.is_zero() {
+            if r.is_zero() {
+                return Self::double_jacobian_point(p1);
+            }
+            // P + (-P) = O
+            return Self::jacobian_zero_point();
+        }
+
+        let h_square = &h * &h;
+        let h_cube = &h_square * &h;
+        let u1_h_square = &u1 * &h_square;
+
+        let x3 = &r * &r - &h_cube - &u1_h_square - &u1_h_square;
+        let y3 = (&u1_h_square - &x3) * &r - &s1 * &h_cube;
+        let z3 = &p1.z * &p2.z * &h;
+
+        JacobianPoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// Double a curve point in jacobian coordinates
+    fn double_jacobian_point(p1: &JacobianPoint<Self>) -> JacobianPoint<Self> {
+        // see: https://hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html#doubling-dbl-2007-bl
+        // xx = x^2, yy = y^2, yyyy = yy^2, zz = z^2
+        // s = 2 * ((x + yy)^2 - xx - yyyy)
+        // m = 3 * xx + a * zz^2
+        // x3 = m^2 - 2 * s
+        // y3 = m * (s - x3) - 8 * yyyy
+        // z3 = 2 * y * z
+
+        // O + O = O, and the tangent at y = 0 is vertical
+        if p1.is_zero_point() || p1.y.is_zero() {
+            return Self::jacobian_zero_point();
+        }
+
+        let const_2 = FieldElement::reduce(&Self::Uint::from_u64(2));
+        let const_3 = FieldElement::reduce(&Self::Uint::from_u64(3));
+        let const_8 = FieldElement::reduce(&Self::Uint::from_u64(8));
+        let a = FieldElement::reduce(&Self::a());
+
+        let xx = &p1.x * &p1.x;
+        let yy = &p1.y * &p1.y;
+        let yyyy = &yy * &yy;
+        let zz = &p1.z * &p1.z;
+        let x_plus_yy = &p1.x + &yy;
+        let s = (&x_plus_yy * &x_plus_yy - &xx - &yyyy) * &const_2;
+        let m = &xx * &const_3 + &a * &zz * &zz;
+
+        let x3 = &m * &m - &s - &s;
+        let y3 = (&s - &x3) * &m - &yyyy * &const_8;
+        let z3 = &p1.y * &p1.z * &const_2;
+
+        JacobianPoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// Convert a jacobian point back to affine coordinates
+    fn to_affine(point: &JacobianPoint<Self>) -> Point<Self> {
+        if point.is_zero_point() {
+            return Self::zero_point();
+        }
+
+        Self::scale_to_affine(point, &point.z.invert())
+    }
+
+    /// Convert many jacobian points to affine coordinates at the cost of a single division
+    fn batch_normalize(points: &[JacobianPoint<Self>]) -> Vec<Point<Self>> {
+        // every conversion needs 1 / z, so rather than dividing per point
+        // we invert all the z values together (see: batch_inv_mod in ru256.rs)
+        // the identity has z = 0, batch_inv_mod leaves it as zero and we map
+        // it back to the zero point below
+        let mut z_invs: Vec<Self::Uint> =
+            points.iter().map(|point| point.z.value().clone()).collect();
+        batch_inv_mod(&mut z_invs, &Self::p());
+
+        points
+            .iter()
+            .zip(z_invs.iter())
+            .map(|(point, z_inv)| {
+                if point.is_zero_point() {
+                    Self::zero_point()
+                } else {
+                    Self::scale_to_affine(point, &FieldElement::reduce(z_inv))
+                }
+            })
+            .collect()
+    }
+
+    /// Map (x, y, z) to (x / z^2, y / z^3) given 1 / z
+    fn scale_to_affine(point: &JacobianPoint<Self>, z_inv: &FieldElement<Self>) -> Point<Self> {
+        let z_inv_square = z_inv * z_inv;
+        let z_inv_cube = &z_inv_square * z_inv;
+
+        Point {
+            x: &point.x * &z_inv_square,
+            y: &point.y * &z_inv_cube,
+        }
+    }
+}
+
+// constant time scalar multiplication: every bit of the scalar costs
+// one addition and one doubling whatever its value, and the bit only decides
+// which of two points gets swapped in, through a mask
+
+/// A point in jacobian coordinates whose coordinates are plain numbers, so the
+/// constant time operations can be used on them
+struct LadderPoint<C: Curve> {
+    x: C::Uint,
+    y: C::Uint,
+    z: C::Uint,
+}
+
+impl<C: Curve> LadderPoint<C> {
+    fn from_point(point: &Point<C>) -> Self {
+        LadderPoint {
+            x: point.x.value().clone(),
+            y: point.y.value().clone(),
+            z: C::Uint::one(),
+        }
+    }
+
+    /// Swap two points if choice is set
+    fn conditional_swap(a: &mut Self, b: &mut Self, choice: Choice) {
+        C::Uint::conditional_swap(&mut a.x, &mut b.x, choice);
+        C::Uint::conditional_swap(&mut a.y, &mut b.y, choice);
+        C::Uint::conditional_swap(&mut a.z, &mut b.z, choice);
+    }
+
+    /// Pick b if choice is set, otherwise a
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        LadderPoint {
+            x: C::Uint::conditional_select(&a.x, &b.x, choice),
+            y: C::Uint::conditional_select(&a.y, &b.y, choice),
+            z: C::Uint::conditional_select(&a.z, &b.z, choice),
+        }
+    }
+
+    fn is_zero_point(&self) -> Choice {
+        self.z.ct_eq(&C::Uint::zero())
+    }
+}
+
+impl<C: Curve> Drop for LadderPoint<C> {
+    fn drop(&mut self) {
+        // the intermediate points reveal the key's bits
+        wipe(&mut self.x);
+        wipe(&mut self.y);
+        wipe(&mut self.z);
+    }
+}
+
+/// Constant time addition, same formula as Curve::add_jacobian_points
+fn add<C: Curve>(p1: &LadderPoint<C>, p2: &LadderPoint<C>) -> LadderPoint<C> {
+    let p = C::p();
+    let mul = |a: &C::Uint, b: &C::Uint| a.ct_mul_mod_with(b, &C::P_MONTGOMERY);
+    let sub = |a: &C::Uint, b: &C::Uint| a.ct_sub_mod(b, &p);
+
+    let z1_square = mul(&p1.z, &p1.z);
+    let z2_square = mul(&p2.z, &p2.z);
+    let u1 = mul(&p1.x, &z2_square);
+    let u2 = mul(&p2.x, &z1_square);
+    let s1 = mul(&mul(&p1.y, &z2_square), &p2.z);
+    let s2 = mul(&mul(&p2.y, &z1_square), &p1.z);
+    let h = sub(&u2, &u1);
+    let r = sub(&s2, &s1);
+
+    let h_square = mul(&h, &h);
+    let h_cube = mul(&h_square, &h);
+    let u1_h_square = mul(&u1, &h_square);
+    let x3 = sub(
+        &sub(&sub(&mul(&r, &r), &h_cube), &u1_h_square),
+        &u1_h_square,
+    );
+    let y3 = sub(&mul(&sub(&u1_h_square, &x3), &r), &mul(&s1, &h_cube));
+    let z3 = mul(&mul(&p1.z, &p2.z), &h);
+    let sum = LadderPoint {
+        x: x3,
+        y: y3,
+        z: z3,
+    };
+
+    // P + (-P) comes out as the identity with h = 0, but the identity as
+    // an input has to be selected around, the formula would give the identity
+    // the ladder never adds a point to itself, so there is no doubling case
+    let sum = LadderPoint::conditional_select(&sum, p2, p1.is_zero_point());
+    LadderPoint::conditional_select(&sum, p1, p2.is_zero_point())
+}
+
+/// Constant time doubling, same formula as Curve::double_jacobian_point
+/// the identity doubles to z = 0 by itself, and a prime order curve
+/// has no points with y = 0
+fn double<C: Curve>(p1: &LadderPoint<C>) -> LadderPoint<C> {
+    let p = C::p();
+    let mul = |a: &C::Uint, b: &C::Uint| a.ct_mul_mod_with(b, &C::P_MONTGOMERY);
+    let add = |a: &C::Uint, b: &C::Uint| a.ct_add_mod(b, &p);
+    let sub = |a: &C::Uint, b: &C::Uint| a.ct_sub_mod(b, &p);
+
+    let xx = mul(&p1.x, &p1.x);
+    let yy = mul(&p1.y, &p1.y);
+    let yyyy = mul(&yy, &yy);
+    let zz = mul(&p1.z, &p1.z);
+    let x_plus_yy = add(&p1.x, &yy);
+    let s = sub(&sub(&mul(&x_plus_yy, &x_plus_yy), &xx), &yyyy);
+    let s = add(&s, &s);
+    let m = add(&add(&add(&xx, &xx), &xx), &mul(&C::a(), &mul(&zz, &zz)));
+
+    let x3 = sub(&sub(&mul(&m, &m), &s), &s);
+    let yyyy_2 = add(&yyyy, &yyyy);
+    let yyyy_4 = add(&yyyy_2, &yyyy_2);
+    let y3 = sub(&mul(&sub(&s, &x3), &m), &add(&yyyy_4, &yyyy_4));
+    let yz = mul(&p1.y, &p1.z);
+    let z3 = add(&yz, &yz);
+
+    LadderPoint {
+        x: x3,
+        y: y3,
+        z: z3,
+    }
+}
+
+/// Limbs of a + b, one limb longer than the inputs
+fn add_wide(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut sum = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0u128;
+    for (a, b) in a.iter().zip(b.iter()) {
+        let limb = *a as u128 + *b as u128 + carry;
+        sum.push(limb as u64);
+        carry = limb >> 64;
+    }
+    sum.push(carry as u64);
+    sum
+}
+
+/// Montgomery ladder, k * P in constant time
+fn ladder<C: Curve>(scalar: &Scalar<C>, point: &Point<C>) -> Point<C> {
+    // the number of ladder steps follows the scalar's length, so it is made
+    // to always have the same length: with m the bit length of n, either
+    // k + n or k + 2n has bit m set and none above, and both are k mod n
+    let m = C::n().bits();
+    let n = C::n();
+    let mut n_wide = n.limbs().to_vec();
+    n_wide.push(0);
+    let mut k_plus_n = add_wide(scalar.value().limbs(), n.limbs());
+    let mut k_plus_2n = add_wide(&k_plus_n, &n_wide);
+    let bit = |limbs: &[u64], i: usize| (limbs[i / 64] >> (i % 64)) & 1;
+    let mask = Choice::from_bit(bit(&k_plus_n, m)).mask();
+    let mut k: Vec<u64> = k_plus_n
+        .iter()
+        .zip(k_plus_2n.iter())
+        .map(|(a, b)| (a & mask) | (b & !mask))
+        .collect();
+
+    // r0 = j * P and r1 = (j + 1) * P for j the bits of k read so far
+    // with the top bit set, it starts at j = 1
+    let mut r0 = LadderPoint::from_point(point);
+    let mut r1 = double(&r0);
+    for i in (0..m).rev() {
+        // set bit: r0 = r0 + r1, r1 = 2 * r1
+        // clear bit: r1 = r0 + r1, r0 = 2 * r0
+        // swapping before and after makes both cases the second one
+        let choice = Choice::from_bit(bit(&k, i));
+        LadderPoint::conditional_swap(&mut r0, &mut r1, choice);
+        r1 = add(&r0, &r1);
+        r0 = double(&r0);
+        LadderPoint::conditional_swap(&mut r0, &mut r1, choice);
+    }
+    for limb in k
+        .iter_mut()
+        .chain(k_plus_n.iter_mut())
+        .chain(k_plus_2n.iter_mut())
+    {
+        unsafe { std::ptr::write_volatile(limb, 0) };
+    }
+
+    if r0.is_zero_point().is_true() {
+        return C::zero_point();
+    }
+
+    // back to affine, the inversion goes through the constant time division
+    let p = &C::P_MONTGOMERY;
+    let z_inv = C::Uint::one().ct_div_mod_with(&r0.z, p);
+    let z_inv_square = z_inv.ct_mul_mod_with(&z_inv, p);
+    let z_inv_cube = z_inv_square.ct_mul_mod_with(&z_inv, p);
+    Point {
+        x: FieldElement::reduce(&r0.x.ct_mul_mod_with(&z_inv_square, p)),
+        y: FieldElement::reduce(&r0.y.ct_mul_mod_with(&z_inv_cube, p)),
+    }
+}
+
+/// Double a curve point in jacobian coordinates on a curve with a = -3
+/// the NIST curves all pick a = -3 for this, they override
+/// Curve::double_jacobian_point with it
+pub(crate) fn double_jacobian_point_a_minus_3<C: Curve>(p1: &JacobianPoint<C>) -> JacobianPoint<C> {
+    // see: https://hyperelliptic.org/EFD/g1p/auto-shortw-jacobian-3.html#doubling-dbl-2001-b
+    // with a = -3 the 3 * xx + a * zz^2 term of the generic formula
+    // factors as 3 * (x - zz) * (x + zz), saving two multiplications
+    // delta = z^2, gamma = y^2, beta = x * gamma
+    // alpha = 3 * (x - delta) * (x + delta)
+    // x3 = alpha^2 - 8 * beta
+    // z3 = (y + z)^2 - gamma - delta
+    // y3 = alpha * (4 * beta - x3) - 8 * gamma^2
+    debug_assert!(
+        FieldElement::<C>::reduce(&C::a()) == -FieldElement::reduce(&C::Uint::from_u64(3))
+    );
+
+    // O + O = O, and the tangent at y = 0 is vertical
+    if p1.is_zero_point() || p1.y.is_zero() {
+        return C::jacobian_zero_point();
+    }
+
+    let const_3 = FieldElement::reduce(&C::Uint::from_u64(3));
+    let const_4 = FieldElement::reduce(&C::Uint::from_u64(4));
+    let const_8 = FieldElement::reduce(&C::Uint::from_u64(8));
+
+    let delta = &p1.z * &p1.z;
+    let gamma = &p1.y * &p1.y;
+    let beta = &p1.x * &gamma;
+    let alpha = (&p1.x - &delta) * (&p1.x + &delta) * &const_3;
+
+    let x3 = &alpha * &alpha - &beta * &const_8;
+    let y_plus_z = &p1.y + &p1.z;
+    let z3 = &y_plus_z * &y_plus_z - &gamma - &delta;
+    let y3 = (&beta * &const_4 - &x3) * &alpha - &gamma * &gamma * &const_8;
+
+    JacobianPoint {
+        x: x3,
+        y: y3,
+        z: z3,
+    }
+}
+
+/// Registry of the curves implemented by the crate
+/// lets a curve named in a file, a key or a config be found at runtime
+/// adding a curve means implementing Curve for it and listing it here
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveId {
+    Secp256k1,
+    P256,
+    P384,
+    P521,
+}
+
+impl CurveId {
+    /// Every registered curve
+    pub const ALL: &'static [CurveId] = &[
+        CurveId::Secp256k1,
+        CurveId::P256,
+        CurveId::P384,
+        CurveId::P521,
+    ];
+
+    /// Standard name of the curve
+    pub fn name(&self) -> &'static str {
+        match self {
+            CurveId::Secp256k1 => SECP256K1::NAME,
+            CurveId::P256 => P256::NAME,
+            CurveId::P384 => P384::NAME,
+            CurveId::P521 => P521::NAME,
+        }
+    }
+
+    /// Other names the curve goes by
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            CurveId::Secp256k1 => &[],
+            CurveId::P256 => &["P-256", "prime256v1"],
+            CurveId::P384 => &["P-384"],
+            CurveId::P521 => &["P-521"],
+        }
+    }
+
+    /// Object identifier of the curve, in dotted form
+    pub fn oid(&self) -> &'static str {
+        match self {
+            CurveId::Secp256k1 => SECP256K1::OID,
+            CurveId::P256 => P256::OID,
+            CurveId::P384 => P384::OID,
+            CurveId::P521 => P521::OID,
+        }
+    }
+
+    /// Look a curve up by name or alias, ignoring case
+    pub fn from_name(name: &str) -> Option<CurveId> {
+        Self::ALL
+            .iter()
+            .find(|curve| {
+                curve.name().eq_ignore_ascii_case(name)
+                    || curve
+                        .aliases()
+                        .iter()
+                        .any(|alias| alias.eq_ignore_ascii_case(name))
+            })
+            .copied()
+    }
+
+    /// Look a curve up by object identifier
+    pub fn from_oid(oid: &str) -> Option<CurveId> {
+        Self::ALL.iter().find(|curve| curve.oid() == oid).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, CurveId, Point};
+    use crate::field::{FieldElement, Scalar};
+    use crate::p256::P256;
+    use crate::p384::P384;
+    use crate::p521::P521;
+    use crate::ruint::ModInt;
+    use crate::secp256k1::SECP256K1;
+
+    #[test]
+    fn curve_registry_lookup() {
+        assert_eq!(CurveId::from_name("secp256k1"), Some(CurveId::Secp256k1));
+        assert_eq!(CurveId::from_name("SECP256K1"), Some(CurveId::Secp256k1));
+        assert_eq!(CurveId::from_oid("1.3.132.0.10"), Some(CurveId::Secp256k1));
+        assert_eq!(CurveId::from_name("secp256r1"), Some(CurveId::P256));
+        assert_eq!(CurveId::from_name("p-256"), Some(CurveId::P256));
+        assert_eq!(CurveId::from_name("prime256v1"), Some(CurveId::P256));
+        assert_eq!(
+            CurveId::from_oid("1.2.840.10045.3.1.7"),
+            Some(CurveId::P256)
+        );
+        assert_eq!(CurveId::from_name("P-384"), Some(CurveId::P384));
+        assert_eq!(CurveId::from_oid("1.3.132.0.35"), Some(CurveId::P521));
+        assert_eq!(CurveId::from_name("curve25519"), None);
+        assert_eq!(CurveId::from_oid("1.3.132.0"), None);
+
+        for curve in CurveId::ALL {
+            assert_eq!(CurveId::from_name(curve.name()), Some(*curve));
+            assert_eq!(CurveId::from_oid(curve.oid()), Some(*curve));
+            for alias in curve.aliases() {
+                assert_eq!(CurveId::from_name(alias), Some(*curve));
+            }
+        }
+    }
+
+    #[test]
+    fn curve_points_are_on_curve() {
+        let g = SECP256K1::g();
+        assert!(SECP256K1::is_on_curve(&g));
+        assert!(SECP256K1::is_on_curve(&SECP256K1::double_point(&g)));
+        assert!(SECP256K1::is_on_curve(&SECP256K1::zero_point()));
+
+        let off_curve: Point = Point {
+            x: g.x.clone(),
+            y: g.y + FieldElement::one(),
+        };
+        assert!(!SECP256K1::is_on_curve(&off_curve));
+    }
+
+    #[test]
+    fn constant_time_scalar_multiplication() {
+        fn check<C: Curve>() {
+            let scalars = [
+                Scalar::<C>::zero(),
+                Scalar::one(),
+                -Scalar::one(),
+                Scalar::reduce(&C::Uint::from_u64(0xdeadbeef)),
+            ];
+            let point = C::double_point(&C::g());
+            for scalar in &scalars {
+                assert_eq!(
+                    C::ct_scalar_multiplication(scalar, &point),
+                    C::scalar_multiplication(scalar, &point)
+                );
+                assert_eq!(
+                    C::public_key(scalar),
+                    C::scalar_multiplication(scalar, &C::g())
+                );
+            }
+            assert!(C::ct_scalar_multiplication(&Scalar::one(), &C::zero_point()).is_zero_point());
+        }
+        check::<SECP256K1>();
+        check::<P256>();
+        check::<P384>();
+        check::<P521>();
+    }
+
+    #[test]
+    fn compressed_point_encoding() {
+        let g = SECP256K1::g();
+        let compressed = g.to_compressed();
+        assert_eq!(
+            hex::encode_upper(&compressed),
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798"
+        );
+        assert_eq!(Point::from_compressed(&compressed), Some(g.clone()));
+
+        // the other parity gives -G
+        let mut odd = compressed.clone();
+        odd[0] = 3;
+        assert_eq!(Point::<SECP256K1>::from_compressed(&odd).unwrap().y, -&g.y);
+
+        // bad prefix, bad length and x = 5 which isn't on the curve
+        odd[0] = 4;
+        assert_eq!(Point::<SECP256K1>::from_compressed(&odd), None);
+        assert_eq!(Point::<SECP256K1>::from_compressed(&compressed[..32]), None);
+        let mut five = [0u8; 33];
+        five[0] = 2;
+        five[32] = 5;
+        assert_eq!(Point::<SECP256K1>::from_compressed(&five), None);
+
+        assert_eq!(SECP256K1::zero_point().to_compressed(), vec![0]);
+
+        // uncompressed, which has to be on the curve as is
+        let uncompressed = g.to_uncompressed();
+        assert_eq!(hex::encode(&uncompressed), g.to_hex_string());
+        assert_eq!(Point::from_uncompressed(&uncompressed), Some(g.clone()));
+        let mut off_curve = uncompressed.clone();
+        off_curve[64] ^= 1;
+        assert_eq!(Point::<SECP256K1>::from_uncompressed(&off_curve), None);
+        assert_eq!(Point::<SECP256K1>::from_uncompressed(&compressed), None);
+    }
+}
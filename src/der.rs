@@ -0,0 +1,309 @@
+// ASN.1 DER, just enough of it for key files, see: https://www.itu.int/rec/T-REC-X.690
+// every value is a tag, a length and that many bytes of content, and
+// sequences are values whose content is more values
+//
+// lengths below 128 are one byte, longer ones are 0x80 + the number of length
+// bytes followed by the length in big endian, DER allows only the shortest
+// form, so the reader rejects anything else, as well as BER's indefinite length
+
+pub(crate) const INTEGER: u8 = 0x02;
+pub(crate) const BIT_STRING: u8 = 0x03;
+pub(crate) const OCTET_STRING: u8 = 0x04;
+pub(crate) const NULL: u8 = 0x05;
+pub(crate) const OBJECT_IDENTIFIER: u8 = 0x06;
+pub(crate) const SEQUENCE: u8 = 0x30;
+
+/// Tag of an explicitly tagged [n] field
+pub(crate) const fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+/// Reads DER values one after the other
+pub(crate) struct DerReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        DerReader { bytes }
+    }
+
+    /// Whether everything has been read
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Tag of the next value, without reading it
+    pub(crate) fn peek_tag(&self) -> Option<u8> {
+        self.bytes.first().copied()
+    }
+
+    /// Content of the next value, which must have the given tag
+    pub(crate) fn read(&mut self, tag: u8) -> Option<&'a [u8]> {
+        let (first, rest) = self.bytes.split_first()?;
+        if *first != tag {
+            return None;
+        }
+        let (length_byte, mut rest) = rest.split_first()?;
+        let length = if length_byte & 0x80 == 0 {
+            *length_byte as usize
+        } else {
+            // no indefinite length (0x80), no leading zeros, and nothing below 128
+            let count = (length_byte & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count || rest[0] == 0 {
+                return None;
+            }
+            let length = rest[..count]
+                .iter()
+                .fold(0usize, |length, byte| length << 8 | *byte as usize);
+            if length < 0x80 {
+                return None;
+            }
+            rest = &rest[count..];
+            length
+        };
+        if rest.len() < length {
+            return None;
+        }
+        let (content, rest) = rest.split_at(length);
+        self.bytes = rest;
+        Some(content)
+    }
+
+    /// Content of the next value if it has the given tag, for OPTIONAL fields
+    pub(crate) fn read_optional(&mut self, tag: u8) -> Option<Option<&'a [u8]>> {
+        if self.peek_tag() == Some(tag) {
+            self.read(tag).map(Some)
+        } else {
+            Some(None)
+        }
+    }
+
+    /// Reader for the content of the next value, e.g. a SEQUENCE
+    pub(crate) fn read_nested(&mut self, tag: u8) -> Option<DerReader<'a>> {
+        self.read(tag).map(DerReader::new)
+    }
+
+    /// The next value as a non-negative INTEGER that fits in a u64
+    pub(crate) fn read_u64(&mut self) -> Option<u64> {
+        let content = self.read(INTEGER)?;
+        // minimal two's complement: a leading zero only to clear the sign bit
+        match content {
+            [] => return None,
+            [first, ..] if first & 0x80 != 0 => return None,
+            [0, second, ..] if second & 0x80 == 0 => return None,
+            _ => {}
+        }
+        let content = content.strip_prefix(&[0]).unwrap_or(content);
+        if content.len() > 8 {
+            return None;
+        }
+        Some(
+            content
+                .iter()
+                .fold(0u64, |value, byte| value << 8 | *byte as u64),
+        )
+    }
+
+    /// The next value as an OBJECT IDENTIFIER, in dotted form
+    pub(crate) fn read_oid(&mut self) -> Option<String> {
+        decode_oid(self.read(OBJECT_IDENTIFIER)?)
+    }
+
+    /// The next value as a BIT STRING of whole bytes
+    pub(crate) fn read_bit_string(&mut self) -> Option<&'a [u8]> {
+        // the first byte counts the unused bits at the end
+        match self.read(BIT_STRING)? {
+            [0, bits @ ..] => Some(bits),
+            _ => None,
+        }
+    }
+
+    /// Check that nothing is left over
+    pub(crate) fn finish(self) -> Option<()> {
+        self.is_empty().then_some(())
+    }
+}
+
+/// Tag and length of a value with content of the given length
+fn header(tag: u8, length: usize) -> Vec<u8> {
+    let mut bytes = vec![tag];
+    if length < 0x80 {
+        bytes.push(length as u8);
+    } else {
+        let length = length.to_be_bytes();
+        let zeros = length.iter().take_while(|byte| **byte == 0).count();
+        bytes.push(0x80 | (length.len() - zeros) as u8);
+        bytes.extend_from_slice(&length[zeros..]);
+    }
+    bytes
+}
+
+/// DER encoding of a value
+pub(crate) fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    encode_all(tag, &[content])
+}
+
+/// DER encoding of a value whose content comes in parts
+/// written straight into the result, so no copies of secret parts are left behind
+fn encode_all(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+    let length = parts.iter().map(|part| part.len()).sum();
+    let mut bytes = header(tag, length);
+    bytes.reserve_exact(length);
+    for part in parts {
+        bytes.extend_from_slice(part);
+    }
+    bytes
+}
+
+/// SEQUENCE of already encoded values
+pub(crate) fn sequence(values: &[&[u8]]) -> Vec<u8> {
+    encode_all(SEQUENCE, values)
+}
+
+/// Non-negative INTEGER
+pub(crate) fn integer(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+    // a leading zero byte keeps the top bit from reading as a sign
+    let sign: &[u8] = if bytes[zeros] & 0x80 != 0 { &[0] } else { &[] };
+    encode_all(INTEGER, &[sign, &bytes[zeros..]])
+}
+
+/// OCTET STRING
+pub(crate) fn octet_string(bytes: &[u8]) -> Vec<u8> {
+    encode(OCTET_STRING, bytes)
+}
+
+/// BIT STRING of whole bytes
+pub(crate) fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    encode_all(BIT_STRING, &[&[0], bytes])
+}
+
+/// OBJECT IDENTIFIER from its dotted form
+/// panics on a malformed identifier, they are all constants
+pub(crate) fn oid(dotted: &str) -> Vec<u8> {
+    let arcs: Vec<u64> = dotted.split('.').map(|arc| arc.parse().unwrap()).collect();
+    assert!(arcs.len() >= 2 && arcs[0] <= 2, "malformed OID {}", dotted);
+
+    // the first two arcs share a number, and every arc is base 128 with the
+    // top bit set on all but its last byte
+    let mut content = vec![];
+    for arc in std::iter::once(arcs[0] * 40 + arcs[1]).chain(arcs[2..].iter().copied()) {
+        let digits = (64 - arc.leading_zeros() as usize).div_ceil(7).max(1);
+        for i in (0..digits).rev() {
+            let more = if i > 0 { 0x80 } else { 0 };
+            content.push(more | (arc >> (7 * i)) as u8 & 0x7f);
+        }
+    }
+    encode(OBJECT_IDENTIFIER, &content)
+}
+
+/// Dotted form of an OBJECT IDENTIFIER's content
+fn decode_oid(content: &[u8]) -> Option<String> {
+    let mut arcs = vec![];
+    let mut arc = 0u64;
+    for (i, byte) in content.iter().enumerate() {
+        // no leading 0x80 bytes, and no arcs that overflow
+        let starts_arc = i == 0 || content[i - 1] & 0x80 == 0;
+        if (starts_arc && *byte == 0x80) || arc >> 57 != 0 {
+            return None;
+        }
+        arc = arc << 7 | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    // the last byte has to end an arc
+    let (first, rest) = arcs.split_first()?;
+    if content.last()? & 0x80 != 0 {
+        return None;
+    }
+
+    let (top, second) = match first {
+        0..=39 => (0, *first),
+        40..=79 => (1, first - 40),
+        _ => (2, first - 80),
+    };
+    let dotted = [top, second]
+        .iter()
+        .chain(rest)
+        .map(|arc| arc.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+    Some(dotted)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::der::{self, DerReader, INTEGER, OCTET_STRING, SEQUENCE};
+
+    #[test]
+    fn der_encoding() {
+        // ecPublicKey and secp256k1, as in every EC key file
+        let oid = der::oid("1.2.840.10045.2.1");
+        assert_eq!(hex::encode(&oid), "06072a8648ce3d0201");
+        assert_eq!(hex::encode(der::oid("1.3.132.0.10")), "06052b8104000a");
+        for dotted in ["1.2.840.10045.2.1", "1.3.132.0.10", "2.999.3", "0.0"] {
+            let oid = der::oid(dotted);
+            assert_eq!(DerReader::new(&oid).read_oid().unwrap(), dotted);
+        }
+
+        for (value, encoding) in [
+            (0, "020100"),
+            (1, "020101"),
+            (128, "02020080"),
+            (256, "02020100"),
+        ] {
+            assert_eq!(hex::encode(der::integer(value)), encoding);
+            let bytes = hex::decode(encoding).unwrap();
+            assert_eq!(DerReader::new(&bytes).read_u64(), Some(value));
+        }
+
+        // long lengths and nesting
+        let content = vec![0x55; 300];
+        let value = der::sequence(&[&der::integer(1), &der::octet_string(&content)]);
+        assert_eq!(hex::encode(&value[..8]), "3082013302010104");
+        let mut reader = DerReader::new(&value);
+        let mut sequence = reader.read_nested(SEQUENCE).unwrap();
+        assert!(reader.finish().is_some());
+        assert_eq!(sequence.read_u64(), Some(1));
+        assert_eq!(sequence.read_optional(INTEGER), Some(None));
+        assert_eq!(sequence.read(OCTET_STRING).unwrap(), &content[..]);
+        assert!(sequence.finish().is_some());
+    }
+
+    #[test]
+    fn der_rejects_non_canonical_encodings() {
+        let cases = [
+            // lengths: indefinite, long form below 128, leading zero, truncated
+            (OCTET_STRING, "048001"),
+            (OCTET_STRING, "04810100"),
+            (OCTET_STRING, "0482000100"),
+            (OCTET_STRING, "040201"),
+            // the wrong tag
+            (INTEGER, "040100"),
+        ];
+        for (tag, encoding) in cases {
+            let bytes = hex::decode(encoding).unwrap();
+            assert_eq!(DerReader::new(&bytes).read(tag), None, "{}", encoding);
+        }
+
+        // integers: empty, negative, padded
+        for encoding in ["0200", "020180", "02020001", "0209010000000000000000"] {
+            let bytes = hex::decode(encoding).unwrap();
+            assert_eq!(DerReader::new(&bytes).read_u64(), None, "{}", encoding);
+        }
+
+        // object identifiers: empty, padded arc, unfinished arc
+        for encoding in ["0600", "06032a8001", "06022a86"] {
+            let bytes = hex::decode(encoding).unwrap();
+            assert_eq!(DerReader::new(&bytes).read_oid(), None, "{}", encoding);
+        }
+
+        // bit strings with unused bits
+        let bytes = hex::decode("030201ff").unwrap();
+        assert_eq!(DerReader::new(&bytes).read_bit_string(), None);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::curve::{Curve, Point};
+use crate::hash::HashFunction;
+use crate::secp256k1::SECP256K1;
+use crate::secret::PrivateKey;
+
+// Elliptic curve Diffie-Hellman, see: https://www.secg.org/sec1-v2.pdf section 3.3.1
+// both sides multiply the other's public key by their own private key
+// and end up at the same point, d_a * (d_b * G) = d_b * (d_a * G)
+//
+// the private key is secret, so the multiplication goes through
+// Curve::ct_scalar_multiplication rather than the branchy scalar_multiplication
+
+/// Errors of the key agreement
+#[derive(Debug, PartialEq, Eq)]
+pub enum EcdhError {
+    /// The peer's public key is the identity or not on the curve
+    InvalidPublicKey,
+    /// The private key is zero
+    InvalidPrivateKey,
+}
+
+/// Shared secret as libsecp256k1 derives it by default, SHA-256 of the
+/// compressed shared point
+pub fn shared_secret(private_key: &PrivateKey, peer: &Point) -> Result<[u8; 32], EcdhError> {
+    shared_secret_with(private_key, peer, |x, y| {
+        let mut compressed = vec![0x02 | (y[31] & 1)];
+        compressed.extend_from_slice(x);
+        HashFunction::Sha256.digest(&compressed).try_into().unwrap()
+    })
+}
+
+/// Shared secret as the raw x coordinate of the shared point, as in SEC 1
+/// it isn't uniformly random, hash it or run it through a KDF before using it as a key
+pub fn shared_secret_x(private_key: &PrivateKey, peer: &Point) -> Result<[u8; 32], EcdhError> {
+    shared_secret_with(private_key, peer, |x, _| x.try_into().unwrap())
+}
+
+/// Shared secret derived by a custom function of the shared point's
+/// big endian x and y coordinates
+pub fn shared_secret_with<T>(
+    private_key: &PrivateKey,
+    peer: &Point,
+    hash: impl FnOnce(&[u8], &[u8]) -> T,
+) -> Result<T, EcdhError> {
+    // an invalid point can be on a weaker curve, multiplying it by the key
+    // would give the key away bit by bit (invalid curve attack)
+    if peer.is_zero_point() || !SECP256K1::is_on_curve(peer) {
+        return Err(EcdhError::InvalidPublicKey);
+    }
+    if private_key.expose_secret().is_zero() {
+        return Err(EcdhError::InvalidPrivateKey);
+    }
+
+    // the curve has prime order and the key is non-zero, so the shared
+    // point is never the identity
+    let point = SECP256K1::ct_scalar_multiplication(private_key.expose_secret(), peer);
+    Ok(hash(&point.x.to_bytes(), &point.y.to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::ecdh::{shared_secret, shared_secret_with, shared_secret_x, EcdhError};
+    use crate::field::{FieldElement, Scalar};
+    use crate::hash::HashFunction;
+    use crate::p384::P384;
+    use crate::ru256::RU256;
+    use crate::ruint::ModInt;
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+    use std::str::FromStr;
+
+    #[test]
+    fn ecdh_shared_secret() {
+        // reference values from OpenSSL
+        let private_key = PrivateKey::from_str(
+            "8F72A3C9B02E11D5F6A1C0E77B3D4F2E96A0B1C2D3E4F5061728394A5B6C7D8E",
+        )
+        .unwrap();
+        let peer = Point::from_hex_coordinates(
+            "43F8C41498980A79E07B2F93ACA5FB513B29AB1AD1DD328044E16D8EB883BA42",
+            "27504C38BAAAAEE61363B5EA32A1078D617BC57E8DFA574983FA639DB0FDBF92",
+        );
+        assert_eq!(
+            hex::encode(shared_secret_x(&private_key, &peer).unwrap()),
+            "c0f2ad0ba2eccc04e0b429876230e48964506b521e42c63fb7b4b62312ab3201"
+        );
+        assert_eq!(
+            hex::encode(shared_secret(&private_key, &peer).unwrap()),
+            "44060d6be2c850b0ca1f4d20493ee052088951da597a8c8ee08809b0a182c592"
+        );
+
+        // both sides agree, whatever the hash
+        let peer_key = PrivateKey::from_str(
+            "1B2C3D4E5F60718293A4B5C6D7E8F90112233445566778899AABBCCDDEEFF001",
+        )
+        .unwrap();
+        let own = SECP256K1::public_key(private_key.expose_secret());
+        let sha512 = |x: &[u8], y: &[u8]| HashFunction::Sha512.digest(&[x, y].concat());
+        assert_eq!(
+            shared_secret(&peer_key, &own).unwrap(),
+            shared_secret(&private_key, &peer).unwrap()
+        );
+        assert_eq!(
+            shared_secret_with(&peer_key, &own, sha512).unwrap(),
+            shared_secret_with(&private_key, &peer, sha512).unwrap()
+        );
+
+        // invalid points and keys
+        let off_curve = Point {
+            x: peer.x.clone(),
+            y: &peer.y + &FieldElement::one(),
+        };
+        assert_eq!(
+            shared_secret(&private_key, &off_curve),
+            Err(EcdhError::InvalidPublicKey)
+        );
+        assert_eq!(
+            shared_secret(&private_key, &SECP256K1::zero_point()),
+            Err(EcdhError::InvalidPublicKey)
+        );
+        assert_eq!(
+            shared_secret(&PrivateKey::new(&RU256::zero()), &peer),
+            Err(EcdhError::InvalidPrivateKey)
+        );
+    }
+
+    #[test]
+    fn ecdh_ladder_matches_scalar_multiplication() {
+        let n = SECP256K1::n();
+        let mut scalars = vec![
+            Scalar::one(),
+            Scalar::reduce(&RU256::from_u64(2)),
+            Scalar::reduce(&RU256::from_u64(3)),
+            -Scalar::one(),
+            Scalar::reduce(&n.checked_sub(&RU256::from_u64(2))),
+        ];
+        for _ in 0..8 {
+            let mut scalar = Scalar::zero();
+            scalar.randomize();
+            scalars.push(scalar);
+        }
+
+        let point = SECP256K1::double_point(&SECP256K1::g());
+        for scalar in &scalars {
+            assert_eq!(
+                SECP256K1::ct_scalar_multiplication(scalar, &point),
+                SECP256K1::scalar_multiplication(scalar, &point)
+            );
+        }
+        assert!(SECP256K1::ct_scalar_multiplication(&Scalar::zero(), &point).is_zero_point());
+
+        // the ladder is generic, the NIST curves with a = -3 and wide integers work too
+        let mut scalar = Scalar::<P384>::zero();
+        scalar.randomize();
+        assert_eq!(
+            P384::ct_scalar_multiplication(&scalar, &P384::g()),
+            P384::scalar_multiplication(&scalar, &P384::g())
+        );
+    }
+}
@@ -1,97 +1,324 @@
-use crate::ru256::RU256;
-use crate::secp256k1::{Point, SECP256K1};
-use rand::Rng;
-use sha256::digest;
-use std::str::FromStr;
+use crate::curve::{Curve, JacobianPoint, Point};
+use crate::field::Scalar;
+use crate::ruint::ModInt;
+use crate::secp256k1::SECP256K1;
+use crate::secret::{Nonce, PrivateKey};
 
 /// Represents an ECDSA signature
-pub struct Signature {
-    r: RU256,
-    s: RU256,
+pub struct Signature<C: Curve = SECP256K1> {
+    pub(crate) r: Scalar<C>,
+    pub(crate) s: Scalar<C>,
 }
 
-/// Generate the sha256 hash of a string
-fn hash_string(message: &str) -> String {
-    digest(message)
+impl<C: Curve> Signature<C> {
+    /// Signature from its r and s values
+    /// both have to be in [1, n - 1], anything else is rejected
+    /// rather than reduced so a signature has exactly one encoding
+    pub(crate) fn new(r: &C::Uint, s: &C::Uint) -> Option<Self> {
+        let n = C::n();
+        if r.is_zero() || s.is_zero() || r >= &n || s >= &n {
+            return None;
+        }
+
+        Some(Signature {
+            r: Scalar::reduce(r),
+            s: Scalar::reduce(s),
+        })
+    }
+
+    /// Signature from its compact encoding, r followed by s, each as long as n
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let length = C::n().bits().div_ceil(8);
+        if bytes.len() != 2 * length {
+            return None;
+        }
+        Self::new(
+            &C::Uint::from_bytes(&bytes[..length]),
+            &C::Uint::from_bytes(&bytes[length..]),
+        )
+    }
+
+    /// Compact encoding of the signature, r followed by s
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.r.to_bytes(), self.s.to_bytes()].concat()
+    }
+}
+
+/// Convert a message digest to a scalar
+pub(crate) fn digest_to_scalar<C: Curve>(digest: &[u8]) -> Scalar<C> {
+    // see: https://www.secg.org/sec1-v2.pdf section 4.1.3 step 5
+    // only the leftmost bits of the digest are used, as many as the group order has
+    let n_bits = C::n().bits();
+    let digest = &digest[..digest.len().min(n_bits.div_ceil(8))];
+    let excess_bits = (digest.len() * 8).saturating_sub(n_bits);
+    let value = C::Uint::from_bytes(digest).shr(excess_bits);
+
+    // the truncated digest can still be larger than n
+    Scalar::reduce(&value)
 }
 
 /// ECDSA algorithm for signing a message
-fn sign_message(message: &str, private_key: &RU256) -> Signature {
-    // Hash the message to sign
-    let hash = RU256::from_str(&hash_string(message)).unwrap();
+pub fn sign_message<C: Curve>(message: &str, private_key: &PrivateKey<C>) -> Signature<C> {
+    // Hash the message to sign, with the hash that goes with the curve
+    let digest = C::HASH.digest(message.as_bytes());
+
+    loop {
+        // generate a random nonce
+        // the nonce is wiped when it goes out of scope, anyone who learns it
+        // can recover the private key from the signature
+        let nonce = Nonce::random();
 
-    // generate a random nonce
-    let mut rng = rand::thread_rng();
-    let nonce_bytes: [u8; 32] = rng.gen();
-    let nonce_num = RU256::from_bytes(&nonce_bytes);
+        // a nonce giving r = 0 or s = 0 is astronomically unlikely
+        // but has to be thrown away, just try another one
+        if let Some(signature) = sign_digest(&digest, private_key, &nonce) {
+            return signature;
+        }
+    }
+}
 
-    // map the nonce scalar to a point on the SECP256k1 curve using
-    // the generator as the base point
-    let nonce_point = SECP256K1::scalar_multiplication(&nonce_num, &SECP256K1::g());
+/// ECDSA signing of an already hashed message with the given nonce
+/// returns None if the nonce gives an invalid signature
+fn sign_digest<C: Curve>(
+    digest: &[u8],
+    private_key: &PrivateKey<C>,
+    nonce: &Nonce<C>,
+) -> Option<Signature<C>> {
+    // the hash is used as a scalar
+    let hash = digest_to_scalar::<C>(digest);
+    let nonce_num = nonce.expose_secret();
 
-    // r is the x component of the point
-    let r = &nonce_point.x;
+    // map the nonce scalar to a point on the curve using
+    // the generator as the base point, in constant time
+    // as the nonce gives the private key away
+    let nonce_point = C::public_key(nonce_num);
 
-    // grab the group order
-    let n = SECP256K1::n();
+    // r is the x component of the point
+    // x is a field element (mod p), we need it as a scalar (mod n)
+    let r = Scalar::reduce(nonce_point.x.value());
 
-    // compute s
-    let s = &r
-        .mul_mod(&private_key, &n)
-        .add_mod(&hash, &n)
-        .div_mod(&nonce_num, &n);
+    let s = signature_s(&hash, &r, private_key, nonce_num);
 
-    Signature {
-        r: r.clone(),
-        s: s.clone(),
+    if r.is_zero() || s.is_zero() {
+        return None;
     }
+
+    Some(Signature { r, s })
+}
+
+/// The s half of a signature, (r * private_key + hash) / nonce
+pub(crate) fn signature_s<C: Curve>(
+    hash: &Scalar<C>,
+    r: &Scalar<C>,
+    private_key: &PrivateKey<C>,
+    nonce: &Scalar<C>,
+) -> Scalar<C> {
+    // scalar arithmetic is constant time, so the private key
+    // and the nonce don't leak through timing (see: field.rs)
+    (r * private_key.expose_secret() + hash) / nonce
 }
 
 /// ECDSA algorithm for verification of a signed message
-fn verify_message(message: &str, pub_key: &Point, signature: &Signature) -> bool {
+pub fn verify_message<C: Curve>(
+    message: &str,
+    pub_key: &Point<C>,
+    signature: &Signature<C>,
+) -> bool {
     // hash the message
-    let hash = RU256::from_str(&hash_string(message)).unwrap();
+    let digest = C::HASH.digest(message.as_bytes());
 
-    // grab the group order
-    let n = SECP256K1::n();
+    verify_digest(&digest, pub_key, signature)
+}
+
+/// ECDSA verification of an already hashed message
+pub fn verify_digest<C: Curve>(
+    digest: &[u8],
+    pub_key: &Point<C>,
+    signature: &Signature<C>,
+) -> bool {
+    // a public key has to be an actual point on the curve
+    if pub_key.is_zero_point() || !C::is_on_curve(pub_key) {
+        return false;
+    }
+
+    let hash = digest_to_scalar::<C>(digest);
 
     // TODO: add comment showing short proof on why this works
-    let w = RU256::from_bytes(&[1]).div_mod(&signature.s, &n);
-    let u1 = &hash.mul_mod(&w, &n);
-    let u2 = &signature.r.mul_mod(&w, &n);
-    let u1_point = SECP256K1::scalar_multiplication(&u1, &SECP256K1::g());
-    let u2_point = SECP256K1::scalar_multiplication(&u2, &pub_key);
+    let w = signature.s.invert();
+    let u1 = &hash * &w;
+    let u2 = &signature.r * &w;
+    let u1_point = C::scalar_multiplication(&u1, &C::g());
+    let u2_point = C::scalar_multiplication(&u2, pub_key);
 
-    let verification_point = SECP256K1::add_points(&u1_point, &u2_point);
+    // u1 * G and u2 * pub_key can be the same point (or each other's negation)
+    // jacobian addition handles both cases, the affine one can't
+    let verification_point = C::to_affine(&C::add_jacobian_points(
+        &JacobianPoint::from(&u1_point),
+        &JacobianPoint::from(&u2_point),
+    ));
+    if verification_point.is_zero_point() {
+        return false;
+    }
 
-    verification_point.x == signature.r
+    Scalar::reduce(verification_point.x.value()) == signature.r
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ecdsa::{sign_message, verify_message};
-    use crate::ru256::RU256;
+    use crate::curve::{Curve, Point};
+    use crate::ecdsa::{sign_digest, sign_message, verify_digest, verify_message, Signature};
+    use crate::p256::P256;
+    use crate::p384::P384;
+    use crate::p521::P521;
     use crate::secp256k1::SECP256K1;
+    use crate::secret::{Nonce, PrivateKey};
+    use std::collections::HashMap;
     use std::str::FromStr;
 
+    /// Parse a test vector file made of blank line separated
+    /// blocks of `key = value` lines, lines starting with # are comments
+    fn parse_test_vectors(text: &str) -> Vec<HashMap<&str, &str>> {
+        text.split("\n\n")
+            .map(|block| {
+                block
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .filter_map(|line| line.split_once(" = "))
+                    .collect::<HashMap<_, _>>()
+            })
+            .filter(|vector| !vector.is_empty())
+            .collect()
+    }
+
     #[test]
     fn ecdsa_signing_and_verification() {
-        let private_key = &RU256::from_str("3424").unwrap();
-        let public_key = SECP256K1::public_key(&private_key);
+        let private_key = &PrivateKey::from_str("3424").unwrap();
+        let public_key = SECP256K1::public_key(private_key.expose_secret());
 
-        let signature = sign_message("hello-world", &private_key);
+        let signature = sign_message("hello-world", private_key);
         let verification_result = verify_message("hello-world", &public_key, &signature);
-        assert_eq!(verification_result, true);
+        assert!(verification_result);
 
         // should not verify for the same public key but different message
         let verification_result = verify_message("different-message", &public_key, &signature);
-        assert_eq!(verification_result, false);
+        assert!(!verification_result);
 
         // should not verify if we use a different public key
-        let private_key = &RU256::from_str("3425").unwrap();
-        let public_key = SECP256K1::public_key(&private_key);
+        let private_key = &PrivateKey::from_str("3425").unwrap();
+        let public_key = SECP256K1::public_key(private_key.expose_secret());
 
         let verification_result = verify_message("hello-world", &public_key, &signature);
-        assert_eq!(verification_result, false);
+        assert!(!verification_result);
+    }
+
+    #[test]
+    fn ecdsa_p256_signing_and_verification() {
+        let private_key: &PrivateKey<P256> = &PrivateKey::from_str("3424").unwrap();
+        let public_key = P256::public_key(private_key.expose_secret());
+
+        let signature = sign_message("hello-world", private_key);
+        assert!(verify_message("hello-world", &public_key, &signature));
+        assert!(!verify_message(
+            "different-message",
+            &public_key,
+            &signature
+        ));
+    }
+
+    /// Sign with the nonce from every SigGen vector and compare the signatures
+    fn check_sign_vectors<C: Curve>(text: &str) {
+        let vectors = parse_test_vectors(text);
+        assert_eq!(vectors.len(), 15);
+
+        for vector in vectors {
+            let private_key: PrivateKey<C> = PrivateKey::from_str(vector["D"]).unwrap();
+            let public_key = C::public_key(private_key.expose_secret());
+            assert_eq!(
+                public_key,
+                Point::from_hex_coordinates(vector["X"], vector["Y"])
+            );
+
+            let digest = hex::decode(vector["Digest"]).unwrap();
+            let nonce = Nonce::new(&C::Uint::from_str(vector["K"]).unwrap());
+            let signature = sign_digest(&digest, &private_key, &nonce).unwrap();
+            assert_eq!(
+                signature.r.value(),
+                &C::Uint::from_str(vector["R"]).unwrap()
+            );
+            assert_eq!(
+                signature.s.value(),
+                &C::Uint::from_str(vector["S"]).unwrap()
+            );
+
+            assert!(verify_digest(&digest, &public_key, &signature));
+
+            // flipping a bit of the digest breaks the signature
+            let mut tampered = digest.clone();
+            tampered[0] ^= 1;
+            assert!(!verify_digest(&tampered, &public_key, &signature));
+        }
+    }
+
+    /// Verify every SigVer vector, checking it is accepted or rejected as expected
+    fn check_verify_vectors<C: Curve>(text: &str, count: usize) {
+        let vectors = parse_test_vectors(text);
+        assert_eq!(vectors.len(), count);
+
+        for vector in vectors {
+            let public_key: Point<C> = Point::from_hex_coordinates(vector["X"], vector["Y"]);
+            let digest = hex::decode(vector["Digest"]).unwrap();
+            let valid = !vector.contains_key("Invalid");
+
+            // out of range r and s values can't even be parsed into a signature
+            let verified = Signature::new(
+                &C::Uint::from_str(vector["R"]).unwrap(),
+                &C::Uint::from_str(vector["S"]).unwrap(),
+            )
+            .map(|signature| verify_digest(&digest, &public_key, &signature))
+            .unwrap_or(false);
+
+            assert_eq!(verified, valid, "{:?}", vector);
+        }
+    }
+
+    #[test]
+    fn ecdsa_p256_nist_vectors() {
+        check_sign_vectors::<P256>(include_str!("../test_vectors/ecdsa_p256_sign.txt"));
+        check_verify_vectors::<P256>(include_str!("../test_vectors/ecdsa_p256_verify.txt"), 85);
+    }
+
+    #[test]
+    fn ecdsa_p384_nist_vectors() {
+        check_sign_vectors::<P384>(include_str!("../test_vectors/ecdsa_p384_sign.txt"));
+        check_verify_vectors::<P384>(include_str!("../test_vectors/ecdsa_p384_verify.txt"), 85);
+    }
+
+    #[test]
+    fn ecdsa_p521_nist_vectors() {
+        check_sign_vectors::<P521>(include_str!("../test_vectors/ecdsa_p521_sign.txt"));
+        check_verify_vectors::<P521>(include_str!("../test_vectors/ecdsa_p521_verify.txt"), 304);
+    }
+
+    #[test]
+    fn ecdsa_wide_curves_signing_and_verification() {
+        // sign_message picks the hash that goes with the curve
+        let private_key: &PrivateKey<P384> = &PrivateKey::from_str("3424").unwrap();
+        let public_key = P384::public_key(private_key.expose_secret());
+        let signature = sign_message("hello-world", private_key);
+        assert!(verify_message("hello-world", &public_key, &signature));
+        assert!(!verify_message(
+            "different-message",
+            &public_key,
+            &signature
+        ));
+
+        let private_key: &PrivateKey<P521> = &PrivateKey::from_str("3424").unwrap();
+        let public_key = P521::public_key(private_key.expose_secret());
+        let signature = sign_message("hello-world", private_key);
+        assert!(verify_message("hello-world", &public_key, &signature));
+        assert!(!verify_message(
+            "different-message",
+            &public_key,
+            &signature
+        ));
     }
 }
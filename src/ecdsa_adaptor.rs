@@ -0,0 +1,349 @@
+use crate::curve::{Curve, Point};
+use crate::ecdsa::{digest_to_scalar, signature_s, Signature};
+use crate::field::Scalar;
+use crate::hash::tagged_hash;
+use crate::msm::multi_scalar_multiplication;
+use crate::ru256::RU256;
+use crate::ruint::ModInt;
+use crate::secp256k1::SECP256K1;
+use crate::secret::{Nonce, PrivateKey};
+
+// ECDSA adaptor signatures, as used by DLCs and atomic swaps
+// see: https://github.com/discreetlogcontracts/dlcspecs/blob/master/ECDSA-adaptor.md
+// and: https://github.com/BlockstreamResearch/secp256k1-zkp/tree/master/src/modules/ecdsa_adaptor
+//
+// a pre-signature is an ECDSA signature whose nonce point is k * Y instead of
+// k * G, for an encryption key Y = y * G: r = x(k * Y) and s_a = (m + r * x) / k
+// whoever learns y turns it into the signature (r, s_a / y), and whoever sees
+// that signature next to the pre-signature learns y = s_a / s
+//
+// the verifier can't compute k * Y itself, so the signer also gives k * G and a
+// DLEQ proof that both points have the same discrete logarithm k
+// the encoding is the libsecp256k1-zkp one, 162 bytes of
+// R (33) || R_a (33) || s_a (32) || proof e (32) || proof s (32)
+
+/// Size of an encoded adaptor signature
+pub const ENCRYPTED_SIGNATURE_SIZE: usize = 162;
+
+/// ECDSA adaptor signature, a signature encrypted to a point
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncryptedSignature {
+    /// k * Y, whose x coordinate is the r of the decrypted signature
+    r: Point<SECP256K1>,
+    /// k * G
+    r_a: Point<SECP256K1>,
+    s_a: Scalar<SECP256K1>,
+    proof_e: Scalar<SECP256K1>,
+    proof_s: Scalar<SECP256K1>,
+}
+
+impl EncryptedSignature {
+    /// Adaptor signature from its 162 byte encoding
+    /// both points have to be on the curve, s_a in [1, n - 1] and the proof s below n
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != ENCRYPTED_SIGNATURE_SIZE {
+            return None;
+        }
+        let signature = Self {
+            r: Point::from_compressed(&bytes[..33])?,
+            r_a: Point::from_compressed(&bytes[33..66])?,
+            s_a: parse_scalar(&bytes[66..98])?,
+            proof_e: Scalar::reduce(&RU256::from_bytes(&bytes[98..130])),
+            proof_s: parse_scalar(&bytes[130..])?,
+        };
+        if signature.r_value().is_zero() || signature.s_a.is_zero() {
+            return None;
+        }
+        Some(signature)
+    }
+
+    /// 162 byte encoding of the adaptor signature
+    pub fn to_bytes(&self) -> [u8; ENCRYPTED_SIGNATURE_SIZE] {
+        let bytes = [
+            self.r.to_compressed(),
+            self.r_a.to_compressed(),
+            self.s_a.to_bytes(),
+            self.proof_e.to_bytes(),
+            self.proof_s.to_bytes(),
+        ]
+        .concat();
+        bytes.try_into().unwrap()
+    }
+
+    /// The r of the decrypted signature, x(R) mod n
+    fn r_value(&self) -> Scalar<SECP256K1> {
+        Scalar::reduce(self.r.x.value())
+    }
+}
+
+/// 32 bytes as a scalar, if they are a number below n
+fn parse_scalar(bytes: &[u8]) -> Option<Scalar<SECP256K1>> {
+    let value = RU256::from_bytes(bytes);
+    (value < SECP256K1::n()).then(|| Scalar::reduce(&value))
+}
+
+/// The DLEQ challenge, the hash of both statements and both commitments
+fn dleq_challenge(points: [&Point<SECP256K1>; 5]) -> Scalar<SECP256K1> {
+    // R_a || Y || R || A1 || A2, all compressed
+    let encoded = points.map(|point| point.to_compressed());
+    let data = encoded.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    Scalar::reduce(&RU256::from_bytes(&tagged_hash("DLEQ", &data)))
+}
+
+/// Proof that R_a = k * G and R = k * Y have the same k, as (e, s)
+fn dleq_prove(
+    k: &Scalar<SECP256K1>,
+    encryption_key: &Point<SECP256K1>,
+    r_a: &Point<SECP256K1>,
+    r: &Point<SECP256K1>,
+) -> (Scalar<SECP256K1>, Scalar<SECP256K1>) {
+    // a Schnorr proof run against both bases with the same nonce
+    let nonce = Nonce::<SECP256K1>::random();
+    let a1 = SECP256K1::public_key(nonce.expose_secret());
+    let a2 = SECP256K1::ct_scalar_multiplication(nonce.expose_secret(), encryption_key);
+    let e = dleq_challenge([r_a, encryption_key, r, &a1, &a2]);
+    let s = nonce.expose_secret() + &e * k;
+    (e, s)
+}
+
+/// Check a DLEQ proof, recomputing the commitments as s * base - e * point
+fn dleq_verify(
+    e: &Scalar<SECP256K1>,
+    s: &Scalar<SECP256K1>,
+    encryption_key: &Point<SECP256K1>,
+    r_a: &Point<SECP256K1>,
+    r: &Point<SECP256K1>,
+) -> bool {
+    let a1 = multi_scalar_multiplication(&[s.clone(), -e], &[SECP256K1::g(), r_a.clone()]);
+    let a2 = multi_scalar_multiplication(&[s.clone(), -e], &[encryption_key.clone(), r.clone()]);
+    // a forged proof can make a commitment the identity, which has no encoding
+    if a1.is_zero_point() || a2.is_zero_point() {
+        return false;
+    }
+    &dleq_challenge([r_a, encryption_key, r, &a1, &a2]) == e
+}
+
+/// Adaptor signature of a message digest, encrypted to the given encryption key
+pub fn encrypted_sign(
+    digest: &[u8],
+    private_key: &PrivateKey,
+    encryption_key: &Point<SECP256K1>,
+) -> EncryptedSignature {
+    let hash = digest_to_scalar::<SECP256K1>(digest);
+    loop {
+        // the same nonce handling as plain ECDSA signing, only the nonce
+        // point is taken on the encryption key instead of the generator
+        let nonce = Nonce::<SECP256K1>::random();
+        let k = nonce.expose_secret();
+        let r_a = SECP256K1::public_key(k);
+        let r = SECP256K1::ct_scalar_multiplication(k, encryption_key);
+        let r_value = Scalar::reduce(r.x.value());
+
+        let s_a = signature_s(&hash, &r_value, private_key, k);
+        if r_value.is_zero() || s_a.is_zero() {
+            continue;
+        }
+
+        let (proof_e, proof_s) = dleq_prove(k, encryption_key, &r_a, &r);
+        return EncryptedSignature {
+            r,
+            r_a,
+            s_a,
+            proof_e,
+            proof_s,
+        };
+    }
+}
+
+/// Check an adaptor signature of a message digest against the public and encryption keys
+/// a valid one decrypts to a valid ECDSA signature with the decryption key
+pub fn encrypted_verify(
+    digest: &[u8],
+    public_key: &Point<SECP256K1>,
+    encryption_key: &Point<SECP256K1>,
+    signature: &EncryptedSignature,
+) -> bool {
+    if public_key.is_zero_point()
+        || !SECP256K1::is_on_curve(public_key)
+        || encryption_key.is_zero_point()
+        || !SECP256K1::is_on_curve(encryption_key)
+    {
+        return false;
+    }
+    if !dleq_verify(
+        &signature.proof_e,
+        &signature.proof_s,
+        encryption_key,
+        &signature.r_a,
+        &signature.r,
+    ) {
+        return false;
+    }
+
+    // R_a = (m * G + r * X) / s_a, plain ECDSA verification with R_a as the nonce point
+    let hash = digest_to_scalar::<SECP256K1>(digest);
+    let w = signature.s_a.invert();
+    let u1 = &hash * &w;
+    let u2 = &signature.r_value() * &w;
+    let point = multi_scalar_multiplication(&[u1, u2], &[SECP256K1::g(), public_key.clone()]);
+    !point.is_zero_point() && point == signature.r_a
+}
+
+/// The ECDSA signature hidden in an adaptor signature, with the decryption key y
+/// s is normalized to the lower half like libsecp256k1-zkp does, so the result
+/// is accepted by Bitcoin's low s rule
+pub fn decrypt(signature: &EncryptedSignature, decryption_key: &PrivateKey) -> Option<Signature> {
+    let y = decryption_key.expose_secret();
+    if y.is_zero() {
+        return None;
+    }
+    let s = &signature.s_a / y;
+    let s = if s.value() > &SECP256K1::n().shr(1) {
+        -s
+    } else {
+        s
+    };
+    Signature::new(signature.r_value().value(), s.value())
+}
+
+/// The decryption key, from an adaptor signature and the signature it was decrypted to
+/// returns None if the signature doesn't belong to the adaptor signature and encryption key
+pub fn recover(
+    signature: &Signature,
+    encrypted_signature: &EncryptedSignature,
+    encryption_key: &Point<SECP256K1>,
+) -> Option<PrivateKey> {
+    if signature.r != encrypted_signature.r_value() {
+        return None;
+    }
+
+    // y = s_a / s, up to the sign, since anyone can negate s in a published signature
+    let mut y = &encrypted_signature.s_a / &signature.s;
+    let point = SECP256K1::public_key(&y);
+    let result = if point.x != encryption_key.x {
+        None
+    } else if point.y != encryption_key.y {
+        Some(PrivateKey::new((-&y).value()))
+    } else {
+        Some(PrivateKey::new(y.value()))
+    };
+    y.wipe();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::ecdsa::{verify_digest, Signature};
+    use crate::ecdsa_adaptor::{
+        decrypt, encrypted_sign, encrypted_verify, recover, EncryptedSignature,
+    };
+    use crate::hash::HashFunction;
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+    use serde_json::Value;
+
+    #[test]
+    fn ecdsa_adaptor_test_vectors() {
+        // see: https://github.com/discreetlogcontracts/dlcspecs/blob/596a177375932a47306f07e7385f398f52519a83/test/ecdsa_adaptor.json
+        let vectors: Value =
+            serde_json::from_str(include_str!("../test_vectors/ecdsa_adaptor.json")).unwrap();
+        let bytes = |vector: &Value, key: &str| hex::decode(vector[key].as_str().unwrap()).unwrap();
+        for vector in vectors.as_array().unwrap() {
+            let comment = vector["comment"].as_str().unwrap();
+            let adaptor_bytes = bytes(vector, "adaptor_sig");
+            let adaptor = EncryptedSignature::from_bytes(&adaptor_bytes);
+            if let Some(expected) = vector["expected_serialization"].as_bool() {
+                assert_eq!(adaptor.is_some(), expected, "{}", comment);
+                if expected {
+                    assert_eq!(adaptor.unwrap().to_bytes().to_vec(), adaptor_bytes);
+                }
+                continue;
+            }
+
+            let adaptor = adaptor.unwrap();
+            let encryption_key = Point::from_compressed(&bytes(vector, "encryption_key")).unwrap();
+            let signature = Signature::from_bytes(&bytes(vector, "signature")).unwrap();
+            let decryption_key = vector
+                .get("decryption_key")
+                .map(|_| PrivateKey::from_bytes(&bytes(vector, "decryption_key")).unwrap());
+
+            if let Some(expected) = vector["expected_verify"].as_bool() {
+                let public_key = Point::from_compressed(&bytes(vector, "pubkey")).unwrap();
+                let digest = bytes(vector, "message_hash");
+                assert_eq!(
+                    encrypted_verify(&digest, &public_key, &encryption_key, &adaptor),
+                    expected,
+                    "{}",
+                    comment
+                );
+            }
+            if let Some(expected) = vector["expected_decrypt"].as_bool() {
+                let decrypted = decrypt(&adaptor, decryption_key.as_ref().unwrap()).unwrap();
+                assert_eq!(
+                    decrypted.to_bytes() == bytes(vector, "signature"),
+                    expected,
+                    "{}",
+                    comment
+                );
+            }
+            let recovered = recover(&signature, &adaptor, &encryption_key);
+            if vector["expected_recover"].as_bool().unwrap() {
+                assert_eq!(
+                    recovered.unwrap().expose_secret(),
+                    decryption_key.unwrap().expose_secret(),
+                    "{}",
+                    comment
+                );
+            } else {
+                assert!(recovered.is_none(), "{}", comment);
+            }
+        }
+    }
+
+    #[test]
+    fn ecdsa_adaptor_round_trip() {
+        let private_key = PrivateKey::from_bytes(&[0x11; 32]).unwrap();
+        let public_key = SECP256K1::public_key(private_key.expose_secret());
+        let decryption_key = PrivateKey::from_bytes(&[0x22; 32]).unwrap();
+        let encryption_key = SECP256K1::public_key(decryption_key.expose_secret());
+        let digest = HashFunction::Sha256.digest(b"pay 1 BTC to Bob");
+
+        let adaptor = encrypted_sign(&digest, &private_key, &encryption_key);
+        assert!(encrypted_verify(
+            &digest,
+            &public_key,
+            &encryption_key,
+            &adaptor
+        ));
+        assert_eq!(
+            EncryptedSignature::from_bytes(&adaptor.to_bytes()),
+            Some(adaptor.clone())
+        );
+        // the pre-signature is no signature yet, and is bound to the message and both keys
+        assert!(!encrypted_verify(
+            &digest,
+            &encryption_key,
+            &encryption_key,
+            &adaptor
+        ));
+        assert!(!encrypted_verify(
+            &digest,
+            &public_key,
+            &public_key,
+            &adaptor
+        ));
+        assert!(!encrypted_verify(
+            &[0; 32],
+            &public_key,
+            &encryption_key,
+            &adaptor
+        ));
+
+        let signature = decrypt(&adaptor, &decryption_key).unwrap();
+        assert!(verify_digest(&digest, &public_key, &signature));
+        let recovered = recover(&signature, &adaptor, &encryption_key).unwrap();
+        assert_eq!(recovered.expose_secret(), decryption_key.expose_secret());
+        assert!(recover(&signature, &adaptor, &public_key).is_none());
+    }
+}
@@ -0,0 +1,379 @@
+use crate::curve::{Curve, Point};
+use crate::ecdh::{shared_secret_with, shared_secret_x, EcdhError};
+use crate::field::Scalar;
+use crate::hash::HashFunction;
+use crate::secp256k1::SECP256K1;
+use crate::secret::{wipe_bytes, PrivateKey};
+use aes::cipher::{KeyIvInit, StreamCipher};
+use aes_gcm::aead::consts::U16;
+use aes_gcm::aead::AeadInPlace;
+use aes_gcm::aes::Aes256;
+use aes_gcm::{AesGcm, KeyInit};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+
+// ECIES hybrid encryption to a secp256k1 public key
+// the sender makes up an ephemeral key pair, runs ECDH between it and the
+// recipient's key, derives a symmetric key from the shared point and encrypts
+// with that, the ephemeral public key goes along with the ciphertext so the
+// recipient can run the same ECDH with their private key
+//
+// two wire formats:
+// - eciespy / eciesrs: HKDF-SHA256 over the uncompressed ephemeral key and
+//   shared point, AES-256-GCM with a 16 byte nonce
+//   ephemeral public key (65) || nonce (16) || tag (16) || ciphertext
+// - Ethereum devp2p (RLPx): NIST concatenation KDF over the shared x,
+//   AES-128-CTR and HMAC-SHA256 over the iv, ciphertext and shared mac data
+//   ephemeral public key (65) || iv (16) || ciphertext || tag (32)
+
+/// Length of an uncompressed public key
+const PUBLIC_KEY_LENGTH: usize = 65;
+
+/// AES-GCM nonce length, eciespy uses 16 rather than the usual 12
+const NONCE_LENGTH: usize = 16;
+
+/// AES-GCM tag length
+const TAG_LENGTH: usize = 16;
+
+/// AES-CTR iv length, devp2p
+const IV_LENGTH: usize = 16;
+
+/// HMAC-SHA256 tag length, devp2p
+const MAC_LENGTH: usize = 32;
+
+type Aes256Gcm16 = AesGcm<Aes256, U16>;
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+/// Errors of encryption and decryption
+#[derive(Debug, PartialEq, Eq)]
+pub enum EciesError {
+    /// The recipient's public key is the identity or not on the curve
+    InvalidPublicKey,
+    /// The private key is zero
+    InvalidPrivateKey,
+    /// The ciphertext is too short or its ephemeral key is malformed
+    InvalidCiphertext,
+    /// The tag doesn't match, the ciphertext was tampered with or is for another key
+    AuthenticationFailed,
+}
+
+impl From<EcdhError> for EciesError {
+    fn from(error: EcdhError) -> Self {
+        match error {
+            EcdhError::InvalidPublicKey => EciesError::InvalidPublicKey,
+            EcdhError::InvalidPrivateKey => EciesError::InvalidPrivateKey,
+        }
+    }
+}
+
+/// Random ephemeral key
+fn ephemeral_key() -> PrivateKey {
+    let mut k = Scalar::<SECP256K1>::zero();
+    k.randomize();
+    let key = PrivateKey::new(k.value());
+    k.wipe();
+    key
+}
+
+/// ECDH shared point of a key pair, uncompressed
+fn shared_point(private_key: &PrivateKey, public_key: &Point) -> Result<Vec<u8>, EciesError> {
+    Ok(shared_secret_with(private_key, public_key, |x, y| {
+        [&[4][..], x, y].concat()
+    })?)
+}
+
+/// eciespy key derivation, HKDF-SHA256 without salt or info
+fn derive_key(ephemeral_public_key: &[u8], shared_point: &[u8]) -> [u8; 32] {
+    let mut master = [ephemeral_public_key, shared_point].concat();
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, &master)
+        .expand(&[], &mut key)
+        .unwrap();
+    wipe_bytes(&mut master);
+    key
+}
+
+/// Encrypt a message to a public key, eciespy compatible
+pub fn encrypt(public_key: &Point, message: &[u8]) -> Result<Vec<u8>, EciesError> {
+    let nonce: [u8; NONCE_LENGTH] = rand::thread_rng().gen();
+    encrypt_with(public_key, message, &ephemeral_key(), &nonce)
+}
+
+/// Encryption with the given ephemeral key and nonce
+fn encrypt_with(
+    public_key: &Point,
+    message: &[u8],
+    ephemeral_key: &PrivateKey,
+    nonce: &[u8; NONCE_LENGTH],
+) -> Result<Vec<u8>, EciesError> {
+    let ephemeral_public_key =
+        SECP256K1::public_key(ephemeral_key.expose_secret()).to_uncompressed();
+    let mut shared = shared_point(ephemeral_key, public_key)?;
+    let mut key = derive_key(&ephemeral_public_key, &shared);
+    wipe_bytes(&mut shared);
+
+    let mut ciphertext = message.to_vec();
+    let tag = Aes256Gcm16::new(&key.into())
+        .encrypt_in_place_detached(nonce.into(), &[], &mut ciphertext)
+        .unwrap();
+    wipe_bytes(&mut key);
+
+    Ok([&ephemeral_public_key[..], nonce, &tag, &ciphertext].concat())
+}
+
+/// Decrypt a message encrypted to our public key, eciespy compatible
+pub fn decrypt(private_key: &PrivateKey, ciphertext: &[u8]) -> Result<Vec<u8>, EciesError> {
+    if ciphertext.len() < PUBLIC_KEY_LENGTH + NONCE_LENGTH + TAG_LENGTH {
+        return Err(EciesError::InvalidCiphertext);
+    }
+    let (ephemeral_public_key, rest) = ciphertext.split_at(PUBLIC_KEY_LENGTH);
+    let (nonce, rest) = rest.split_at(NONCE_LENGTH);
+    let (tag, encrypted) = rest.split_at(TAG_LENGTH);
+
+    let ephemeral_point =
+        Point::from_uncompressed(ephemeral_public_key).ok_or(EciesError::InvalidCiphertext)?;
+    let mut shared = shared_point(private_key, &ephemeral_point)?;
+    let mut key = derive_key(ephemeral_public_key, &shared);
+    wipe_bytes(&mut shared);
+
+    let mut message = encrypted.to_vec();
+    let result = Aes256Gcm16::new(&key.into()).decrypt_in_place_detached(
+        nonce.into(),
+        &[],
+        &mut message,
+        tag.into(),
+    );
+    wipe_bytes(&mut key);
+
+    result.map_err(|_| EciesError::AuthenticationFailed)?;
+    Ok(message)
+}
+
+/// devp2p key derivation, NIST SP 800-56 concatenation KDF with SHA-256
+/// 16 bytes of encryption key, and the mac key as the hash of the other 16
+fn derive_devp2p_keys(shared_x: &[u8]) -> ([u8; 16], [u8; 32]) {
+    // one round is enough for 32 bytes, the counter starts at 1
+    let mut input = [&1u32.to_be_bytes()[..], shared_x].concat();
+    let mut key = HashFunction::Sha256.digest(&input);
+    let encryption_key: [u8; 16] = key[..16].try_into().unwrap();
+    let mac_key: [u8; 32] = HashFunction::Sha256.digest(&key[16..]).try_into().unwrap();
+    wipe_bytes(&mut input);
+    wipe_bytes(&mut key);
+    (encryption_key, mac_key)
+}
+
+/// HMAC-SHA256 over the iv, ciphertext and shared mac data
+fn devp2p_mac(
+    mac_key: &[u8; 32],
+    iv_and_ciphertext: &[u8],
+    shared_mac_data: &[u8],
+) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(mac_key).unwrap();
+    mac.update(iv_and_ciphertext);
+    mac.update(shared_mac_data);
+    mac
+}
+
+/// Encrypt a message to a public key in the Ethereum devp2p format
+/// the shared mac data is authenticated but not sent, both sides have to know it
+pub fn encrypt_devp2p(
+    public_key: &Point,
+    message: &[u8],
+    shared_mac_data: &[u8],
+) -> Result<Vec<u8>, EciesError> {
+    let iv: [u8; IV_LENGTH] = rand::thread_rng().gen();
+    encrypt_devp2p_with(public_key, message, shared_mac_data, &ephemeral_key(), &iv)
+}
+
+/// devp2p encryption with the given ephemeral key and iv
+fn encrypt_devp2p_with(
+    public_key: &Point,
+    message: &[u8],
+    shared_mac_data: &[u8],
+    ephemeral_key: &PrivateKey,
+    iv: &[u8; IV_LENGTH],
+) -> Result<Vec<u8>, EciesError> {
+    let mut shared_x = shared_secret_x(ephemeral_key, public_key)?;
+    let (mut encryption_key, mut mac_key) = derive_devp2p_keys(&shared_x);
+    wipe_bytes(&mut shared_x);
+
+    let mut iv_and_ciphertext = [&iv[..], message].concat();
+    Aes128Ctr::new(&encryption_key.into(), iv.into())
+        .apply_keystream(&mut iv_and_ciphertext[IV_LENGTH..]);
+    let tag = devp2p_mac(&mac_key, &iv_and_ciphertext, shared_mac_data)
+        .finalize()
+        .into_bytes();
+    wipe_bytes(&mut encryption_key);
+    wipe_bytes(&mut mac_key);
+
+    let ephemeral_public_key =
+        SECP256K1::public_key(ephemeral_key.expose_secret()).to_uncompressed();
+    Ok([&ephemeral_public_key[..], &iv_and_ciphertext, &tag].concat())
+}
+
+/// Decrypt a message in the Ethereum devp2p format
+pub fn decrypt_devp2p(
+    private_key: &PrivateKey,
+    ciphertext: &[u8],
+    shared_mac_data: &[u8],
+) -> Result<Vec<u8>, EciesError> {
+    if ciphertext.len() < PUBLIC_KEY_LENGTH + IV_LENGTH + MAC_LENGTH {
+        return Err(EciesError::InvalidCiphertext);
+    }
+    let (ephemeral_public_key, rest) = ciphertext.split_at(PUBLIC_KEY_LENGTH);
+    let (iv_and_ciphertext, tag) = rest.split_at(rest.len() - MAC_LENGTH);
+
+    let ephemeral_point =
+        Point::from_uncompressed(ephemeral_public_key).ok_or(EciesError::InvalidCiphertext)?;
+    let mut shared_x = shared_secret_x(private_key, &ephemeral_point)?;
+    let (mut encryption_key, mut mac_key) = derive_devp2p_keys(&shared_x);
+    wipe_bytes(&mut shared_x);
+
+    // the tag is checked before decrypting anything, in constant time
+    let verified = devp2p_mac(&mac_key, iv_and_ciphertext, shared_mac_data).verify_slice(tag);
+    wipe_bytes(&mut mac_key);
+    if verified.is_err() {
+        wipe_bytes(&mut encryption_key);
+        return Err(EciesError::AuthenticationFailed);
+    }
+
+    let (iv, encrypted) = iv_and_ciphertext.split_at(IV_LENGTH);
+    let mut message = encrypted.to_vec();
+    Aes128Ctr::new(&encryption_key.into(), iv.into()).apply_keystream(&mut message);
+    wipe_bytes(&mut encryption_key);
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::ecies::{
+        decrypt, decrypt_devp2p, derive_key, encrypt, encrypt_devp2p, encrypt_devp2p_with,
+        shared_point, EciesError,
+    };
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+    use std::str::FromStr;
+
+    #[test]
+    fn ecies_eciespy_interop() {
+        // known values from eciesrs, which eciespy shares
+        // see: https://github.com/ecies/rs/blob/master/src/elliptic/secp256k1.rs
+        let sk2 = PrivateKey::from_str("2").unwrap();
+        let pk3 = SECP256K1::public_key(
+            PrivateKey::<SECP256K1>::from_str("3")
+                .unwrap()
+                .expose_secret(),
+        );
+        let ephemeral_public_key = SECP256K1::public_key(sk2.expose_secret()).to_uncompressed();
+        assert_eq!(
+            hex::encode(derive_key(
+                &ephemeral_public_key,
+                &shared_point(&sk2, &pk3).unwrap()
+            )),
+            "6f982d63e8590c9d9b5b4c1959ff80315d772edd8f60287c9361d548d5200f82"
+        );
+
+        let private_key = PrivateKey::from_str(
+            "e520872701d9ec44dbac2eab85512ad14ad0c42e01de56d7b528abd8524fcb47",
+        )
+        .unwrap();
+        let ciphertext = hex::decode(
+            "047be1885aeb48d4d4db0c992996725d3264784fef88c5b60782f8d0f940c213227fc3f904f846d5ec3d0fba\
+             6653754501e8ebadc421aa3892a20fef33cff0206047058a4cfb4efbeae96b2d019b4ab2edce33328748a0d0\
+             08a69c8f5816b72d45bd9b5a41bb6ea0127ab23057ec6fcd",
+        )
+        .unwrap();
+        assert_eq!(
+            decrypt(&private_key, &ciphertext).unwrap(),
+            "hello world🌍".as_bytes()
+        );
+    }
+
+    #[test]
+    fn ecies_round_trip_and_tampering() {
+        let private_key = PrivateKey::from_bytes(&[0x42; 32]).unwrap();
+        let public_key = SECP256K1::public_key(private_key.expose_secret());
+        let message = b"attack at dawn";
+
+        let ciphertext = encrypt(&public_key, message).unwrap();
+        assert_eq!(ciphertext.len(), 65 + 16 + 16 + message.len());
+        assert_eq!(decrypt(&private_key, &ciphertext).unwrap(), message);
+        assert_eq!(
+            decrypt(&private_key, &encrypt(&public_key, b"").unwrap()).unwrap(),
+            b""
+        );
+
+        // flipping any bit after the ephemeral key breaks the tag, and a
+        // changed ephemeral key is either off the curve or gives another key
+        for i in [66, 65 + 16, 65 + 32, ciphertext.len() - 1, 10] {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert!(decrypt(&private_key, &tampered).is_err());
+        }
+        assert_eq!(
+            decrypt(&private_key, &ciphertext[..65 + 31]),
+            Err(EciesError::InvalidCiphertext)
+        );
+        assert_eq!(
+            decrypt(&PrivateKey::from_bytes(&[0x43; 32]).unwrap(), &ciphertext),
+            Err(EciesError::AuthenticationFailed)
+        );
+        assert_eq!(
+            encrypt(&SECP256K1::zero_point(), message),
+            Err(EciesError::InvalidPublicKey)
+        );
+    }
+
+    #[test]
+    fn ecies_devp2p() {
+        // reference ciphertext made with OpenSSL following the RLPx spec
+        // see: https://github.com/ethereum/devp2p/blob/master/rlpx.md#ecies-encryption
+        let private_key = PrivateKey::from_str(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        )
+        .unwrap();
+        let public_key = Point::from_hex_coordinates(
+            "4e3b81af9c2234cad09d679ce6035ed1392347ce64ce405f5dcd36228a25de6e",
+            "47fd35c4215d1edf53e6f83de344615ce719bdb0fd878f6ed76f06dd277956de",
+        );
+        let ephemeral_key = PrivateKey::from_str(
+            "5f7d3b0e7ac1a9e2c28d4eab5e4b6f1a0c3d2e1f4a5b6c7d8e9f0a1b2c3d4e5f",
+        )
+        .unwrap();
+        let iv: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let expected = "04c82eb9e91bef55608e7aca17a08b4d98a33e44b43d77a7685e0af0602fd3cc960ece\
+                        804333ed75b895247e1825ad8a888507436a33bcf8bc75660f0731ad23ec0001020304\
+                        05060708090a0b0c0d0e0fc859c0738fe2acbb0103c9585a6cdb8017451e5e6c59ae0e\
+                        615270b00a74e161239df8e35276f23828ef561a";
+
+        let ciphertext =
+            encrypt_devp2p_with(&public_key, b"hello devp2p", &[1, 2], &ephemeral_key, &iv)
+                .unwrap();
+        assert_eq!(hex::encode(&ciphertext), expected);
+        assert_eq!(
+            decrypt_devp2p(&private_key, &ciphertext, &[1, 2]).unwrap(),
+            b"hello devp2p"
+        );
+
+        // the shared mac data is part of the tag
+        assert_eq!(
+            decrypt_devp2p(&private_key, &ciphertext, &[1, 3]),
+            Err(EciesError::AuthenticationFailed)
+        );
+        let mut tampered = ciphertext.clone();
+        tampered[65 + 16] ^= 1;
+        assert_eq!(
+            decrypt_devp2p(&private_key, &tampered, &[1, 2]),
+            Err(EciesError::AuthenticationFailed)
+        );
+
+        let ciphertext = encrypt_devp2p(&public_key, b"fresh randomness", &[]).unwrap();
+        assert_eq!(
+            decrypt_devp2p(&private_key, &ciphertext, &[]).unwrap(),
+            b"fresh randomness"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crate::curve::{Curve, Point};
+use crate::field::Scalar;
+use crate::hash::HashFunction;
+use crate::msm::multi_scalar_multiplication;
+use crate::ru256::RU256;
+use crate::secp256k1::SECP256K1;
+use crate::secret::{wipe, wipe_bytes, Nonce, PrivateKey};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+// ECVRF, a verifiable random function on secp256k1 with SHA-256
+// see: https://www.rfc-editor.org/rfc/rfc9381
+// the holder of a private key x maps an input alpha to a pseudorandom output
+// beta, and a proof pi lets anyone with the public key Y = x * G check that beta
+// is the one and only output for alpha, without learning anything about x
+//
+// Gamma = x * H for the input point H = encode_to_curve(Y, alpha), and pi is
+// Gamma with a DLEQ proof (c, s) that log_G(Y) = log_H(Gamma), beta hashes Gamma
+//
+// the ciphersuite is SECP256K1_SHA256_TAI of draft-irtf-cfrg-vrf-05 as used by
+// witnet/vrf-rs, suite string 0xFE, try-and-increment encode_to_curve, RFC 6979
+// nonces and a 16 byte c, the proof is Gamma (33) || c (16) || s (32)
+// see: https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-vrf-05
+// RFC 9381 later added Y to the challenge and a trailing 0x00 to every hash
+// input, so its suites give different outputs for the same keys
+
+/// Size of an encoded proof
+pub const PROOF_SIZE: usize = 81;
+
+const SUITE: u8 = 0xFE;
+const CHALLENGE_SIZE: usize = 16;
+
+/// ECVRF proof, Gamma with the DLEQ proof (c, s)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proof {
+    gamma: Point<SECP256K1>,
+    c: Scalar<SECP256K1>,
+    s: Scalar<SECP256K1>,
+}
+
+impl Proof {
+    /// Proof from its 81 byte encoding
+    /// Gamma has to be on the curve and s below n
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != PROOF_SIZE {
+            return None;
+        }
+        let s = RU256::from_bytes(&bytes[33 + CHALLENGE_SIZE..]);
+        if s >= SECP256K1::n() {
+            return None;
+        }
+        Some(Self {
+            gamma: Point::from_compressed(&bytes[..33])?,
+            c: Scalar::reduce(&RU256::from_bytes(&bytes[33..33 + CHALLENGE_SIZE])),
+            s: Scalar::reduce(&s),
+        })
+    }
+
+    /// 81 byte encoding of the proof
+    pub fn to_bytes(&self) -> [u8; PROOF_SIZE] {
+        // Gamma is never the identity, which would encode to a single byte
+        // prove and from_bytes both rule it out
+        let bytes = [
+            self.gamma.to_compressed(),
+            self.c.to_bytes()[32 - CHALLENGE_SIZE..].to_vec(),
+            self.s.to_bytes(),
+        ]
+        .concat();
+        bytes.try_into().unwrap()
+    }
+}
+
+/// Hash an input to a point with try-and-increment, the first of
+/// SHA-256(0xFE || 0x01 || Y || alpha || ctr) that is the x coordinate of a point
+/// taken with even y, None after 256 misses which happens with probability 2^-256
+pub fn encode_to_curve(public_key: &Point<SECP256K1>, alpha: &[u8]) -> Option<Point<SECP256K1>> {
+    let mut data = [
+        &[SUITE, 0x01],
+        public_key.to_compressed().as_slice(),
+        alpha,
+        &[0],
+    ]
+    .concat();
+    let last = data.len() - 1;
+    (0..=u8::MAX).find_map(|ctr| {
+        data[last] = ctr;
+        let hash = HashFunction::Sha256.digest(&data);
+        // the cofactor of secp256k1 is 1, every point is in the prime order group
+        Point::from_compressed(&[&[0x02], hash.as_slice()].concat())
+    })
+}
+
+/// Deterministic nonce of RFC 6979 with HMAC-SHA256, over the SHA-256 of the input point
+/// see: https://www.rfc-editor.org/rfc/rfc6979#section-3.2
+fn nonce(private_key: &PrivateKey, h: &Point<SECP256K1>) -> Nonce<SECP256K1> {
+    let hmac = |key: &[u8], data: &[&[u8]]| -> [u8; 32] {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).unwrap();
+        for part in data {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    };
+
+    let digest = HashFunction::Sha256.digest(&h.to_compressed());
+    let h1 = Scalar::<SECP256K1>::reduce(&RU256::from_bytes(&digest)).to_bytes();
+    let mut x = private_key.expose_secret().to_bytes();
+    let mut v = [0x01; 32];
+    let mut k = [0x00; 32];
+    for prefix in [0x00, 0x01] {
+        k = hmac(&k, &[&v, &[prefix], &x, &h1]);
+        v = hmac(&k, &[&v]);
+    }
+    wipe_bytes(&mut x);
+
+    loop {
+        v = hmac(&k, &[&v]);
+        let mut candidate = RU256::from_bytes(&v);
+        if !candidate.is_zero() && candidate < SECP256K1::n() {
+            let nonce = Nonce::new(&candidate);
+            wipe(&mut candidate);
+            wipe_bytes(&mut v);
+            wipe_bytes(&mut k);
+            return nonce;
+        }
+        k = hmac(&k, &[&v, &[0x00]]);
+        v = hmac(&k, &[&v]);
+    }
+}
+
+/// The challenge, the first 16 bytes of SHA-256(0xFE || 0x02 || H || Gamma || U || V)
+fn challenge(points: [&Point<SECP256K1>; 4]) -> Scalar<SECP256K1> {
+    let encoded = points.map(|point| point.to_compressed());
+    let data = [&[SUITE, 0x02], encoded.concat().as_slice()].concat();
+    let hash = HashFunction::Sha256.digest(&data);
+    Scalar::reduce(&RU256::from_bytes(&hash[..CHALLENGE_SIZE]))
+}
+
+/// VRF proof for an input
+/// returns None for a zero private key, which would give the identity as Gamma,
+/// or if the input doesn't hash to a point
+pub fn prove(private_key: &PrivateKey, alpha: &[u8]) -> Option<Proof> {
+    let x = private_key.expose_secret();
+    if x.is_zero() {
+        return None;
+    }
+    let public_key = SECP256K1::public_key(x);
+    let h = encode_to_curve(&public_key, alpha)?;
+    let gamma = SECP256K1::ct_scalar_multiplication(x, &h);
+
+    let nonce = nonce(private_key, &h);
+    let k = nonce.expose_secret();
+    let u = SECP256K1::public_key(k);
+    let v = SECP256K1::ct_scalar_multiplication(k, &h);
+    let c = challenge([&h, &gamma, &u, &v]);
+    let s = k + &(&c * x);
+    Some(Proof { gamma, c, s })
+}
+
+/// Check a proof for an input against a public key
+/// returns the VRF output if the proof is valid
+pub fn verify(public_key: &Point<SECP256K1>, alpha: &[u8], proof: &Proof) -> Option<[u8; 32]> {
+    if public_key.is_zero_point() || !SECP256K1::is_on_curve(public_key) {
+        return None;
+    }
+    let h = encode_to_curve(public_key, alpha)?;
+
+    // U = s * G - c * Y and V = s * H - c * Gamma, the commitments of the prover
+    let minus_c = -&proof.c;
+    let u = multi_scalar_multiplication(
+        &[proof.s.clone(), minus_c.clone()],
+        &[SECP256K1::g(), public_key.clone()],
+    );
+    let v = multi_scalar_multiplication(
+        &[proof.s.clone(), minus_c],
+        &[h.clone(), proof.gamma.clone()],
+    );
+    (challenge([&h, &proof.gamma, &u, &v]) == proof.c).then(|| proof_to_hash(proof))
+}
+
+/// The VRF output of a proof, SHA-256(0xFE || 0x03 || Gamma)
+/// only meaningful for a proof that passed verify
+pub fn proof_to_hash(proof: &Proof) -> [u8; 32] {
+    let data = [&[SUITE, 0x03], proof.gamma.to_compressed().as_slice()].concat();
+    HashFunction::Sha256.digest(&data).try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::ecvrf::{proof_to_hash, prove, verify, Proof, PROOF_SIZE};
+    use crate::ru256::RU256;
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+    use std::str::FromStr;
+
+    #[test]
+    fn ecvrf_witnet_test_vectors() {
+        // see: https://github.com/witnet/vrf-rs/blob/master/src/openssl/mod.rs
+        let private_key = PrivateKey::from_str(
+            "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        )
+        .unwrap();
+        let public_key = Point::from_compressed(
+            &hex::decode("032c8c31fc9f990c6b55e3865a184a4ce50e09481f2eaeb3e60ec1cea13a6ae645")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            SECP256K1::public_key(private_key.expose_secret()),
+            public_key
+        );
+        let beta = hex::decode("612065e309e937ef46c2ef04d5886b9c6efd2991ac484ec64a9b014366fc5d81")
+            .unwrap();
+
+        // test_prove_secp256k1_sha256_tai
+        let proof = prove(&private_key, b"sample").unwrap();
+        assert_eq!(
+            hex::encode(proof.to_bytes()),
+            "031f4dbca087a1972d04a07a779b7df1caa99e0f5db2aa21f3aecc4f9e10e85d08748c9fbe6b95d17359707bfb8e8ab0c93ba0c515333adcb8b64f372c535e115ccf66ebf5abe6fadb01b5efb37c0a0ec9"
+        );
+        assert_eq!(
+            verify(&public_key, b"sample", &proof).unwrap().to_vec(),
+            beta
+        );
+        assert_eq!(proof_to_hash(&proof).to_vec(), beta);
+
+        // test_verify_secp256k1_sha256_tai, a proof made with another nonce
+        // for the same input has another (c, s) but the same Gamma and output
+        let other = Proof::from_bytes(&hex::decode("031f4dbca087a1972d04a07a779b7df1caa99e0f5db2aa21f3aecc4f9e10e85d0814faa89697b482daa377fb6b4a8b0191a65d34a6d90a8a2461e5db9205d4cf0bb4b2c31b5ef6997a585a9f1a72517b6f").unwrap()).unwrap();
+        assert_eq!(
+            verify(&public_key, b"sample", &other).unwrap().to_vec(),
+            beta
+        );
+
+        // test_verify_secp256k1_sha256_tai_bad_message, the proof there is
+        // truncated to 66 bytes so it already fails to decode
+        assert!(verify(&public_key, b"notsample", &proof).is_none());
+        let truncated = hex::decode("031f4dbca087a1972d04a07a779b7df1caa99e0f5db2aa21f3aecc4f9e10e85d0800851b42ee92f76d98c1f19e4a1e855526b20afe0dd6eb232a493adc107eb2b0f1").unwrap();
+        assert!(Proof::from_bytes(&truncated).is_none());
+    }
+
+    #[test]
+    fn ecvrf_tampered_proofs() {
+        let private_key = PrivateKey::from_bytes(&[0x42; 32]).unwrap();
+        let public_key = SECP256K1::public_key(private_key.expose_secret());
+        let alpha = b"leader election, round 7";
+
+        // deterministic, and the output is bound to the key and the input
+        let proof = prove(&private_key, alpha).unwrap();
+        assert_eq!(prove(&private_key, alpha).unwrap(), proof);
+        let beta = verify(&public_key, alpha, &proof).unwrap();
+        assert_eq!(Proof::from_bytes(&proof.to_bytes()), Some(proof.clone()));
+        assert_ne!(
+            proof_to_hash(&prove(&private_key, b"round 8").unwrap()),
+            beta
+        );
+        let other_key = PrivateKey::from_bytes(&[0x43; 32]).unwrap();
+        assert_ne!(proof_to_hash(&prove(&other_key, alpha).unwrap()), beta);
+
+        // a zero key can't prove, Gamma would be the identity
+        assert!(prove(&PrivateKey::new(&RU256::zero()), alpha).is_none());
+
+        // wrong input or key
+        assert!(verify(&public_key, b"leader election, round 8", &proof).is_none());
+        let other_public_key = SECP256K1::public_key(other_key.expose_secret());
+        assert!(verify(&other_public_key, alpha, &proof).is_none());
+        assert!(verify(&SECP256K1::zero_point(), alpha, &proof).is_none());
+
+        // flipping any bit of c or s, or swapping Gamma for another point, breaks the proof
+        let bytes = proof.to_bytes();
+        for index in [33, 40, 48, 49, 64, 80] {
+            let mut tampered = bytes;
+            tampered[index] ^= 1;
+            if let Some(tampered) = Proof::from_bytes(&tampered) {
+                assert!(verify(&public_key, alpha, &tampered).is_none());
+            }
+        }
+        let mut tampered = proof.clone();
+        tampered.gamma = SECP256K1::g();
+        assert!(verify(&public_key, alpha, &tampered).is_none());
+
+        // malformed encodings
+        assert!(Proof::from_bytes(&[0; 16]).is_none());
+        assert!(Proof::from_bytes(&bytes[..PROOF_SIZE - 1]).is_none());
+        let mut high_s = bytes;
+        high_s[49..].copy_from_slice(&[0xff; 32]);
+        assert!(Proof::from_bytes(&high_s).is_none());
+        let mut bad_gamma = bytes;
+        bad_gamma[0] = 0x04;
+        assert!(Proof::from_bytes(&bad_gamma).is_none());
+    }
+}
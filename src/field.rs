@@ -0,0 +1,433 @@
+use crate::curve::Curve;
+use crate::ruint::ModInt;
+use crate::secp256k1::SECP256K1;
+use crate::secret::wipe;
+use rand::Rng;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// The curve works with numbers under two different moduli
+// the coordinates of points live in the base field (mod p)
+// while private keys, nonces and signatures live in the scalar field (mod n)
+// mixing the two up gives wrong results that are hard to spot
+// so each gets its own type that knows its modulus, and the only
+// way to move a number between them is through an explicit reduce
+// both are tied to a curve as well, so numbers from different curves don't mix either
+
+/// Element of a curve's base field (mod p)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldElement<C: Curve = SECP256K1> {
+    v: C::Uint,
+    curve: PhantomData<C>,
+}
+
+/// Element of a curve's scalar field (mod n)
+/// arithmetic on scalars is constant time as they are usually secret
+/// and for the same reason they are never printed
+///
+/// scalars are Clone for the public ones in signatures, proofs and protocol
+/// messages, secret ones live in PrivateKey and Nonce which are not
+#[derive(Clone)]
+pub struct Scalar<C: Curve = SECP256K1> {
+    v: C::Uint,
+    curve: PhantomData<C>,
+}
+
+impl<C: Curve> FieldElement<C> {
+    /// The field prime
+    pub fn modulus() -> C::Uint {
+        C::p()
+    }
+
+    /// Wrap a number that is already less than p
+    fn from_reduced(v: C::Uint) -> Self {
+        Self {
+            v,
+            curve: PhantomData,
+        }
+    }
+
+    /// Field element from any number, reduced mod p
+    pub fn reduce(value: &C::Uint) -> Self {
+        Self::from_reduced(value.reduce(&Self::modulus()))
+    }
+
+    /// The underlying number, always less than p
+    pub fn value(&self) -> &C::Uint {
+        &self.v
+    }
+
+    /// Additive Identity
+    pub fn zero() -> Self {
+        Self::from_reduced(C::Uint::zero())
+    }
+
+    /// Multiplicative Identity
+    pub fn one() -> Self {
+        Self::from_reduced(C::Uint::one())
+    }
+
+    /// Check if additive identity
+    pub fn is_zero(&self) -> bool {
+        self.v.is_zero()
+    }
+
+    /// Multiplicative inverse
+    pub fn invert(&self) -> Self {
+        // Fermat's little theorem, see: div_mod in ru256.rs
+        self.pow(&Self::modulus().checked_sub(&C::Uint::from_u64(2)))
+    }
+
+    /// Raise to the power e
+    pub fn pow(&self, e: &C::Uint) -> Self {
+        // square-multiply, see: exp_mod in ru256.rs
+        // written in terms of field multiplication so it picks up
+        // the curve's own reduction (see: Curve::field_mul)
+        let mut result = Self::one();
+        let mut multiplier = self.clone();
+
+        for i in 0..e.bits() {
+            if e.bit(i) {
+                result = &result * &multiplier;
+            }
+            multiplier = &multiplier * &multiplier;
+        }
+
+        result
+    }
+
+    /// Square root, if there is one
+    /// the other root is its negation
+    pub fn sqrt(&self) -> Option<Self> {
+        // every curve in the crate has p = 3 mod 4, where the root
+        // is simply self^((p + 1) / 4)
+        // see: https://en.wikipedia.org/wiki/Quadratic_residue#Prime_or_prime_power_modulus
+        let p = Self::modulus();
+        debug_assert!(p.limbs()[0] & 3 == 3);
+        let exponent = p.shr(2).add_mod(&C::Uint::one(), &p);
+
+        // half the field elements have no root, squaring back tells them apart
+        let root = self.pow(&exponent);
+        if &root * &root == *self {
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    /// Check if the underlying number is even
+    pub fn is_even(&self) -> bool {
+        !self.v.bit(0)
+    }
+
+    /// Big endian bytes, as many as p takes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; Self::modulus().bits().div_ceil(8)];
+        self.v.to_bytes(&mut bytes);
+        bytes
+    }
+}
+
+impl<C: Curve> Scalar<C> {
+    /// The group order
+    pub fn modulus() -> C::Uint {
+        C::n()
+    }
+
+    /// Wrap a number that is already less than n
+    fn from_reduced(v: C::Uint) -> Self {
+        Self {
+            v,
+            curve: PhantomData,
+        }
+    }
+
+    /// Scalar from any number, reduced mod n
+    pub fn reduce(value: &C::Uint) -> Self {
+        // a * 1 mod n, any value times a reduced one is in range
+        Self::from_reduced(value.ct_mul_mod_with(&C::Uint::one(), &C::N_MONTGOMERY))
+    }
+
+    /// The underlying number, always less than n
+    pub fn value(&self) -> &C::Uint {
+        &self.v
+    }
+
+    /// Additive Identity
+    pub fn zero() -> Self {
+        Self::from_reduced(C::Uint::zero())
+    }
+
+    /// Multiplicative Identity
+    pub fn one() -> Self {
+        Self::from_reduced(C::Uint::one())
+    }
+
+    /// Check if additive identity
+    pub fn is_zero(&self) -> bool {
+        self.v.ct_eq(&C::Uint::zero()).is_true()
+    }
+
+    /// Multiplicative inverse
+    pub fn invert(&self) -> Self {
+        Self::one() / self
+    }
+
+    /// Big endian bytes, as many as n takes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; Self::modulus().bits().div_ceil(8)];
+        self.v.to_bytes(&mut bytes);
+        bytes
+    }
+
+    /// Hex digits of the value, zero padded to the width of the integer type
+    /// Scalar has no Display, so a secret one is only printed on purpose
+    /// through expose_secret
+    pub fn to_hex(&self) -> String {
+        self.v.to_string()
+    }
+
+    /// Overwrite with a uniformly random non-zero scalar, in place
+    pub(crate) fn randomize(&mut self) {
+        // rejection sampling: reducing a random 256 bit number mod n
+        // would make small scalars slightly more likely
+        // only as many random bits as n has, otherwise with wide integers
+        // almost every sample would be rejected
+        let n = Self::modulus();
+        let bits = n.bits();
+        let mut rng = rand::thread_rng();
+        loop {
+            rng.fill(self.v.limbs_mut());
+            for (i, limb) in self.v.limbs_mut().iter_mut().enumerate() {
+                if 64 * i >= bits {
+                    *limb = 0;
+                } else if 64 * (i + 1) > bits {
+                    *limb &= u64::MAX >> (64 * (i + 1) - bits);
+                }
+            }
+            if self.v.ct_lt(&n).is_true() && !self.is_zero() {
+                return;
+            }
+        }
+    }
+
+    /// Overwrite with zeros, see: wipe in secret.rs
+    pub(crate) fn wipe(&mut self) {
+        wipe(&mut self.v);
+    }
+}
+
+impl<C: Curve> PartialEq for Scalar<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.v.ct_eq(&other.v).is_true()
+    }
+}
+
+impl<C: Curve> fmt::Debug for Scalar<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Scalar([REDACTED])")
+    }
+}
+
+impl<C: Curve> fmt::Display for FieldElement<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.v.fmt(f)
+    }
+}
+
+// implements a binary operator for every owned / borrowed combination
+// in terms of the borrowed one, which calls the given RU256 method
+// with the type's modulus
+macro_rules! impl_binary_op {
+    ($ty:ident, $op:ident, $op_fn:ident, $ru256_fn:ident) => {
+        impl<C: Curve> $op<&$ty<C>> for &$ty<C> {
+            type Output = $ty<C>;
+
+            fn $op_fn(self, rhs: &$ty<C>) -> $ty<C> {
+                $ty::from_reduced(self.v.$ru256_fn(&rhs.v, &$ty::<C>::modulus()))
+            }
+        }
+
+        forward_binary_op!($ty, $op, $op_fn);
+    };
+}
+
+// implements the owned combinations of a binary operator
+// in terms of an existing borrowed one
+macro_rules! forward_binary_op {
+    ($ty:ident, $op:ident, $op_fn:ident) => {
+        impl<C: Curve> $op<$ty<C>> for $ty<C> {
+            type Output = $ty<C>;
+
+            fn $op_fn(self, rhs: $ty<C>) -> $ty<C> {
+                (&self).$op_fn(&rhs)
+            }
+        }
+
+        impl<C: Curve> $op<&$ty<C>> for $ty<C> {
+            type Output = $ty<C>;
+
+            fn $op_fn(self, rhs: &$ty<C>) -> $ty<C> {
+                (&self).$op_fn(rhs)
+            }
+        }
+
+        impl<C: Curve> $op<$ty<C>> for &$ty<C> {
+            type Output = $ty<C>;
+
+            fn $op_fn(self, rhs: $ty<C>) -> $ty<C> {
+                self.$op_fn(&rhs)
+            }
+        }
+    };
+}
+
+// field elements are public coordinates, use the fast operations
+// multiplication goes through the curve so it can use its own reduction
+impl_binary_op!(FieldElement, Add, add, add_mod);
+impl_binary_op!(FieldElement, Sub, sub, sub_mod);
+
+impl<C: Curve> Mul<&FieldElement<C>> for &FieldElement<C> {
+    type Output = FieldElement<C>;
+
+    fn mul(self, rhs: &FieldElement<C>) -> FieldElement<C> {
+        FieldElement::from_reduced(C::field_mul(&self.v, &rhs.v))
+    }
+}
+
+forward_binary_op!(FieldElement, Mul, mul);
+
+impl<C: Curve> Div<&FieldElement<C>> for &FieldElement<C> {
+    type Output = FieldElement<C>;
+
+    fn div(self, rhs: &FieldElement<C>) -> FieldElement<C> {
+        FieldElement::from_reduced(C::field_mul(&self.v, &rhs.invert().v))
+    }
+}
+
+forward_binary_op!(FieldElement, Div, div);
+
+// scalars hold secrets, use the constant time operations
+impl_binary_op!(Scalar, Add, add, ct_add_mod);
+impl_binary_op!(Scalar, Sub, sub, ct_sub_mod);
+
+// n is fixed per curve, multiplication and division use its precomputed constants
+impl<C: Curve> Mul<&Scalar<C>> for &Scalar<C> {
+    type Output = Scalar<C>;
+
+    fn mul(self, rhs: &Scalar<C>) -> Scalar<C> {
+        Scalar::from_reduced(self.v.ct_mul_mod_with(&rhs.v, &C::N_MONTGOMERY))
+    }
+}
+
+forward_binary_op!(Scalar, Mul, mul);
+
+impl<C: Curve> Div<&Scalar<C>> for &Scalar<C> {
+    type Output = Scalar<C>;
+
+    fn div(self, rhs: &Scalar<C>) -> Scalar<C> {
+        Scalar::from_reduced(self.v.ct_div_mod_with(&rhs.v, &C::N_MONTGOMERY))
+    }
+}
+
+forward_binary_op!(Scalar, Div, div);
+
+impl<C: Curve> Neg for &FieldElement<C> {
+    type Output = FieldElement<C>;
+
+    fn neg(self) -> FieldElement<C> {
+        &FieldElement::zero() - self
+    }
+}
+
+impl<C: Curve> Neg for FieldElement<C> {
+    type Output = FieldElement<C>;
+
+    fn neg(self) -> FieldElement<C> {
+        -&self
+    }
+}
+
+impl<C: Curve> Neg for &Scalar<C> {
+    type Output = Scalar<C>;
+
+    fn neg(self) -> Scalar<C> {
+        &Scalar::zero() - self
+    }
+}
+
+impl<C: Curve> Neg for Scalar<C> {
+    type Output = Scalar<C>;
+
+    fn neg(self) -> Scalar<C> {
+        -&self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::field::{FieldElement, Scalar};
+    use crate::ru256::RU256;
+    use crate::secp256k1::SECP256K1;
+    use std::str::FromStr;
+
+    #[test]
+    fn field_element_arithmetic() {
+        let p = FieldElement::<SECP256K1>::modulus();
+        let a: FieldElement = FieldElement::reduce(
+            &RU256::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2E")
+                .unwrap(),
+        );
+        let b = FieldElement::reduce(&RU256::from_str("0xacc12484").unwrap());
+
+        assert_eq!((&a + &b).value(), &a.value().add_mod(b.value(), &p));
+        assert_eq!((&a - &b).value(), &a.value().sub_mod(b.value(), &p));
+        assert_eq!((&a * &b).value(), &a.value().mul_mod(b.value(), &p));
+        assert_eq!((&a / &b).value(), &a.value().div_mod(b.value(), &p));
+
+        assert_eq!(&a + &(-&a), FieldElement::zero());
+        assert_eq!(&b * &b.invert(), FieldElement::one());
+        assert_eq!(b.pow(&RU256::from_str("0x2").unwrap()), &b * &b);
+
+        // b^2 has the roots b and -b, while -b^2 has none (p = 3 mod 4)
+        let root = (&b * &b).sqrt().unwrap();
+        assert!(root == b || root == -&b);
+        assert!((-(&b * &b)).sqrt().is_none());
+        assert_eq!(b.to_bytes().len(), 32);
+        assert_eq!(&b.to_bytes()[28..], &[0xac, 0xc1, 0x24, 0x84]);
+        assert!(b.is_even());
+
+        // p reduces to zero
+        assert!(FieldElement::<SECP256K1>::reduce(&p).is_zero());
+    }
+
+    #[test]
+    fn scalar_arithmetic() {
+        let n = Scalar::<SECP256K1>::modulus();
+        let a: Scalar = Scalar::reduce(
+            &RU256::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364140")
+                .unwrap(),
+        );
+        let b = Scalar::reduce(&RU256::from_str("0x1ce606").unwrap());
+
+        assert_eq!((&a + &b).value(), &a.value().add_mod(b.value(), &n));
+        assert_eq!((&a - &b).value(), &a.value().sub_mod(b.value(), &n));
+        assert_eq!((&a * &b).value(), &a.value().mul_mod(b.value(), &n));
+        assert_eq!((&a / &b).value(), &a.value().div_mod(b.value(), &n));
+
+        // a = n - 1 = -1
+        assert_eq!(-&a, Scalar::one());
+        assert_eq!(&b * &b.invert(), Scalar::one());
+
+        // n reduces to zero
+        assert!(Scalar::<SECP256K1>::reduce(&n).is_zero());
+
+        let mut r: Scalar = Scalar::zero();
+        r.randomize();
+        assert!(!r.is_zero());
+        assert!(r.value().ct_lt(&n).is_true());
+        r.wipe();
+        assert!(r.is_zero());
+    }
+}
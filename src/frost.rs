@@ -0,0 +1,926 @@
+use crate::curve::{Curve, Point};
+use crate::field::Scalar;
+use crate::hash::tagged_hash;
+use crate::msm::multi_scalar_multiplication;
+use crate::ru256::RU256;
+use crate::ruint::ModInt;
+use crate::schnorr::{challenge, verify, Signature, XOnlyPublicKey};
+use crate::secp256k1::SECP256K1;
+use crate::secret::{Nonce, PrivateKey};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+
+// FROST threshold Schnorr signatures, see: https://www.rfc-editor.org/rfc/rfc9591
+// any t of the n participants can sign for the group key, fewer than t learn
+// nothing about it, and the result is an ordinary BIP-340 signature
+//
+// the RFC's secp256k1 ciphersuite is adapted the same way MuSig2 does it:
+// - the challenge is the BIP-340 one, over the x coordinates of R and the group key
+// - signers negate their nonces if R has odd y, and their shares if the group key has
+// - the other hashes are BIP-340 style tagged hashes reduced mod n instead of
+//   hash_to_field, n is so close to 2^256 the bias doesn't matter
+//
+// key generation is the Pedersen DKG from the FROST paper (RFC 9591 appendix C
+// describes the trusted dealer alternative), so no one ever holds the group secret:
+// 1. every participant picks a random polynomial of degree t - 1, broadcasts
+//    commitments to its coefficients and a proof of knowledge of the constant term
+// 2. every participant sends each other one their polynomial evaluated at their identifier
+// 3. every participant checks the shares against the commitments, and sums them
+//    into their signing share, the group secret is the sum of the constant terms
+//
+// participants are identified by non-zero numbers, which are the points their
+// shares are evaluated at
+
+/// Errors of the FROST protocol, naming the participant at fault where there is one
+#[derive(Debug, PartialEq, Eq)]
+pub enum FrostError {
+    /// The threshold is below 2 or above the number of participants
+    InvalidThreshold,
+    /// An identifier is zero
+    InvalidIdentifier,
+    /// The packages don't come from the expected participants
+    UnexpectedParticipants,
+    /// A participant's proof of knowledge of their secret doesn't verify
+    InvalidProofOfKnowledge { participant: u16 },
+    /// A participant sent a secret share that doesn't match their commitments
+    InvalidSecretShare { participant: u16 },
+    /// Fewer signers than the threshold
+    NotEnoughSigners,
+    /// The signer's commitments are missing from the signing package, or
+    /// don't match their nonces
+    SignerNotIncluded,
+    /// The nonce commitments add up to the point at infinity
+    PointAtInfinity,
+    /// Signature shares that don't verify, the signature couldn't be aggregated
+    InvalidSignatureShares { participants: Vec<u16> },
+}
+
+/// Identifier as a scalar, the point a participant's share is evaluated at
+fn identifier_scalar(identifier: u16) -> Scalar<SECP256K1> {
+    Scalar::reduce(&RU256::from_u64(identifier as u64))
+}
+
+/// Scalar from a hash, reduced mod n
+fn hash_to_scalar(hash: &[u8; 32]) -> Scalar<SECP256K1> {
+    Scalar::reduce(&RU256::from_bytes(hash))
+}
+
+/// Evaluate a committed polynomial at x: C_0 + x * C_1 + x^2 * C_2 + ...
+fn evaluate_commitment(commitment: &[Point<SECP256K1>], x: &Scalar<SECP256K1>) -> Point<SECP256K1> {
+    let mut powers = vec![Scalar::one()];
+    for i in 1..commitment.len() {
+        powers.push(&powers[i - 1] * x);
+    }
+    multi_scalar_multiplication(&powers, commitment)
+}
+
+/// Lagrange coefficient of a signer for interpolating at 0
+pub(crate) fn lagrange_coefficient(identifier: u16, signers: &[u16]) -> Scalar<SECP256K1> {
+    // λ_i = ∏ x_j / (x_j - x_i) over the other signers j
+    let x_i = identifier_scalar(identifier);
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for x_j in signers.iter().filter(|j| **j != identifier) {
+        let x_j = identifier_scalar(*x_j);
+        numerator = &numerator * &x_j;
+        denominator = &denominator * &(&x_j - &x_i);
+    }
+    &numerator * &denominator.invert()
+}
+
+/// A participant's random polynomial, kept between the DKG rounds
+/// wiped from memory when dropped and never printed
+pub struct DkgSecret {
+    identifier: u16,
+    min_signers: u16,
+    max_signers: u16,
+    /// coefficients a_0 ... a_(t-1), a_0 is the participant's part of the group secret
+    coefficients: Vec<Scalar<SECP256K1>>,
+    commitment: Vec<Point<SECP256K1>>,
+}
+
+impl DkgSecret {
+    /// The polynomial evaluated at x
+    fn evaluate(&self, x: &Scalar<SECP256K1>) -> Scalar<SECP256K1> {
+        // Horner's rule from the highest coefficient down
+        let mut value = Scalar::zero();
+        for coefficient in self.coefficients.iter().rev() {
+            value = &(&value * x) + coefficient;
+        }
+        value
+    }
+}
+
+impl Drop for DkgSecret {
+    fn drop(&mut self) {
+        for coefficient in self.coefficients.iter_mut() {
+            coefficient.wipe();
+        }
+    }
+}
+
+impl fmt::Debug for DkgSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DkgSecret([REDACTED])")
+    }
+}
+
+/// What a participant broadcasts in the first DKG round
+#[derive(Clone, Debug, PartialEq)]
+pub struct DkgCommitment {
+    /// commitments to the polynomial's coefficients, a_i * G
+    commitment: Vec<Point<SECP256K1>>,
+    /// Schnorr proof of knowledge of a_0, so no one can pick their commitment
+    /// as a function of the others' to cancel them out of the group key
+    proof_r: Point<SECP256K1>,
+    proof_mu: Scalar<SECP256K1>,
+}
+
+/// A secret share sent from one participant to another in the second DKG round
+/// wiped from memory when dropped and never printed
+#[derive(Debug)]
+pub struct SecretShare {
+    value: PrivateKey,
+}
+
+/// Challenge of the proof of knowledge, bound to the participant
+fn dkg_challenge(
+    identifier: u16,
+    constant: &Point<SECP256K1>,
+    r: &Point<SECP256K1>,
+) -> Scalar<SECP256K1> {
+    hash_to_scalar(&tagged_hash(
+        "FROST/dkg",
+        &[
+            &identifier_scalar(identifier).to_bytes(),
+            &constant.to_compressed(),
+            &r.to_compressed(),
+        ],
+    ))
+}
+
+/// First DKG round, returns the secret to keep and the commitment to broadcast
+/// with max_signers participants any min_signers of them can sign
+pub fn dkg_part1(
+    identifier: u16,
+    max_signers: u16,
+    min_signers: u16,
+) -> Result<(DkgSecret, DkgCommitment), FrostError> {
+    if min_signers < 2 || min_signers > max_signers {
+        return Err(FrostError::InvalidThreshold);
+    }
+    if identifier == 0 {
+        return Err(FrostError::InvalidIdentifier);
+    }
+
+    let coefficients: Vec<Scalar<SECP256K1>> = (0..min_signers)
+        .map(|_| {
+            let mut coefficient = Scalar::zero();
+            coefficient.randomize();
+            coefficient
+        })
+        .collect();
+    let commitment: Vec<Point<SECP256K1>> =
+        coefficients.iter().map(SECP256K1::public_key).collect();
+
+    // mu = k + a_0 * c, for R = k * G
+    let k = Nonce::<SECP256K1>::random();
+    let proof_r = SECP256K1::public_key(k.expose_secret());
+    let c = dkg_challenge(identifier, &commitment[0], &proof_r);
+    let proof_mu = k.expose_secret() + &(&coefficients[0] * &c);
+
+    let secret = DkgSecret {
+        identifier,
+        min_signers,
+        max_signers,
+        coefficients,
+        commitment: commitment.clone(),
+    };
+    let package = DkgCommitment {
+        commitment,
+        proof_r,
+        proof_mu,
+    };
+    Ok((secret, package))
+}
+
+/// Check that the commitments come from exactly the other participants
+/// and are for the agreed threshold
+fn check_commitments(
+    secret: &DkgSecret,
+    commitments: &BTreeMap<u16, DkgCommitment>,
+) -> Result<(), FrostError> {
+    if commitments.len() != secret.max_signers as usize - 1
+        || commitments.contains_key(&secret.identifier)
+    {
+        return Err(FrostError::UnexpectedParticipants);
+    }
+    for (identifier, package) in commitments {
+        if *identifier == 0 {
+            return Err(FrostError::InvalidIdentifier);
+        }
+        if package.commitment.len() != secret.min_signers as usize {
+            return Err(FrostError::InvalidProofOfKnowledge {
+                participant: *identifier,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Second DKG round, checks the other participants' proofs of knowledge
+/// and returns the secret share to send to each of them
+pub fn dkg_part2(
+    secret: &DkgSecret,
+    commitments: &BTreeMap<u16, DkgCommitment>,
+) -> Result<BTreeMap<u16, SecretShare>, FrostError> {
+    check_commitments(secret, commitments)?;
+
+    // mu * G = R + c * C_0
+    for (identifier, package) in commitments {
+        let c = dkg_challenge(*identifier, &package.commitment[0], &package.proof_r);
+        let expected = multi_scalar_multiplication(
+            &[Scalar::one(), c],
+            &[package.proof_r.clone(), package.commitment[0].clone()],
+        );
+        if SECP256K1::public_key(&package.proof_mu) != expected {
+            return Err(FrostError::InvalidProofOfKnowledge {
+                participant: *identifier,
+            });
+        }
+    }
+
+    Ok(commitments
+        .keys()
+        .map(|identifier| {
+            let mut value = secret.evaluate(&identifier_scalar(*identifier));
+            let share = SecretShare {
+                value: PrivateKey::new(value.value()),
+            };
+            value.wipe();
+            (*identifier, share)
+        })
+        .collect())
+}
+
+/// Third DKG round, checks the shares received from the other participants
+/// and sums them into this participant's signing share
+pub fn dkg_part3(
+    secret: DkgSecret,
+    commitments: &BTreeMap<u16, DkgCommitment>,
+    shares: &BTreeMap<u16, SecretShare>,
+) -> Result<(KeyPackage, PublicKeyPackage), FrostError> {
+    check_commitments(&secret, commitments)?;
+    if shares.len() != commitments.len() || !shares.keys().eq(commitments.keys()) {
+        return Err(FrostError::UnexpectedParticipants);
+    }
+
+    // the share from participant l is f_l(i), which has to match their
+    // commitments evaluated at i, and the signing share is the sum of them all
+    let x = identifier_scalar(secret.identifier);
+    let mut signing_share = secret.evaluate(&x);
+    for (identifier, share) in shares {
+        let expected = evaluate_commitment(&commitments[identifier].commitment, &x);
+        if SECP256K1::public_key(share.value.expose_secret()) != expected {
+            signing_share.wipe();
+            return Err(FrostError::InvalidSecretShare {
+                participant: *identifier,
+            });
+        }
+        signing_share = &signing_share + share.value.expose_secret();
+    }
+
+    // summing the commitments coefficient by coefficient gives commitments to
+    // the group polynomial, which the group key and everyone's verifying
+    // share can be read off
+    let mut group_commitment: Vec<Vec<Point<SECP256K1>>> =
+        vec![vec![]; secret.min_signers as usize];
+    for commitment in commitments
+        .values()
+        .map(|package| &package.commitment)
+        .chain([&secret.commitment])
+    {
+        for (k, point) in commitment.iter().enumerate() {
+            group_commitment[k].push(point.clone());
+        }
+    }
+    let group_commitment: Vec<Point<SECP256K1>> = group_commitment
+        .iter()
+        .map(|points| multi_scalar_multiplication(&vec![Scalar::one(); points.len()], points))
+        .collect();
+
+    let group_public_key = group_commitment[0].clone();
+    let verifying_shares: BTreeMap<u16, Point<SECP256K1>> = commitments
+        .keys()
+        .chain([&secret.identifier])
+        .map(|identifier| {
+            let share = evaluate_commitment(&group_commitment, &identifier_scalar(*identifier));
+            (*identifier, share)
+        })
+        .collect();
+
+    let key_package = KeyPackage {
+        identifier: secret.identifier,
+        signing_share: PrivateKey::new(signing_share.value()),
+        verifying_share: verifying_shares[&secret.identifier].clone(),
+        group_public_key: group_public_key.clone(),
+        min_signers: secret.min_signers,
+    };
+    signing_share.wipe();
+
+    let public_key_package = PublicKeyPackage {
+        verifying_shares,
+        group_public_key,
+        min_signers: secret.min_signers,
+    };
+    Ok((key_package, public_key_package))
+}
+
+/// A participant's signing share along with the group's public data
+#[derive(Debug)]
+pub struct KeyPackage {
+    pub(crate) identifier: u16,
+    pub(crate) signing_share: PrivateKey,
+    pub(crate) verifying_share: Point<SECP256K1>,
+    pub(crate) group_public_key: Point<SECP256K1>,
+    pub(crate) min_signers: u16,
+}
+
+impl KeyPackage {
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// s_i * G for this participant's signing share s_i
+    pub fn verifying_share(&self) -> &Point<SECP256K1> {
+        &self.verifying_share
+    }
+}
+
+/// Public data of the group, everything needed to check signature shares
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicKeyPackage {
+    /// s_i * G for every participant's signing share s_i
+    pub(crate) verifying_shares: BTreeMap<u16, Point<SECP256K1>>,
+    pub(crate) group_public_key: Point<SECP256K1>,
+    pub(crate) min_signers: u16,
+}
+
+impl PublicKeyPackage {
+    /// Group key as a BIP-340 public key, which the signatures verify for
+    pub fn x_only_public_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(&self.group_public_key)
+    }
+}
+
+/// A signer's hiding and binding nonces for one signature
+/// wiped from memory when dropped and never printed
+///
+/// signing consumes them, signing twice with the same nonces gives the share away
+pub struct SigningNonces {
+    hiding: Nonce<SECP256K1>,
+    binding: Nonce<SECP256K1>,
+}
+
+impl fmt::Debug for SigningNonces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SigningNonces([REDACTED])")
+    }
+}
+
+/// Public commitments to a signer's nonces, D = d * G and E = e * G
+#[derive(Clone, Debug, PartialEq)]
+pub struct SigningCommitments {
+    hiding: Point<SECP256K1>,
+    binding: Point<SECP256K1>,
+}
+
+/// A nonce derived from fresh randomness and the signing share
+fn nonce_generate(signing_share: &PrivateKey) -> Nonce<SECP256K1> {
+    // mixing in the share keeps the nonce secret even if the rng is weak
+    let rand: [u8; 32] = rand::thread_rng().gen();
+    let hash = tagged_hash(
+        "FROST/nonce",
+        &[&rand, &signing_share.expose_secret().to_bytes()],
+    );
+    Nonce::new(&RU256::from_bytes(&hash))
+}
+
+/// First signing round, returns the nonces to keep and the commitments to send out
+pub fn commit(key_package: &KeyPackage) -> (SigningNonces, SigningCommitments) {
+    let nonces = SigningNonces {
+        hiding: nonce_generate(&key_package.signing_share),
+        binding: nonce_generate(&key_package.signing_share),
+    };
+    let commitments = SigningCommitments {
+        hiding: SECP256K1::public_key(nonces.hiding.expose_secret()),
+        binding: SECP256K1::public_key(nonces.binding.expose_secret()),
+    };
+    (nonces, commitments)
+}
+
+/// The message and the nonce commitments of the signers taking part
+#[derive(Clone, Debug)]
+pub struct SigningPackage {
+    commitments: BTreeMap<u16, SigningCommitments>,
+    message: Vec<u8>,
+}
+
+impl SigningPackage {
+    pub fn new(commitments: BTreeMap<u16, SigningCommitments>, message: &[u8]) -> Self {
+        SigningPackage {
+            commitments,
+            message: message.to_vec(),
+        }
+    }
+
+    /// Identifiers of the signers, in increasing order
+    fn signers(&self) -> Vec<u16> {
+        self.commitments.keys().copied().collect()
+    }
+}
+
+/// A signer's part of the signature, z_i
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureShare {
+    z: Scalar<SECP256K1>,
+}
+
+/// Values every signer and the aggregator derive from the signing package
+struct SigningContext {
+    /// binding factor of each signer, ρ_i
+    binding_factors: BTreeMap<u16, Scalar<SECP256K1>>,
+    /// group commitment R
+    r: Point<SECP256K1>,
+    /// BIP-340 challenge
+    c: Scalar<SECP256K1>,
+}
+
+impl SigningContext {
+    fn new(
+        signing_package: &SigningPackage,
+        group_public_key: &Point<SECP256K1>,
+    ) -> Result<Self, FrostError> {
+        // ρ_i binds each signer's nonces to the message and all the commitments,
+        // so the group commitment can't be steered by a signer who waits
+        // for everyone else's commitments
+        let message_hash = tagged_hash("FROST/msg", &[&signing_package.message]);
+        let encoded: Vec<Vec<u8>> = signing_package
+            .commitments
+            .iter()
+            .map(|(identifier, commitments)| {
+                [
+                    identifier_scalar(*identifier).to_bytes(),
+                    commitments.hiding.to_compressed(),
+                    commitments.binding.to_compressed(),
+                ]
+                .concat()
+            })
+            .collect();
+        let encoded: Vec<&[u8]> = encoded.iter().map(|item| &item[..]).collect();
+        let commitments_hash = tagged_hash("FROST/com", &encoded);
+        let group_public_key_bytes = group_public_key.to_compressed();
+
+        let binding_factors: BTreeMap<u16, Scalar<SECP256K1>> = signing_package
+            .commitments
+            .keys()
+            .map(|identifier| {
+                let hash = tagged_hash(
+                    "FROST/rho",
+                    &[
+                        &group_public_key_bytes,
+                        &message_hash,
+                        &commitments_hash,
+                        &identifier_scalar(*identifier).to_bytes(),
+                    ],
+                );
+                (*identifier, hash_to_scalar(&hash))
+            })
+            .collect();
+
+        // R = D_1 + ρ_1 * E_1 + D_2 + ρ_2 * E_2 + ...
+        let mut scalars = vec![];
+        let mut points = vec![];
+        for (identifier, commitments) in &signing_package.commitments {
+            scalars.push(Scalar::one());
+            points.push(commitments.hiding.clone());
+            scalars.push(binding_factors[identifier].clone());
+            points.push(commitments.binding.clone());
+        }
+        let r = multi_scalar_multiplication(&scalars, &points);
+        if r.is_zero_point() {
+            return Err(FrostError::PointAtInfinity);
+        }
+
+        let c = challenge(
+            &r.x.to_bytes(),
+            &XOnlyPublicKey::from_point(group_public_key).to_bytes(),
+            &signing_package.message,
+        );
+
+        Ok(SigningContext {
+            binding_factors,
+            r,
+            c,
+        })
+    }
+}
+
+/// Second signing round, consumes the nonces
+pub fn sign(
+    signing_package: &SigningPackage,
+    nonces: SigningNonces,
+    key_package: &KeyPackage,
+) -> Result<SignatureShare, FrostError> {
+    if signing_package.commitments.len() < key_package.min_signers as usize {
+        return Err(FrostError::NotEnoughSigners);
+    }
+    if signing_package.commitments.contains_key(&0) {
+        return Err(FrostError::InvalidIdentifier);
+    }
+    let own_commitments = SigningCommitments {
+        hiding: SECP256K1::public_key(nonces.hiding.expose_secret()),
+        binding: SECP256K1::public_key(nonces.binding.expose_secret()),
+    };
+    if signing_package.commitments.get(&key_package.identifier) != Some(&own_commitments) {
+        return Err(FrostError::SignerNotIncluded);
+    }
+
+    let context = SigningContext::new(signing_package, &key_package.group_public_key)?;
+    let lambda = lagrange_coefficient(key_package.identifier, &signing_package.signers());
+
+    // the signature is for R and the group key with even y, so the nonces
+    // and the share are negated whenever the points have odd y
+    let d = nonces.hiding.conditional_negate(!context.r.y.is_even());
+    let e = nonces.binding.conditional_negate(!context.r.y.is_even());
+    let s = key_package
+        .signing_share
+        .conditional_negate(!key_package.group_public_key.y.is_even());
+
+    // z_i = d_i + e_i * ρ_i + λ_i * s_i * c
+    let rho = &context.binding_factors[&key_package.identifier];
+    let z =
+        d.expose_secret() + &(e.expose_secret() * rho) + &lambda * s.expose_secret() * &context.c;
+
+    Ok(SignatureShare { z })
+}
+
+/// Check one signer's signature share against their verifying share
+fn verify_share(
+    identifier: u16,
+    share: &SignatureShare,
+    signing_package: &SigningPackage,
+    public_key_package: &PublicKeyPackage,
+    context: &SigningContext,
+) -> bool {
+    let (commitments, verifying_share) = match (
+        signing_package.commitments.get(&identifier),
+        public_key_package.verifying_shares.get(&identifier),
+    ) {
+        (Some(commitments), Some(verifying_share)) => (commitments, verifying_share),
+        _ => return false,
+    };
+
+    // z_i * G = R_i + λ_i * c * Y_i, with R_i = D_i + ρ_i * E_i
+    // and the same negations the signer applied
+    let nonce_sign = if context.r.y.is_even() {
+        Scalar::one()
+    } else {
+        -Scalar::one()
+    };
+    let key_sign = if public_key_package.group_public_key.y.is_even() {
+        Scalar::one()
+    } else {
+        -Scalar::one()
+    };
+    let lambda = lagrange_coefficient(identifier, &signing_package.signers());
+    let expected = multi_scalar_multiplication(
+        &[
+            nonce_sign.clone(),
+            &nonce_sign * &context.binding_factors[&identifier],
+            &key_sign * &lambda * &context.c,
+        ],
+        &[
+            commitments.hiding.clone(),
+            commitments.binding.clone(),
+            verifying_share.clone(),
+        ],
+    );
+
+    SECP256K1::public_key(&share.z) == expected
+}
+
+/// Check one signer's signature share, e.g. as it comes in
+pub fn verify_signature_share(
+    identifier: u16,
+    share: &SignatureShare,
+    signing_package: &SigningPackage,
+    public_key_package: &PublicKeyPackage,
+) -> bool {
+    match SigningContext::new(signing_package, &public_key_package.group_public_key) {
+        Ok(context) => verify_share(
+            identifier,
+            share,
+            signing_package,
+            public_key_package,
+            &context,
+        ),
+        Err(_) => false,
+    }
+}
+
+/// Sum the signature shares into the final BIP-340 signature
+/// if it doesn't verify, the shares are checked one by one to name the culprits
+pub fn aggregate(
+    signing_package: &SigningPackage,
+    shares: &BTreeMap<u16, SignatureShare>,
+    public_key_package: &PublicKeyPackage,
+) -> Result<Signature, FrostError> {
+    if signing_package.commitments.len() < public_key_package.min_signers as usize {
+        return Err(FrostError::NotEnoughSigners);
+    }
+    if shares.len() != signing_package.commitments.len()
+        || !shares.keys().eq(signing_package.commitments.keys())
+        || !shares
+            .keys()
+            .all(|identifier| public_key_package.verifying_shares.contains_key(identifier))
+    {
+        return Err(FrostError::UnexpectedParticipants);
+    }
+
+    let context = SigningContext::new(signing_package, &public_key_package.group_public_key)?;
+    let mut z = Scalar::zero();
+    for share in shares.values() {
+        z = &z + &share.z;
+    }
+    let signature = Signature {
+        r: context.r.x.clone(),
+        s: z,
+    };
+
+    // checking the signature as a whole is much cheaper than every share,
+    // so the shares are only looked at when it fails
+    if verify(
+        &signing_package.message,
+        &public_key_package.x_only_public_key(),
+        &signature,
+    ) {
+        return Ok(signature);
+    }
+
+    let participants = shares
+        .iter()
+        .filter(|(identifier, share)| {
+            !verify_share(
+                **identifier,
+                share,
+                signing_package,
+                public_key_package,
+                &context,
+            )
+        })
+        .map(|(identifier, _)| *identifier)
+        .collect();
+    Err(FrostError::InvalidSignatureShares { participants })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::curve::Curve;
+    use crate::field::Scalar;
+    use crate::frost::{
+        aggregate, commit, dkg_part1, dkg_part2, dkg_part3, lagrange_coefficient, sign,
+        verify_signature_share, DkgCommitment, DkgSecret, FrostError, KeyPackage, PublicKeyPackage,
+        SecretShare, SigningPackage,
+    };
+    use crate::schnorr::verify;
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+    use std::collections::BTreeMap;
+
+    /// Every participant's view of the first DKG round, without their own commitment
+    fn others(
+        commitments: &BTreeMap<u16, DkgCommitment>,
+        identifier: u16,
+    ) -> BTreeMap<u16, DkgCommitment> {
+        commitments
+            .iter()
+            .filter(|(other, _)| **other != identifier)
+            .map(|(other, commitment)| (*other, commitment.clone()))
+            .collect()
+    }
+
+    /// First DKG round for participants 1 to max_signers
+    fn dkg_round1(
+        max_signers: u16,
+        min_signers: u16,
+    ) -> (BTreeMap<u16, DkgSecret>, BTreeMap<u16, DkgCommitment>) {
+        (1..=max_signers)
+            .map(|identifier| {
+                let (secret, commitment) = dkg_part1(identifier, max_signers, min_signers).unwrap();
+                ((identifier, secret), (identifier, commitment))
+            })
+            .unzip()
+    }
+
+    /// Second DKG round, returns the shares received by each participant
+    fn dkg_round2(
+        secrets: &BTreeMap<u16, DkgSecret>,
+        commitments: &BTreeMap<u16, DkgCommitment>,
+    ) -> BTreeMap<u16, BTreeMap<u16, SecretShare>> {
+        let mut received: BTreeMap<u16, BTreeMap<u16, SecretShare>> = BTreeMap::new();
+        for (sender, secret) in secrets {
+            for (receiver, share) in dkg_part2(secret, &others(commitments, *sender)).unwrap() {
+                received.entry(receiver).or_default().insert(*sender, share);
+            }
+        }
+        received
+    }
+
+    /// A whole honest DKG run
+    pub(crate) fn dkg(max_signers: u16, min_signers: u16) -> (Vec<KeyPackage>, PublicKeyPackage) {
+        let (secrets, commitments) = dkg_round1(max_signers, min_signers);
+        let mut received = dkg_round2(&secrets, &commitments);
+
+        let results: Vec<(KeyPackage, PublicKeyPackage)> = secrets
+            .into_iter()
+            .map(|(identifier, secret)| {
+                let shares = received.remove(&identifier).unwrap();
+                dkg_part3(secret, &others(&commitments, identifier), &shares).unwrap()
+            })
+            .collect();
+
+        // everyone ends up with the same public data
+        let public_key_package = results[0].1.clone();
+        assert!(results
+            .iter()
+            .all(|(_, package)| package == &public_key_package));
+        let key_packages = results.into_iter().map(|(key, _)| key).collect();
+        (key_packages, public_key_package)
+    }
+
+    /// Run both signing rounds with the given signers, and aggregate
+    fn sign_with(
+        key_packages: &[KeyPackage],
+        public_key_package: &PublicKeyPackage,
+        signers: &[u16],
+        message: &[u8],
+        tamper: Option<u16>,
+    ) -> Result<crate::schnorr::Signature, FrostError> {
+        let signers: Vec<&KeyPackage> = key_packages
+            .iter()
+            .filter(|key| signers.contains(&key.identifier()))
+            .collect();
+        let (nonces, commitments): (Vec<_>, BTreeMap<_, _>) = signers
+            .iter()
+            .map(|key| {
+                let (nonces, commitments) = commit(key);
+                (nonces, (key.identifier(), commitments))
+            })
+            .unzip();
+        let signing_package = SigningPackage::new(commitments, message);
+
+        let mut shares = BTreeMap::new();
+        for (key, nonces) in signers.iter().zip(nonces) {
+            let mut share = sign(&signing_package, nonces, key)?;
+            if tamper == Some(key.identifier()) {
+                share.z = &share.z + &Scalar::one();
+            }
+            assert_eq!(
+                verify_signature_share(
+                    key.identifier(),
+                    &share,
+                    &signing_package,
+                    public_key_package
+                ),
+                tamper != Some(key.identifier())
+            );
+            shares.insert(key.identifier(), share);
+        }
+
+        aggregate(&signing_package, &shares, public_key_package)
+    }
+
+    #[test]
+    fn frost_dkg_and_signing() {
+        let (key_packages, public_key_package) = dkg(5, 3);
+        let group_key = public_key_package.x_only_public_key();
+
+        // any 3 of the 5 can sign, more work too
+        for signers in [&[1, 2, 3][..], &[1, 3, 5], &[2, 4, 5], &[1, 2, 3, 4, 5]] {
+            let message = b"release the funds";
+            let signature =
+                sign_with(&key_packages, &public_key_package, signers, message, None).unwrap();
+            assert!(verify(message, &group_key, &signature));
+            assert!(!verify(b"another message", &group_key, &signature));
+        }
+
+        // the shares interpolate to the group secret, which no one ever held
+        let signers = [2, 3, 5];
+        let mut secret = Scalar::zero();
+        for key in key_packages
+            .iter()
+            .filter(|key| signers.contains(&key.identifier))
+        {
+            assert_eq!(
+                SECP256K1::public_key(key.signing_share.expose_secret()),
+                key.verifying_share
+            );
+            secret = &secret
+                + &(&lagrange_coefficient(key.identifier, &signers)
+                    * key.signing_share.expose_secret());
+        }
+        assert_eq!(
+            SECP256K1::public_key(&secret),
+            public_key_package.group_public_key
+        );
+        assert_eq!(
+            format!("{:?}", dkg_part1(1, 2, 2).unwrap().0),
+            "DkgSecret([REDACTED])"
+        );
+    }
+
+    #[test]
+    fn frost_dkg_catches_misbehaving_participants() {
+        assert_eq!(dkg_part1(1, 3, 1).err(), Some(FrostError::InvalidThreshold));
+        assert_eq!(dkg_part1(1, 3, 4).err(), Some(FrostError::InvalidThreshold));
+        assert_eq!(
+            dkg_part1(0, 3, 2).err(),
+            Some(FrostError::InvalidIdentifier)
+        );
+
+        // participant 2 doesn't know the secret behind their commitment
+        let (secrets, mut commitments) = dkg_round1(3, 2);
+        let forged = commitments.get_mut(&2).unwrap();
+        forged.proof_mu = &forged.proof_mu + &Scalar::one();
+        assert_eq!(
+            dkg_part2(&secrets[&1], &others(&commitments, 1)).err(),
+            Some(FrostError::InvalidProofOfKnowledge { participant: 2 })
+        );
+
+        // participant 3 sends participant 1 a share off their polynomial
+        let (secrets, commitments) = dkg_round1(3, 2);
+        let mut received = dkg_round2(&secrets, &commitments);
+        let mut shares = received.remove(&1).unwrap();
+        shares.insert(
+            3,
+            SecretShare {
+                value: PrivateKey::from_bytes(&[3; 32]).unwrap(),
+            },
+        );
+        let secret = secrets.into_iter().next().unwrap().1;
+        assert_eq!(
+            dkg_part3(secret, &others(&commitments, 1), &shares).err(),
+            Some(FrostError::InvalidSecretShare { participant: 3 })
+        );
+
+        // and a missing participant
+        let (secrets, commitments) = dkg_round1(3, 2);
+        let mut partial = others(&commitments, 1);
+        partial.remove(&2);
+        assert_eq!(
+            dkg_part2(&secrets[&1], &partial).err(),
+            Some(FrostError::UnexpectedParticipants)
+        );
+    }
+
+    #[test]
+    fn frost_signing_catches_misbehaving_signers() {
+        let (key_packages, public_key_package) = dkg(4, 3);
+        let message = b"rotate the cold wallet";
+
+        // a bad share fails on its own and is named at aggregation
+        assert_eq!(
+            sign_with(
+                &key_packages,
+                &public_key_package,
+                &[1, 2, 4],
+                message,
+                Some(4)
+            ),
+            Err(FrostError::InvalidSignatureShares {
+                participants: vec![4]
+            })
+        );
+
+        // below the threshold no one signs
+        assert_eq!(
+            sign_with(&key_packages, &public_key_package, &[1, 2], message, None),
+            Err(FrostError::NotEnoughSigners)
+        );
+
+        // nonces only sign for the commitments they were made with
+        let (nonces, _) = commit(&key_packages[0]);
+        let commitments = key_packages[1..]
+            .iter()
+            .map(|key| (key.identifier(), commit(key).1))
+            .chain([(1, commit(&key_packages[0]).1)])
+            .collect();
+        let signing_package = SigningPackage::new(commitments, message);
+        assert_eq!(
+            sign(&signing_package, nonces, &key_packages[0]),
+            Err(FrostError::SignerNotIncluded)
+        );
+    }
+}
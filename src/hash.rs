@@ -0,0 +1,79 @@
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Hash functions used to digest messages before signing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashFunction {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashFunction {
+    /// Hash the given data
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashFunction::Sha256 => Sha256::digest(data).to_vec(),
+            HashFunction::Sha384 => Sha384::digest(data).to_vec(),
+            HashFunction::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    /// Size of the digest in bytes
+    pub fn output_len(&self) -> usize {
+        match self {
+            HashFunction::Sha256 => 32,
+            HashFunction::Sha384 => 48,
+            HashFunction::Sha512 => 64,
+        }
+    }
+}
+
+/// BIP-340 tagged hash, SHA256(SHA256(tag) || SHA256(tag) || data)
+/// the tag keeps hashes made for one purpose from being valid for another
+/// see: https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki#design
+pub fn tagged_hash(tag: &str, data: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    for part in data {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// RIPEMD-160(SHA-256(data)), the hash behind bitcoin addresses and key fingerprints
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash::HashFunction;
+
+    #[test]
+    fn hash_function_test_vectors() {
+        // see: https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Standards-and-Guidelines/documents/examples/SHA_All.pdf
+        assert_eq!(
+            hex::encode(HashFunction::Sha256.digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex::encode(HashFunction::Sha384.digest(b"abc")),
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"
+        );
+        assert_eq!(
+            hex::encode(HashFunction::Sha512.digest(b"abc")),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+
+        for hash in [
+            HashFunction::Sha256,
+            HashFunction::Sha384,
+            HashFunction::Sha512,
+        ] {
+            assert_eq!(hash.digest(b"").len(), hash.output_len());
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::curve::{Curve, JacobianPoint, Point};
+use crate::field::FieldElement;
+use crate::hash::HashFunction;
+use crate::ru256::RU256;
+use crate::secp256k1::SECP256K1;
+use std::str::FromStr;
+
+// hashing to secp256k1, the secp256k1_XMD:SHA-256_SSWU_RO_ and _NU_ suites
+// see: https://www.rfc-editor.org/rfc/rfc9380.html
+//
+// the message is stretched with expand_message_xmd into 48 bytes per field
+// element, 16 more than p so the reduction mod p is close to uniform
+// simplified SWU needs a curve with a and b both non-zero, which y^2 = x^3 + 7
+// isn't, so it maps onto an isogenous curve E' instead and a degree 3 isogeny
+// brings the point back to secp256k1 (see: section 6.6.3)
+//
+// RO hashes to two field elements and adds their points, which makes the
+// result indistinguishable from a random oracle, NU maps a single element and
+// only covers part of the curve, but is cheaper
+// nothing here is constant time, the inputs are meant to be public
+
+/// Errors of the hash to curve functions
+#[derive(Debug, PartialEq, Eq)]
+pub enum HashToCurveError {
+    /// More than 255 hash blocks or 65535 bytes were asked of expand_message_xmd
+    InvalidLength,
+}
+
+/// SHA-256 block and output size in bytes
+const BLOCK_SIZE: usize = 64;
+const OUTPUT_SIZE: usize = 32;
+
+/// Bytes per field element, ceil((ceil(log2(p)) + k) / 8) with k = 128 bits of security
+const L: usize = 48;
+
+/// E': y^2 = x^3 + A' * x + B', 3-isogenous to secp256k1 (see: section 8.7)
+const ISO_A: &str = "3f8731abdd661adca08a5558f0f5d272e953d363cb6f0e5d405447c01a444533";
+const ISO_B: &str = "6eb";
+/// Z = -11
+const Z: &str = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc24";
+
+// coefficients of the isogeny map, lowest degree first (see: appendix E.1)
+// x = x_num / x_den and y = y * y_num / y_den, the leading 1s of the denominators included
+const X_NUM: [&str; 4] = [
+    "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa8c7",
+    "7d3d4c80bc321d5b9f315cea7fd44c5d595d2fc0bf63b92dfff1044f17c6581",
+    "534c328d23f234e6e2a413deca25caece4506144037c40314ecbd0b53d9dd262",
+    "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa88c",
+];
+const X_DEN: [&str; 3] = [
+    "d35771193d94918a9ca34ccbb7b640dd86cd409542f8487d9fe6b745781eb49b",
+    "edadc6f64383dc1df7c4b2d51b54225406d36b641f5e41bbc52a56612a8c6d14",
+    "1",
+];
+const Y_NUM: [&str; 4] = [
+    "4bda12f684bda12f684bda12f684bda12f684bda12f684bda12f684b8e38e23c",
+    "c75e0c32d5cb7c0fa9d0a54b12a0a6d5647ab046d686da6fdffc90fc201d71a3",
+    "29a6194691f91a73715209ef6512e576722830a201be2018a765e85a9ecee931",
+    "2f684bda12f684bda12f684bda12f684bda12f684bda12f684bda12f38e38d84",
+];
+const Y_DEN: [&str; 4] = [
+    "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffff93b",
+    "7a06534bb8bdb49fd5e9e6632722c2989467c1bfc8e8d978dfb425d2685c2573",
+    "6484aa716545ca2cf3a70c3fa8fe337e0a3d21162f0d6299a7bf8192bfd2a76f",
+    "1",
+];
+
+fn field_element(hex: &str) -> FieldElement<SECP256K1> {
+    FieldElement::reduce(&RU256::from_str(hex).unwrap())
+}
+
+/// expand_message_xmd with SHA-256, len_in_bytes uniformly random bytes of a message
+/// a domain separation tag longer than 255 bytes is hashed first (see: section 5.3.3)
+pub fn expand_message_xmd(
+    message: &[u8],
+    dst: &[u8],
+    len_in_bytes: usize,
+) -> Result<Vec<u8>, HashToCurveError> {
+    let hash = HashFunction::Sha256;
+    let ell = len_in_bytes.div_ceil(OUTPUT_SIZE);
+    if ell > 255 || len_in_bytes > 65535 {
+        return Err(HashToCurveError::InvalidLength);
+    }
+    let dst = if dst.len() > 255 {
+        hash.digest(&[b"H2C-OVERSIZE-DST-", dst].concat())
+    } else {
+        dst.to_vec()
+    };
+    let dst_prime = [&dst[..], &[dst.len() as u8]].concat();
+
+    // b_0 = H(Z_pad || msg || l_i_b_str || 0 || DST_prime)
+    let b_0 = hash.digest(
+        &[
+            &[0; BLOCK_SIZE][..],
+            message,
+            &(len_in_bytes as u16).to_be_bytes(),
+            &[0],
+            &dst_prime,
+        ]
+        .concat(),
+    );
+    // b_1 = H(b_0 || 1 || DST_prime), b_i = H((b_0 xor b_(i - 1)) || i || DST_prime)
+    let mut b_i = hash.digest(&[&b_0[..], &[1], &dst_prime].concat());
+    let mut uniform_bytes = b_i.clone();
+    for i in 2..=ell {
+        let chained = b_0.iter().zip(&b_i).map(|(a, b)| a ^ b).collect::<Vec<_>>();
+        b_i = hash.digest(&[&chained[..], &[i as u8], &dst_prime].concat());
+        uniform_bytes.extend(&b_i);
+    }
+    uniform_bytes.truncate(len_in_bytes);
+    Ok(uniform_bytes)
+}
+
+/// count field elements of secp256k1 from a message
+/// at most 170, as many as fit in the 255 blocks expand_message_xmd can give
+pub fn hash_to_field(
+    message: &[u8],
+    dst: &[u8],
+    count: usize,
+) -> Result<Vec<FieldElement<SECP256K1>>, HashToCurveError> {
+    let len_in_bytes = count
+        .checked_mul(L)
+        .ok_or(HashToCurveError::InvalidLength)?;
+    let uniform_bytes = expand_message_xmd(message, dst, len_in_bytes)?;
+    // 48 bytes don't fit in 256 bits, split them as high * 2^192 + low
+    // with 24 bytes each, both already below p
+    let shift = field_element("1000000000000000000000000000000000000000000000000");
+    Ok(uniform_bytes
+        .chunks(L)
+        .map(|bytes| {
+            let high = FieldElement::reduce(&RU256::from_bytes(&bytes[..L / 2]));
+            let low = FieldElement::reduce(&RU256::from_bytes(&bytes[L / 2..]));
+            &high * &shift + low
+        })
+        .collect())
+}
+
+/// Simplified SWU map of a field element to a point (x, y) of the isogenous curve E'
+fn map_to_iso_curve(
+    u: &FieldElement<SECP256K1>,
+) -> (FieldElement<SECP256K1>, FieldElement<SECP256K1>) {
+    // see: section 6.6.2, the straightforward version with two square roots
+    let (a, b, z) = (field_element(ISO_A), field_element(ISO_B), field_element(Z));
+    let g = |x: &FieldElement<SECP256K1>| x * x * x + &a * x + &b;
+
+    let u2 = u * u;
+    let tv1 = (&z * &z * &u2 * &u2 + &z * &u2).invert();
+    let x1 = if tv1.is_zero() {
+        &b / &(&z * &a)
+    } else {
+        -&b / &a * (tv1 + FieldElement::one())
+    };
+    let (x, y) = match g(&x1).sqrt() {
+        Some(y) => (x1, y),
+        None => {
+            // g(x2) = Z^3 * u^6 * g(x1) is a square exactly when g(x1) is not
+            let x2 = &z * &u2 * &x1;
+            let y = g(&x2).sqrt().unwrap();
+            (x2, y)
+        }
+    };
+    // sgn0 is the parity for a prime field
+    let y = if u.is_even() == y.is_even() { y } else { -y };
+    (x, y)
+}
+
+/// Evaluate a polynomial given lowest degree first
+fn polynomial(coefficients: &[&str], x: &FieldElement<SECP256K1>) -> FieldElement<SECP256K1> {
+    coefficients
+        .iter()
+        .rev()
+        .fold(FieldElement::zero(), |sum, c| sum * x + field_element(c))
+}
+
+/// The 3-isogeny from E' to secp256k1
+fn iso_map(x: &FieldElement<SECP256K1>, y: &FieldElement<SECP256K1>) -> Point<SECP256K1> {
+    let x_den = polynomial(&X_DEN, x);
+    let y_den = polynomial(&Y_DEN, x);
+    // the points of the kernel go to the identity
+    if x_den.is_zero() || y_den.is_zero() {
+        return SECP256K1::to_affine(&SECP256K1::jacobian_zero_point());
+    }
+    Point {
+        x: polynomial(&X_NUM, x) / x_den,
+        y: y * &polynomial(&Y_NUM, x) / y_den,
+    }
+}
+
+/// Deterministic map of a field element to a point of secp256k1
+pub fn map_to_curve(u: &FieldElement<SECP256K1>) -> Point<SECP256K1> {
+    let (x, y) = map_to_iso_curve(u);
+    iso_map(&x, &y)
+}
+
+/// secp256k1_XMD:SHA-256_SSWU_RO_, a point with no known discrete logarithm
+/// uniformly distributed over the curve
+pub fn hash_to_curve(message: &[u8], dst: &[u8]) -> Point<SECP256K1> {
+    // the cofactor is 1, clearing it is a no-op
+    // two field elements are well within the limit of hash_to_field
+    let u = hash_to_field(message, dst, 2).expect("two field elements");
+    SECP256K1::to_affine(&SECP256K1::add_jacobian_points(
+        &JacobianPoint::from(&map_to_curve(&u[0])),
+        &JacobianPoint::from(&map_to_curve(&u[1])),
+    ))
+}
+
+/// secp256k1_XMD:SHA-256_SSWU_NU_, a point with no known discrete logarithm
+/// not uniformly distributed, use hash_to_curve unless the protocol says otherwise
+pub fn encode_to_curve(message: &[u8], dst: &[u8]) -> Point<SECP256K1> {
+    map_to_curve(&hash_to_field(message, dst, 1).expect("one field element")[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::hash_to_curve::{
+        encode_to_curve, expand_message_xmd, hash_to_curve, hash_to_field, map_to_curve,
+        HashToCurveError,
+    };
+    use crate::secp256k1::SECP256K1;
+    use serde_json::Value;
+
+    #[test]
+    fn expand_message_xmd_vectors() {
+        // see: https://www.rfc-editor.org/rfc/rfc9380.html#appendix-K.1
+        let vectors: Value =
+            serde_json::from_str(include_str!("../test_vectors/hash_to_curve.json")).unwrap();
+        for vector in vectors["expand_message_xmd"].as_array().unwrap() {
+            let uniform_bytes = expand_message_xmd(
+                vector["msg"].as_str().unwrap().as_bytes(),
+                vector["dst"].as_str().unwrap().as_bytes(),
+                vector["len_in_bytes"].as_u64().unwrap() as usize,
+            )
+            .unwrap();
+            assert_eq!(
+                hex::encode(uniform_bytes),
+                vector["uniform_bytes"].as_str().unwrap()
+            );
+        }
+        assert_eq!(
+            expand_message_xmd(b"", b"DST", 255 * 32 + 1),
+            Err(HashToCurveError::InvalidLength)
+        );
+
+        // 170 field elements of 48 bytes are the most 255 blocks of 32 bytes give
+        assert_eq!(hash_to_field(b"", b"DST", 170).unwrap().len(), 170);
+        for count in [171, 1366, usize::MAX] {
+            assert_eq!(
+                hash_to_field(b"", b"DST", count).unwrap_err(),
+                HashToCurveError::InvalidLength
+            );
+        }
+    }
+
+    #[test]
+    fn hash_to_curve_vectors() {
+        // see: https://www.rfc-editor.org/rfc/rfc9380.html#appendix-J.8
+        let vectors: Value =
+            serde_json::from_str(include_str!("../test_vectors/hash_to_curve.json")).unwrap();
+        let point = |value: &Value| {
+            Point::from_hex_coordinates(value[0].as_str().unwrap(), value[1].as_str().unwrap())
+        };
+
+        for (suite, encode) in [
+            ("secp256k1_XMD:SHA-256_SSWU_RO_", false),
+            ("secp256k1_XMD:SHA-256_SSWU_NU_", true),
+        ] {
+            let dst = vectors[suite]["dst"].as_str().unwrap().as_bytes();
+            for vector in vectors[suite]["vectors"].as_array().unwrap() {
+                let message = vector["msg"].as_str().unwrap().as_bytes();
+                let expected_u = vector["u"].as_array().unwrap();
+                let u = hash_to_field(message, dst, expected_u.len()).unwrap();
+                for (u, expected) in u.iter().zip(expected_u) {
+                    assert_eq!(&u.to_string().to_lowercase(), expected.as_str().unwrap());
+                }
+
+                let p = point(&vector["P"]);
+                assert!(SECP256K1::is_on_curve(&p));
+                if encode {
+                    assert_eq!(encode_to_curve(message, dst), p);
+                } else {
+                    assert_eq!(map_to_curve(&u[0]), point(&vector["Q0"]));
+                    assert_eq!(map_to_curve(&u[1]), point(&vector["Q1"]));
+                    assert_eq!(hash_to_curve(message, dst), p);
+                }
+            }
+        }
+    }
+}
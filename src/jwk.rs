@@ -0,0 +1,282 @@
+use crate::base64::{decode_url, encode_url};
+use crate::curve::{Curve, CurveId, Point};
+use crate::hash::HashFunction;
+use crate::ruint::ModInt;
+use crate::secret::{wipe_bytes, PrivateKey};
+use serde_json::{Map, Value};
+
+// JSON Web Keys for EC keys, see: https://www.rfc-editor.org/rfc/rfc7518#section-6.2
+// {"crv":"secp256k1","d":..,"kty":"EC","x":..,"y":..} with the coordinates and
+// the private key in base64url, without padding, at the full field and order
+// length, secp256k1 and ES256K are registered by https://www.rfc-editor.org/rfc/rfc8812
+//
+// the thumbprint (RFC 7638) is the SHA-256 of the public members crv, kty, x
+// and y, sorted and without whitespace, the usual "kid" of a key
+//
+// importing is strict: canonical base64url of the exact length, the point on
+// the curve, d in 1..n-1 and matching x and y, and an "alg" if there is one
+// has to be the curve's ECDSA algorithm, other members like "kid" are ignored
+
+/// Errors importing JSON Web Keys
+#[derive(Debug, PartialEq, Eq)]
+pub enum JwkError {
+    /// Not a JSON object, or a member is missing or not a string
+    InvalidJson,
+    /// A "kty" other than "EC"
+    UnsupportedKeyType,
+    /// A "crv" or "alg" for another curve
+    WrongCurve,
+    /// A coordinate or private key that isn't canonical base64url of the right length
+    InvalidEncoding,
+    /// A point not on the curve, a private key outside 1..n-1, or one that doesn't match the point
+    InvalidKey,
+}
+
+/// "crv" and "alg" of the curve, as registered for JOSE
+fn jwk_names<C: Curve>() -> (&'static str, &'static str) {
+    match CurveId::from_name(C::NAME) {
+        Some(CurveId::Secp256k1) => ("secp256k1", "ES256K"),
+        Some(CurveId::P256) => ("P-256", "ES256"),
+        Some(CurveId::P384) => ("P-384", "ES384"),
+        Some(CurveId::P521) => ("P-521", "ES512"),
+        None => unreachable!("{} is not a registered curve", C::NAME),
+    }
+}
+
+/// Public members of the JWK of a point, in the order of the thumbprint
+fn public_members<C: Curve>(point: &Point<C>) -> Map<String, Value> {
+    let mut members = Map::new();
+    members.insert("crv".into(), jwk_names::<C>().0.into());
+    members.insert("kty".into(), "EC".into());
+    members.insert("x".into(), encode_url(&point.x.to_bytes()).into());
+    members.insert("y".into(), encode_url(&point.y.to_bytes()).into());
+    members
+}
+
+/// The JSON object of a JWK for the curve, with kty, crv and alg checked
+fn parse_object<C: Curve>(jwk: &str) -> Result<Map<String, Value>, JwkError> {
+    let object = match serde_json::from_str(jwk) {
+        Ok(Value::Object(object)) => object,
+        _ => return Err(JwkError::InvalidJson),
+    };
+    if member(&object, "kty")? != "EC" {
+        return Err(JwkError::UnsupportedKeyType);
+    }
+    let (crv, alg) = jwk_names::<C>();
+    if member(&object, "crv")? != crv {
+        return Err(JwkError::WrongCurve);
+    }
+    match object.get("alg") {
+        None => {}
+        Some(Value::String(value)) if value == alg => {}
+        Some(Value::String(_)) => return Err(JwkError::WrongCurve),
+        Some(_) => return Err(JwkError::InvalidJson),
+    }
+    Ok(object)
+}
+
+/// A string member of the JSON object
+fn member<'a>(object: &'a Map<String, Value>, name: &str) -> Result<&'a str, JwkError> {
+    object
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or(JwkError::InvalidJson)
+}
+
+/// base64url of exactly the given length
+fn decode_member(value: &str, length: usize) -> Result<Vec<u8>, JwkError> {
+    match decode_url(value) {
+        Some(bytes) if bytes.len() == length => Ok(bytes),
+        Some(mut bytes) => {
+            wipe_bytes(&mut bytes);
+            Err(JwkError::InvalidEncoding)
+        }
+        None => Err(JwkError::InvalidEncoding),
+    }
+}
+
+/// The point of a parsed JWK
+fn parse_point<C: Curve>(object: &Map<String, Value>) -> Result<Point<C>, JwkError> {
+    let length = C::p().bits().div_ceil(8);
+    let x = decode_member(member(object, "x")?, length)?;
+    let y = decode_member(member(object, "y")?, length)?;
+    Point::from_uncompressed(&[&[4], &x[..], &y[..]].concat()).ok_or(JwkError::InvalidKey)
+}
+
+impl<C: Curve> Point<C> {
+    /// Public JWK of the point, {"crv":..,"kty":"EC","x":..,"y":..}
+    pub fn to_jwk(&self) -> String {
+        Value::Object(public_members(self)).to_string()
+    }
+
+    /// Point from a JWK, public or private, whose private part is ignored
+    pub fn from_jwk(jwk: &str) -> Result<Self, JwkError> {
+        parse_point(&parse_object::<C>(jwk)?)
+    }
+
+    /// RFC 7638 thumbprint of the JWK of the point, in base64url
+    pub fn jwk_thumbprint(&self) -> String {
+        // serde_json keeps members sorted, which is the order the RFC asks for
+        let canonical = Value::Object(public_members(self)).to_string();
+        encode_url(&HashFunction::Sha256.digest(canonical.as_bytes()))
+    }
+}
+
+impl<C: Curve> PrivateKey<C> {
+    /// Private JWK, the public one with "d"
+    /// the string holds the private key, keep it as safe as the key
+    pub fn to_jwk(&self) -> String {
+        let point = C::public_key(self.expose_secret());
+        let mut secret = self.expose_secret().to_bytes();
+        let mut d = encode_url(&secret).into_bytes();
+        wipe_bytes(&mut secret);
+
+        // members in order, without going through serde_json so d is written once
+        let members = public_members(&point);
+        let jwk = format!(
+            "{{\"crv\":{},\"d\":\"{}\",\"kty\":{},\"x\":{},\"y\":{}}}",
+            members["crv"],
+            std::str::from_utf8(&d).unwrap(),
+            members["kty"],
+            members["x"],
+            members["y"]
+        );
+        wipe_bytes(&mut d);
+        jwk
+    }
+
+    /// Private key from a private JWK, x and y have to be its public key
+    pub fn from_jwk(jwk: &str) -> Result<Self, JwkError> {
+        let mut object = parse_object::<C>(jwk)?;
+        let point = parse_point::<C>(&object);
+        let mut d = match object.remove("d") {
+            Some(Value::String(d)) => d.into_bytes(),
+            _ => return Err(JwkError::InvalidJson),
+        };
+        let secret = std::str::from_utf8(&d)
+            .map_err(|_| JwkError::InvalidEncoding)
+            .and_then(|d| decode_member(d, C::n().bits().div_ceil(8)));
+        wipe_bytes(&mut d);
+        let mut secret = secret?;
+        let point = point?;
+
+        let private_key = Self::from_bytes(&secret);
+        wipe_bytes(&mut secret);
+        let private_key = private_key.map_err(|_| JwkError::InvalidKey)?;
+        if C::public_key(private_key.expose_secret()) != point {
+            return Err(JwkError::InvalidKey);
+        }
+        Ok(private_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::jwk::JwkError;
+    use crate::p256::P256;
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+
+    // the key of the OpenSSL files in key_encoding.rs
+    const PRIVATE_JWK: &str = r#"{"crv":"secp256k1","d":"RwlTID7qRtixd-BR-8aRqUwcTD4OVDnWl8SCsJatQJI","kty":"EC","x":"xgBDLvaxU6me8QJi9QSZAVjmxgXsOLMsue_MqKgcUuE","y":"oIpoQh9X2NeeKzyaHf_pIASChZ72tCXxiOJxy8ZU8Lg"}"#;
+    const PUBLIC_JWK: &str = r#"{"crv":"secp256k1","kty":"EC","x":"xgBDLvaxU6me8QJi9QSZAVjmxgXsOLMsue_MqKgcUuE","y":"oIpoQh9X2NeeKzyaHf_pIASChZ72tCXxiOJxy8ZU8Lg"}"#;
+
+    #[test]
+    fn jwk_round_trips() {
+        let private_key: PrivateKey = PrivateKey::from_jwk(PRIVATE_JWK).unwrap();
+        assert_eq!(
+            private_key.expose_secret().to_hex(),
+            "470953203eea46d8b177e051fbc691a94c1c4c3e0e5439d697c482b096ad4092"
+        );
+        assert_eq!(private_key.to_jwk(), PRIVATE_JWK);
+
+        let public_key = SECP256K1::public_key(private_key.expose_secret());
+        assert_eq!(public_key.to_jwk(), PUBLIC_JWK);
+        assert_eq!(Point::from_jwk(PUBLIC_JWK).unwrap(), public_key);
+        assert_eq!(Point::from_jwk(PRIVATE_JWK).unwrap(), public_key);
+        assert_eq!(
+            public_key.jwk_thumbprint(),
+            "2yzIdrHJf_0eMKWLJi9NUoGcD0pfyyUgaLazcnYi5GA"
+        );
+
+        // members in any order, with whitespace, an alg and other members
+        let jwk = r#"{
+            "kid": "custody-1", "use": "sig", "alg": "ES256K",
+            "y": "oIpoQh9X2NeeKzyaHf_pIASChZ72tCXxiOJxy8ZU8Lg",
+            "x": "xgBDLvaxU6me8QJi9QSZAVjmxgXsOLMsue_MqKgcUuE",
+            "kty": "EC", "crv": "secp256k1"
+        }"#;
+        assert_eq!(Point::from_jwk(jwk).unwrap(), public_key);
+
+        // the P-256 key of RFC 7517 appendix A.2
+        let jwk = r#"{"kty":"EC","crv":"P-256","x":"MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4","y":"4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM","d":"870MB6gfuTJ4HtUnUvYMyJpr5eUZNP4Bk43bVdj3eAE","use":"enc","kid":"1"}"#;
+        let private_key: PrivateKey<P256> = PrivateKey::from_jwk(jwk).unwrap();
+        let public_key = P256::public_key(private_key.expose_secret());
+        assert_eq!(Point::<P256>::from_jwk(jwk).unwrap(), public_key);
+        assert_eq!(
+            public_key.jwk_thumbprint(),
+            "cn-I_WNMClehiVp51i_0VpOENW1upEerA8sEam5hn-s"
+        );
+    }
+
+    #[test]
+    fn jwk_rejects_invalid_keys() {
+        let x = "xgBDLvaxU6me8QJi9QSZAVjmxgXsOLMsue_MqKgcUuE";
+        let y = "oIpoQh9X2NeeKzyaHf_pIASChZ72tCXxiOJxy8ZU8Lg";
+        let cases = [
+            ("[]".to_string(), JwkError::InvalidJson),
+            (
+                PUBLIC_JWK.replace(r#","y":"#, r#","z":"#),
+                JwkError::InvalidJson,
+            ),
+            (
+                PUBLIC_JWK.replace(r#""EC""#, r#""OKP""#),
+                JwkError::UnsupportedKeyType,
+            ),
+            (
+                PUBLIC_JWK.replace("secp256k1", "P-256"),
+                JwkError::WrongCurve,
+            ),
+            (
+                PUBLIC_JWK.replace("{", r#"{"alg":"ES256","#),
+                JwkError::WrongCurve,
+            ),
+            // padded, standard alphabet, leading zero stripped
+            (
+                PUBLIC_JWK.replace(x, &format!("{}=", x)),
+                JwkError::InvalidEncoding,
+            ),
+            (
+                PUBLIC_JWK.replace(x, &x.replace('_', "/")),
+                JwkError::InvalidEncoding,
+            ),
+            (PUBLIC_JWK.replace(x, &x[..42]), JwkError::InvalidEncoding),
+            // y of another point
+            (PUBLIC_JWK.replace(y, x), JwkError::InvalidKey),
+        ];
+        for (jwk, error) in cases {
+            assert_eq!(
+                Point::<SECP256K1>::from_jwk(&jwk).unwrap_err(),
+                error,
+                "{}",
+                jwk
+            );
+        }
+
+        // a public JWK has no private key, and d has to match x and y
+        assert_eq!(
+            PrivateKey::<SECP256K1>::from_jwk(PUBLIC_JWK).unwrap_err(),
+            JwkError::InvalidJson
+        );
+        let other = PrivateKey::<SECP256K1>::from_bytes(&[1]).unwrap().to_jwk();
+        let d = &other[other.find(r#""d":"#).unwrap() + 5..other.find(r#"","kty"#).unwrap()];
+        assert_eq!(
+            PrivateKey::<SECP256K1>::from_jwk(
+                &PRIVATE_JWK.replace("RwlTID7qRtixd-BR-8aRqUwcTD4OVDnWl8SCsJatQJI", d)
+            )
+            .unwrap_err(),
+            JwkError::InvalidKey
+        );
+    }
+}
@@ -1,3 +1,34 @@
-mod ecdsa;
-mod ru256;
-mod secp256k1;
+mod base58;
+mod base64;
+pub mod bech32;
+pub mod bip32;
+pub mod bip39;
+pub mod curve;
+mod der;
+pub mod ecdh;
+pub mod ecdsa;
+pub mod ecdsa_adaptor;
+pub mod ecies;
+pub mod ecvrf;
+pub mod field;
+pub mod frost;
+pub mod hash;
+pub mod hash_to_curve;
+pub mod jwk;
+pub mod key_encoding;
+mod msm;
+pub mod musig2;
+pub mod p256;
+pub mod p384;
+pub mod p521;
+pub mod paillier;
+pub mod pbes2;
+pub mod pedersen;
+pub mod ru256;
+pub mod ruint;
+pub mod schnorr;
+pub mod secp256k1;
+pub mod secret;
+pub mod taproot;
+pub mod threshold_ecdsa;
+pub mod tweak;
@@ -0,0 +1,169 @@
+use crate::curve::{Curve, JacobianPoint, Point};
+use crate::field::Scalar;
+use crate::ruint::ModInt;
+
+// Multi-scalar multiplication: a1 * P1 + a2 * P2 + ... + ak * Pk
+// doing the k scalar multiplications one by one and adding them up costs
+// k full double-and-add runs, both algorithms below share the doublings
+// between all the terms instead
+
+/// Number of terms from which Pippenger is faster than Strauss
+const PIPPENGER_THRESHOLD: usize = 128;
+
+/// Strauss window size in bits, each point gets a table of 2^w - 1 multiples
+const STRAUSS_WINDOW: usize = 4;
+
+/// Multi-scalar multiplication, picking the algorithm by the number of terms
+pub(crate) fn multi_scalar_multiplication<C: Curve>(
+    scalars: &[Scalar<C>],
+    points: &[Point<C>],
+) -> Point<C> {
+    assert_eq!(scalars.len(), points.len());
+
+    let result = if scalars.len() < PIPPENGER_THRESHOLD {
+        strauss(scalars, points)
+    } else {
+        pippenger(scalars, points)
+    };
+
+    C::to_affine(&result)
+}
+
+/// Read `width` bits of a scalar starting from bit `start`
+fn window<C: Curve>(scalar: &Scalar<C>, start: usize, width: usize) -> usize {
+    (0..width)
+        .filter(|i| scalar.value().bit(start + i))
+        .map(|i| 1 << i)
+        .sum()
+}
+
+/// Interleaved windowed multiplication, see: https://cr.yp.to/papers/pippenger.pdf section 2
+pub(crate) fn strauss<C: Curve>(scalars: &[Scalar<C>], points: &[Point<C>]) -> JacobianPoint<C> {
+    // every point gets a table of its first 2^w - 1 multiples
+    // then the scalars are walked w bits at a time from the top, the
+    // accumulator is doubled w times per window (once for all the points)
+    // and each point adds in the multiple given by its window
+    let table_size = (1 << STRAUSS_WINDOW) - 1;
+    let tables: Vec<Vec<JacobianPoint<C>>> = points
+        .iter()
+        .map(|point| {
+            let point = JacobianPoint::from(point);
+            let mut table = vec![point.clone()];
+            for i in 1..table_size {
+                table.push(C::add_jacobian_points(&table[i - 1], &point));
+            }
+            table
+        })
+        .collect();
+
+    let windows = C::n().bits().div_ceil(STRAUSS_WINDOW);
+    let mut result = C::jacobian_zero_point();
+    for w in (0..windows).rev() {
+        for _ in 0..STRAUSS_WINDOW {
+            result = C::double_jacobian_point(&result);
+        }
+
+        for (scalar, table) in scalars.iter().zip(tables.iter()) {
+            let digit = window(scalar, w * STRAUSS_WINDOW, STRAUSS_WINDOW);
+            if digit != 0 {
+                result = C::add_jacobian_points(&result, &table[digit - 1]);
+            }
+        }
+    }
+
+    result
+}
+
+/// Bucket method, see: https://cr.yp.to/papers/pippenger.pdf section 4
+pub(crate) fn pippenger<C: Curve>(scalars: &[Scalar<C>], points: &[Point<C>]) -> JacobianPoint<C> {
+    // the scalars are walked c bits at a time from the top, every point
+    // goes into the bucket its window selects, and the buckets are summed
+    // as 1 * B1 + 2 * B2 + ... with running sums, costing about 2^(c + 1)
+    // additions per window whatever the number of points
+    // the window grows with the number of points, roughly log2(k) - 2 bits
+    if scalars.is_empty() {
+        return C::jacobian_zero_point();
+    }
+    let width = match scalars.len().ilog2() {
+        0..=4 => 2,
+        bits => bits as usize - 2,
+    };
+    let points: Vec<JacobianPoint<C>> = points.iter().map(JacobianPoint::from).collect();
+
+    let windows = C::n().bits().div_ceil(width);
+    let mut result = C::jacobian_zero_point();
+    for w in (0..windows).rev() {
+        for _ in 0..width {
+            result = C::double_jacobian_point(&result);
+        }
+
+        let mut buckets = vec![C::jacobian_zero_point(); (1 << width) - 1];
+        for (scalar, point) in scalars.iter().zip(points.iter()) {
+            let digit = window(scalar, w * width, width);
+            if digit != 0 {
+                buckets[digit - 1] = C::add_jacobian_points(&buckets[digit - 1], point);
+            }
+        }
+
+        // going down from the top bucket, the running sum holds
+        // B_j + ... + B_top, and adding it in at every step counts
+        // each bucket as many times as its index
+        let mut running_sum = C::jacobian_zero_point();
+        let mut window_sum = C::jacobian_zero_point();
+        for bucket in buckets.iter().rev() {
+            running_sum = C::add_jacobian_points(&running_sum, bucket);
+            window_sum = C::add_jacobian_points(&window_sum, &running_sum);
+        }
+
+        result = C::add_jacobian_points(&result, &window_sum);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, JacobianPoint, Point};
+    use crate::field::Scalar;
+    use crate::msm::{multi_scalar_multiplication, pippenger, strauss};
+    use crate::secp256k1::SECP256K1;
+
+    #[test]
+    fn msm_matches_separate_multiplications() {
+        let mut scalars: Vec<Scalar> = vec![Scalar::zero(), Scalar::one(), -Scalar::one()];
+        let mut points: Vec<Point> = vec![
+            SECP256K1::g(),
+            SECP256K1::double_point(&SECP256K1::g()),
+            SECP256K1::zero_point(),
+        ];
+        for _ in 0..37 {
+            let mut scalar = Scalar::zero();
+            scalar.randomize();
+            let mut k = Scalar::zero();
+            k.randomize();
+            scalars.push(scalar);
+            points.push(SECP256K1::public_key(&k));
+        }
+
+        // the same point twice makes the additions hit the doubling case
+        scalars.push(Scalar::one());
+        points.push(SECP256K1::g());
+
+        let mut expected = SECP256K1::jacobian_zero_point();
+        for (scalar, point) in scalars.iter().zip(points.iter()) {
+            let term = SECP256K1::scalar_multiplication(scalar, point);
+            expected = SECP256K1::add_jacobian_points(&expected, &JacobianPoint::from(&term));
+        }
+        let expected = SECP256K1::to_affine(&expected);
+
+        assert_eq!(SECP256K1::to_affine(&strauss(&scalars, &points)), expected);
+        assert_eq!(
+            SECP256K1::to_affine(&pippenger(&scalars, &points)),
+            expected
+        );
+        assert_eq!(multi_scalar_multiplication(&scalars, &points), expected);
+
+        // and no terms at all is the identity
+        assert!(multi_scalar_multiplication::<SECP256K1>(&[], &[]).is_zero_point());
+    }
+}
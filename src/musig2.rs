@@ -0,0 +1,1004 @@
+use crate::curve::{Curve, JacobianPoint, Point};
+use crate::field::Scalar;
+use crate::hash::tagged_hash;
+use crate::ru256::RU256;
+use crate::schnorr::{challenge, Signature, XOnlyPublicKey};
+use crate::secp256k1::SECP256K1;
+use crate::secret::{Nonce, PrivateKey};
+use rand::Rng;
+use std::fmt;
+
+// MuSig2 multi-signatures, see: https://github.com/bitcoin/bips/blob/master/bip-0327.mediawiki
+// n signers aggregate their public keys into one, and together produce a
+// single BIP-340 signature for it, which can't be told apart from an ordinary one
+//
+// signing takes two rounds:
+// 1. every signer generates a secret nonce pair and sends out the public nonces,
+//    which are aggregated into one (nonce_gen, nonce_agg)
+// 2. every signer computes a partial signature over the message, the partial
+//    signatures are checked and summed into the final signature
+//    (sign, partial_sig_verify, partial_sig_agg)
+//
+// public keys are exchanged as 33 byte compressed points, public nonces as
+// two of them back to back, and partial signatures as 32 byte scalars
+
+/// Errors of the MuSig2 protocol
+#[derive(Debug, PartialEq, Eq)]
+pub enum MuSigError {
+    /// A signer sent a public key, nonce or partial signature that is invalid
+    /// signer is None if it is the aggregate nonce, which can't be pinned on anyone
+    InvalidContribution {
+        signer: Option<usize>,
+        contribution: Contribution,
+    },
+    /// A tweak is not less than n
+    TweakOutOfRange,
+    /// The aggregate key or a tweaked key is the point at infinity
+    PointAtInfinity,
+    /// A secret nonce is out of range, e.g. one that was already used
+    InvalidSecretNonce,
+    /// The private key is zero
+    InvalidPrivateKey,
+    /// The secret nonce was generated for a different public key
+    PublicKeyMismatch,
+    /// The signer's public key is not one of the aggregated keys
+    SignerNotIncluded,
+}
+
+/// What a signer contributes to a signing session
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Contribution {
+    PublicKey,
+    PublicNonce,
+    AggregateNonce,
+    PartialSignature,
+}
+
+/// Add two points, either of which can be the identity or equal to the other
+fn add(p1: &Point<SECP256K1>, p2: &Point<SECP256K1>) -> Point<SECP256K1> {
+    SECP256K1::to_affine(&SECP256K1::add_jacobian_points(
+        &JacobianPoint::from(p1),
+        &JacobianPoint::from(p2),
+    ))
+}
+
+/// Decode a compressed point that is allowed to be the identity (33 zero bytes)
+fn decode_point_ext(bytes: &[u8]) -> Option<Point<SECP256K1>> {
+    if bytes.iter().all(|byte| *byte == 0) {
+        return Some(SECP256K1::zero_point());
+    }
+    Point::from_compressed(bytes)
+}
+
+/// Compressed encoding of a point, with the identity as 33 zero bytes
+fn encode_point_ext(point: &Point<SECP256K1>) -> [u8; 33] {
+    if point.is_zero_point() {
+        return [0; 33];
+    }
+    point.to_compressed().try_into().unwrap()
+}
+
+/// Scalar from a hash, reduced mod n
+fn hash_to_scalar(hash: &[u8; 32]) -> Scalar<SECP256K1> {
+    Scalar::reduce(&RU256::from_bytes(hash))
+}
+
+/// 1 or -1, whichever makes a point with the given y even
+fn parity_factor(point: &Point<SECP256K1>) -> Scalar<SECP256K1> {
+    if point.y.is_even() {
+        Scalar::one()
+    } else {
+        -Scalar::one()
+    }
+}
+
+/// Sort public keys, so signers that don't agree on an order get the same aggregate key
+pub fn key_sort(public_keys: &[[u8; 33]]) -> Vec<[u8; 33]> {
+    let mut sorted = public_keys.to_vec();
+    sorted.sort();
+    sorted
+}
+
+/// Aggregate public key, along with the tweaks applied to it
+#[derive(Clone, Debug)]
+pub struct KeyAggContext {
+    public_keys: Vec<[u8; 33]>,
+    /// hash of the list of keys, part of every key's coefficient
+    list_hash: [u8; 32],
+    /// first key different from the first one, its coefficient is 1
+    second_key: [u8; 33],
+    /// aggregate key
+    q: Point<SECP256K1>,
+    /// accumulated sign flips from x-only tweaks
+    gacc: Scalar<SECP256K1>,
+    /// accumulated tweak
+    tacc: Scalar<SECP256K1>,
+}
+
+impl KeyAggContext {
+    /// Aggregate the signers' public keys, in the given order
+    pub fn new(public_keys: &[[u8; 33]]) -> Result<Self, MuSigError> {
+        // Q = a_1 * P_1 + ... + a_u * P_u
+        // the coefficients a_i depend on all the keys, so a signer can't pick
+        // their key as a function of the others' to cancel them out
+        let keys: Vec<&[u8]> = public_keys.iter().map(|key| &key[..]).collect();
+        let list_hash = tagged_hash("KeyAgg list", &keys);
+        let second_key = public_keys
+            .iter()
+            .find(|key| *key != &public_keys[0])
+            .copied()
+            .unwrap_or([0; 33]);
+
+        let mut context = KeyAggContext {
+            public_keys: public_keys.to_vec(),
+            list_hash,
+            second_key,
+            q: SECP256K1::zero_point(),
+            gacc: Scalar::one(),
+            tacc: Scalar::zero(),
+        };
+
+        for (i, key) in public_keys.iter().enumerate() {
+            let point = Point::from_compressed(key).ok_or(MuSigError::InvalidContribution {
+                signer: Some(i),
+                contribution: Contribution::PublicKey,
+            })?;
+            let term = SECP256K1::scalar_multiplication(&context.coefficient(key), &point);
+            context.q = add(&context.q, &term);
+        }
+
+        if context.q.is_zero_point() {
+            return Err(MuSigError::PointAtInfinity);
+        }
+
+        Ok(context)
+    }
+
+    /// Coefficient of a public key in the aggregate
+    fn coefficient(&self, public_key: &[u8; 33]) -> Scalar<SECP256K1> {
+        // the second key gets 1, which saves a scalar multiplication
+        if public_key == &self.second_key {
+            return Scalar::one();
+        }
+        hash_to_scalar(&tagged_hash(
+            "KeyAgg coefficient",
+            &[&self.list_hash, public_key],
+        ))
+    }
+
+    /// Tweak the aggregate key, either as a plain key or as the x-only key
+    /// (e.g. BIP-32 derivation is a plain tweak, taproot an x-only one)
+    pub fn apply_tweak(&mut self, tweak: &[u8; 32], is_xonly: bool) -> Result<(), MuSigError> {
+        // an x-only tweak applies to the key with even y, flip Q if it isn't
+        // Q' = g * Q + t * G
+        let g = if is_xonly {
+            parity_factor(&self.q)
+        } else {
+            Scalar::one()
+        };
+
+        let t = RU256::from_bytes(tweak);
+        if t >= SECP256K1::n() {
+            return Err(MuSigError::TweakOutOfRange);
+        }
+        let t = Scalar::reduce(&t);
+
+        let q = add(
+            &SECP256K1::scalar_multiplication(&g, &self.q),
+            &SECP256K1::public_key(&t),
+        );
+        if q.is_zero_point() {
+            return Err(MuSigError::PointAtInfinity);
+        }
+
+        self.q = q;
+        self.gacc = &g * &self.gacc;
+        self.tacc = &t + &g * &self.tacc;
+        Ok(())
+    }
+
+    /// Aggregate key as a BIP-340 public key, which the final signature verifies for
+    pub fn x_only_public_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(&self.q)
+    }
+
+    /// Aggregate key as a compressed point
+    pub fn public_key(&self) -> [u8; 33] {
+        self.q.to_compressed().try_into().unwrap()
+    }
+}
+
+/// A signer's secret nonce pair, along with the public key it was generated for
+/// wiped from memory when dropped and never printed
+///
+/// signing consumes it, signing twice with the same nonce gives the key away
+pub struct SecretNonce {
+    k1: Nonce<SECP256K1>,
+    k2: Nonce<SECP256K1>,
+    public_key: [u8; 33],
+}
+
+impl SecretNonce {
+    /// Secret nonce from its 97 byte encoding, k1 || k2 || public key
+    pub fn from_bytes(bytes: &[u8; 97]) -> Result<Self, MuSigError> {
+        let k1 = RU256::from_bytes(&bytes[..32]);
+        let k2 = RU256::from_bytes(&bytes[32..64]);
+        let n = SECP256K1::n();
+        if k1.is_zero() || k1 >= n || k2.is_zero() || k2 >= n {
+            return Err(MuSigError::InvalidSecretNonce);
+        }
+
+        Ok(SecretNonce {
+            k1: Nonce::new(&k1),
+            k2: Nonce::new(&k2),
+            public_key: bytes[64..].try_into().unwrap(),
+        })
+    }
+
+    /// Public nonce pair, k1 * G || k2 * G
+    fn public_nonce(&self) -> [u8; 66] {
+        let mut public_nonce = [0u8; 66];
+        public_nonce[..33]
+            .copy_from_slice(&SECP256K1::public_key(self.k1.expose_secret()).to_compressed());
+        public_nonce[33..]
+            .copy_from_slice(&SECP256K1::public_key(self.k2.expose_secret()).to_compressed());
+        public_nonce
+    }
+}
+
+impl fmt::Debug for SecretNonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretNonce([REDACTED])")
+    }
+}
+
+/// Generate a secret nonce pair and its public nonce, first round of signing
+/// every argument but the public key is optional, each one that is given
+/// makes the nonce more robust against a broken random number generator
+pub fn nonce_gen(
+    private_key: Option<&PrivateKey>,
+    public_key: &[u8; 33],
+    aggregate_key: Option<&[u8; 32]>,
+    message: Option<&[u8]>,
+    extra_input: Option<&[u8]>,
+) -> (SecretNonce, [u8; 66]) {
+    loop {
+        let rand: [u8; 32] = rand::thread_rng().gen();
+
+        // a zero nonce is astronomically unlikely, just try again
+        if let Some(nonce) = nonce_gen_with_rand(
+            &rand,
+            private_key,
+            public_key,
+            aggregate_key,
+            message,
+            extra_input,
+        ) {
+            return nonce;
+        }
+    }
+}
+
+/// Nonce generation with the given randomness, returns None if a nonce is zero
+fn nonce_gen_with_rand(
+    rand: &[u8; 32],
+    private_key: Option<&PrivateKey>,
+    public_key: &[u8; 33],
+    aggregate_key: Option<&[u8; 32]>,
+    message: Option<&[u8]>,
+    extra_input: Option<&[u8]>,
+) -> Option<(SecretNonce, [u8; 66])> {
+    // the private key is mixed into the randomness the same way BIP-340 does it
+    let mut rand = *rand;
+    if let Some(private_key) = private_key {
+        let aux_hash = tagged_hash("MuSig/aux", &[&rand]);
+        for (byte, (key, aux)) in rand
+            .iter_mut()
+            .zip(private_key.expose_secret().to_bytes().iter().zip(aux_hash))
+        {
+            *byte = key ^ aux;
+        }
+    }
+
+    // every optional input is length prefixed, so they can't run into each other
+    let aggregate_key: &[u8] = aggregate_key.map_or(&[], |key| key);
+    let message_prefixed = match message {
+        None => vec![0],
+        Some(message) => [&[1][..], &(message.len() as u64).to_be_bytes(), message].concat(),
+    };
+    let extra_input = extra_input.unwrap_or(&[]);
+
+    let nonce = |i: u8| {
+        let hash = tagged_hash(
+            "MuSig/nonce",
+            &[
+                &rand,
+                &[public_key.len() as u8],
+                public_key,
+                &[aggregate_key.len() as u8],
+                aggregate_key,
+                &message_prefixed,
+                &(extra_input.len() as u32).to_be_bytes(),
+                extra_input,
+                &[i],
+            ],
+        );
+        Nonce::new(&RU256::from_bytes(&hash))
+    };
+
+    let secret_nonce = SecretNonce {
+        k1: nonce(0),
+        k2: nonce(1),
+        public_key: *public_key,
+    };
+    if secret_nonce.k1.expose_secret().is_zero() || secret_nonce.k2.expose_secret().is_zero() {
+        return None;
+    }
+
+    let public_nonce = secret_nonce.public_nonce();
+    Some((secret_nonce, public_nonce))
+}
+
+/// Aggregate the signers' public nonces
+pub fn nonce_agg(public_nonces: &[[u8; 66]]) -> Result<[u8; 66], MuSigError> {
+    // R_1 and R_2 are summed separately, either sum can be the identity
+    let mut aggregate_nonce = [0u8; 66];
+    for j in 0..2 {
+        let mut sum = SECP256K1::zero_point();
+        for (i, public_nonce) in public_nonces.iter().enumerate() {
+            let point = Point::from_compressed(&public_nonce[33 * j..33 * (j + 1)]).ok_or(
+                MuSigError::InvalidContribution {
+                    signer: Some(i),
+                    contribution: Contribution::PublicNonce,
+                },
+            )?;
+            sum = add(&sum, &point);
+        }
+        aggregate_nonce[33 * j..33 * (j + 1)].copy_from_slice(&encode_point_ext(&sum));
+    }
+
+    Ok(aggregate_nonce)
+}
+
+/// Everything the signers agreed on for one signature, derived from
+/// the aggregate nonce, the (tweaked) aggregate key and the message
+#[derive(Clone, Debug)]
+pub struct SessionContext {
+    key_agg: KeyAggContext,
+    /// nonce coefficient
+    b: Scalar<SECP256K1>,
+    /// final nonce point, R_1 + b * R_2
+    r: Point<SECP256K1>,
+    /// BIP-340 challenge
+    e: Scalar<SECP256K1>,
+}
+
+impl SessionContext {
+    /// Start a signing session, second round of signing
+    pub fn new(
+        aggregate_nonce: &[u8; 66],
+        key_agg: &KeyAggContext,
+        message: &[u8],
+    ) -> Result<Self, MuSigError> {
+        let invalid_nonce = MuSigError::InvalidContribution {
+            signer: None,
+            contribution: Contribution::AggregateNonce,
+        };
+        let r1 = decode_point_ext(&aggregate_nonce[..33]).ok_or(invalid_nonce)?;
+        let r2 =
+            decode_point_ext(&aggregate_nonce[33..]).ok_or(MuSigError::InvalidContribution {
+                signer: None,
+                contribution: Contribution::AggregateNonce,
+            })?;
+
+        // with two nonces combined by a message dependent b, the nonce can't
+        // be steered by a signer waiting for the others' nonces
+        let q_bytes = key_agg.x_only_public_key().to_bytes();
+        let b = hash_to_scalar(&tagged_hash(
+            "MuSig/noncecoef",
+            &[aggregate_nonce, &q_bytes, message],
+        ));
+
+        // R is the identity with negligible probability unless a signer
+        // is malicious, G is used then so the protocol carries on
+        let mut r = add(&r1, &SECP256K1::scalar_multiplication(&b, &r2));
+        if r.is_zero_point() {
+            r = SECP256K1::g();
+        }
+        let e = challenge(&r.x.to_bytes(), &q_bytes, message);
+
+        Ok(SessionContext {
+            key_agg: key_agg.clone(),
+            b,
+            r,
+            e,
+        })
+    }
+
+    /// Coefficient of a signer's key, None if it isn't one of the aggregated keys
+    fn coefficient(&self, public_key: &[u8; 33]) -> Option<Scalar<SECP256K1>> {
+        if !self.key_agg.public_keys.contains(public_key) {
+            return None;
+        }
+        Some(self.key_agg.coefficient(public_key))
+    }
+}
+
+/// Partial signature of the session's message, consumes the secret nonce
+pub fn sign(
+    secret_nonce: SecretNonce,
+    private_key: &PrivateKey,
+    session: &SessionContext,
+) -> Result<[u8; 32], MuSigError> {
+    // the final R has to have even y, if it doesn't every signer negates their nonces
+    let k1 = secret_nonce.k1.conditional_negate(!session.r.y.is_even());
+    let k2 = secret_nonce.k2.conditional_negate(!session.r.y.is_even());
+
+    let d = private_key.expose_secret();
+    if d.is_zero() {
+        return Err(MuSigError::InvalidPrivateKey);
+    }
+    let public_key: [u8; 33] = SECP256K1::public_key(d).to_compressed().try_into().unwrap();
+    if public_key != secret_nonce.public_key {
+        return Err(MuSigError::PublicKeyMismatch);
+    }
+    let a = session
+        .coefficient(&public_key)
+        .ok_or(MuSigError::SignerNotIncluded)?;
+
+    // the key signs for the even y version of the tweaked aggregate key
+    // which is g * gacc times the untweaked one
+    let g = parity_factor(&session.key_agg.q);
+    let mut d = &g * &session.key_agg.gacc * d;
+
+    // s = k1 + b * k2 + e * a * d
+    let s = k1.expose_secret() + &session.b * k2.expose_secret() + &session.e * &a * &d;
+    d.wipe();
+
+    Ok(s.to_bytes().try_into().unwrap())
+}
+
+/// Check one signer's partial signature
+/// invalid encodings of the signature, nonce or key fail the check
+pub fn partial_sig_verify(
+    partial_signature: &[u8; 32],
+    public_nonce: &[u8; 66],
+    public_key: &[u8; 33],
+    session: &SessionContext,
+) -> bool {
+    let s = RU256::from_bytes(partial_signature);
+    if s >= SECP256K1::n() {
+        return false;
+    }
+    let s = Scalar::reduce(&s);
+
+    let (r1, r2, point, a) = match (
+        Point::from_compressed(&public_nonce[..33]),
+        Point::from_compressed(&public_nonce[33..]),
+        Point::from_compressed(public_key),
+        session.coefficient(public_key),
+    ) {
+        (Some(r1), Some(r2), Some(point), Some(a)) => (r1, r2, point, a),
+        _ => return false,
+    };
+
+    // the signer's effective nonce, negated along with the final R
+    let mut r = add(&r1, &SECP256K1::scalar_multiplication(&session.b, &r2));
+    if !session.r.y.is_even() {
+        r.y = -r.y;
+    }
+
+    // s * G = R_i + e * a * g * gacc * P
+    let g = parity_factor(&session.key_agg.q);
+    let factor = &session.e * &a * &g * &session.key_agg.gacc;
+    let expected = add(&r, &SECP256K1::scalar_multiplication(&factor, &point));
+
+    SECP256K1::public_key(&s) == expected
+}
+
+/// Sum the partial signatures into the final BIP-340 signature
+pub fn partial_sig_agg(
+    partial_signatures: &[[u8; 32]],
+    session: &SessionContext,
+) -> Result<Signature, MuSigError> {
+    let mut s = Scalar::zero();
+    for (i, partial_signature) in partial_signatures.iter().enumerate() {
+        let value = RU256::from_bytes(partial_signature);
+        if value >= SECP256K1::n() {
+            return Err(MuSigError::InvalidContribution {
+                signer: Some(i),
+                contribution: Contribution::PartialSignature,
+            });
+        }
+        s = &s + &Scalar::reduce(&value);
+    }
+
+    // the tweaks are public, their share of the signature is added in here
+    let g = parity_factor(&session.key_agg.q);
+    s = &s + &session.e * &g * &session.key_agg.tacc;
+
+    Ok(Signature {
+        r: session.r.x.clone(),
+        s,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::Curve;
+    use crate::musig2::{
+        key_sort, nonce_agg, nonce_gen, nonce_gen_with_rand, partial_sig_agg, partial_sig_verify,
+        sign, Contribution, KeyAggContext, MuSigError, SecretNonce, SessionContext,
+    };
+    use crate::schnorr::{verify, XOnlyPublicKey};
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+    use serde_json::Value;
+
+    // the official vectors, see: https://github.com/bitcoin/bips/tree/master/bip-0327/vectors
+    fn load(name: &str) -> Value {
+        let text = match name {
+            "key_sort" => include_str!("../test_vectors/bip327/key_sort_vectors.json"),
+            "key_agg" => include_str!("../test_vectors/bip327/key_agg_vectors.json"),
+            "nonce_gen" => include_str!("../test_vectors/bip327/nonce_gen_vectors.json"),
+            "nonce_agg" => include_str!("../test_vectors/bip327/nonce_agg_vectors.json"),
+            "sign_verify" => include_str!("../test_vectors/bip327/sign_verify_vectors.json"),
+            "tweak" => include_str!("../test_vectors/bip327/tweak_vectors.json"),
+            "sig_agg" => include_str!("../test_vectors/bip327/sig_agg_vectors.json"),
+            _ => unreachable!(),
+        };
+        serde_json::from_str(text).unwrap()
+    }
+
+    /// Decode a hex string, None if it doesn't have the expected length
+    fn bytes<const N: usize>(value: &Value) -> Option<[u8; N]> {
+        hex::decode(value.as_str().unwrap())
+            .unwrap()
+            .try_into()
+            .ok()
+    }
+
+    /// Pick the entries of a list given by a list of indices
+    fn pick<const N: usize>(list: &Value, indices: &Value) -> Vec<Option<[u8; N]>> {
+        indices
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| bytes(&list[i.as_u64().unwrap() as usize]))
+            .collect()
+    }
+
+    /// Wrong length inputs can't be passed in, they show up as that signer's
+    /// invalid contribution the same way an invalid encoding does
+    fn all_or_invalid<const N: usize>(
+        items: Vec<Option<[u8; N]>>,
+        contribution: Contribution,
+    ) -> Result<Vec<[u8; N]>, MuSigError> {
+        items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| {
+                item.ok_or(MuSigError::InvalidContribution {
+                    signer: Some(i),
+                    contribution: contribution.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// The error a vector expects
+    fn expected_error(error: &Value) -> MuSigError {
+        match error["type"].as_str().unwrap() {
+            "invalid_contribution" => MuSigError::InvalidContribution {
+                signer: error["signer"].as_u64().map(|signer| signer as usize),
+                contribution: match error["contrib"].as_str() {
+                    Some("pubkey") => Contribution::PublicKey,
+                    Some("pubnonce") => Contribution::PublicNonce,
+                    Some("aggnonce") => Contribution::AggregateNonce,
+                    // partial signature errors don't name the contribution
+                    _ => Contribution::PartialSignature,
+                },
+            },
+            _ => match error["message"].as_str().unwrap() {
+                "The tweak must be less than n." => MuSigError::TweakOutOfRange,
+                "The result of tweaking cannot be infinity." => MuSigError::PointAtInfinity,
+                "The signer's pubkey must be included in the list of pubkeys." => {
+                    MuSigError::SignerNotIncluded
+                }
+                "first secnonce value is out of range." => MuSigError::InvalidSecretNonce,
+                message => panic!("unknown error {}", message),
+            },
+        }
+    }
+
+    /// Aggregate keys and apply the tweaks of a vector
+    fn key_agg_with_tweaks(
+        public_keys: &[[u8; 33]],
+        tweaks: &Value,
+        case: &Value,
+    ) -> Result<KeyAggContext, MuSigError> {
+        let mut key_agg = KeyAggContext::new(public_keys)?;
+        if let Some(tweak_indices) = case.get("tweak_indices") {
+            let tweaks = pick::<32>(tweaks, tweak_indices);
+            for (tweak, is_xonly) in tweaks.iter().zip(case["is_xonly"].as_array().unwrap()) {
+                key_agg.apply_tweak(&tweak.unwrap(), is_xonly.as_bool().unwrap())?;
+            }
+        }
+        Ok(key_agg)
+    }
+
+    #[test]
+    fn musig2_key_sort_vectors() {
+        let vectors = load("key_sort");
+        let public_keys: Vec<[u8; 33]> = vectors["pubkeys"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|key| bytes(key).unwrap())
+            .collect();
+        let sorted: Vec<[u8; 33]> = vectors["sorted_pubkeys"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|key| bytes(key).unwrap())
+            .collect();
+        assert_eq!(key_sort(&public_keys), sorted);
+    }
+
+    #[test]
+    fn musig2_key_agg_vectors() {
+        let vectors = load("key_agg");
+        let (public_keys, tweaks) = (&vectors["pubkeys"], &vectors["tweaks"]);
+
+        for case in vectors["valid_test_cases"].as_array().unwrap() {
+            let keys = all_or_invalid(
+                pick::<33>(public_keys, &case["key_indices"]),
+                Contribution::PublicKey,
+            )
+            .unwrap();
+            let key_agg = KeyAggContext::new(&keys).unwrap();
+            assert_eq!(
+                key_agg.x_only_public_key().to_bytes(),
+                bytes::<32>(&case["expected"]).unwrap()
+            );
+        }
+
+        for case in vectors["error_test_cases"].as_array().unwrap() {
+            let result = all_or_invalid(
+                pick::<33>(public_keys, &case["key_indices"]),
+                Contribution::PublicKey,
+            )
+            .and_then(|keys| key_agg_with_tweaks(&keys, tweaks, case));
+            assert_eq!(
+                result.err(),
+                Some(expected_error(&case["error"])),
+                "{}",
+                case["comment"]
+            );
+        }
+    }
+
+    #[test]
+    fn musig2_nonce_gen_vectors() {
+        for case in load("nonce_gen")["test_cases"].as_array().unwrap() {
+            let private_key = case["sk"]
+                .as_str()
+                .map(|sk| PrivateKey::from_bytes(&hex::decode(sk).unwrap()).unwrap());
+            let aggregate_key = case["aggpk"]
+                .as_str()
+                .map(|_| bytes(&case["aggpk"]).unwrap());
+            let message = case["msg"].as_str().map(|msg| hex::decode(msg).unwrap());
+            let extra_input = case["extra_in"]
+                .as_str()
+                .map(|extra| hex::decode(extra).unwrap());
+
+            let (secret_nonce, public_nonce) = nonce_gen_with_rand(
+                &bytes(&case["rand_"]).unwrap(),
+                private_key.as_ref(),
+                &bytes(&case["pk"]).unwrap(),
+                aggregate_key.as_ref(),
+                message.as_deref(),
+                extra_input.as_deref(),
+            )
+            .unwrap();
+
+            let expected = hex::decode(case["expected_secnonce"].as_str().unwrap()).unwrap();
+            assert_eq!(secret_nonce.k1.expose_secret().to_bytes(), expected[..32]);
+            assert_eq!(secret_nonce.k2.expose_secret().to_bytes(), expected[32..64]);
+            assert_eq!(secret_nonce.public_key[..], expected[64..]);
+            assert_eq!(public_nonce, bytes(&case["expected_pubnonce"]).unwrap());
+        }
+    }
+
+    #[test]
+    fn musig2_nonce_agg_vectors() {
+        let vectors = load("nonce_agg");
+        let public_nonces = &vectors["pnonces"];
+
+        for case in vectors["valid_test_cases"].as_array().unwrap() {
+            let nonces = all_or_invalid(
+                pick::<66>(public_nonces, &case["pnonce_indices"]),
+                Contribution::PublicNonce,
+            )
+            .unwrap();
+            assert_eq!(
+                nonce_agg(&nonces).unwrap(),
+                bytes(&case["expected"]).unwrap()
+            );
+        }
+
+        for case in vectors["error_test_cases"].as_array().unwrap() {
+            let result = all_or_invalid(
+                pick::<66>(public_nonces, &case["pnonce_indices"]),
+                Contribution::PublicNonce,
+            )
+            .and_then(|nonces| nonce_agg(&nonces));
+            assert_eq!(
+                result.err(),
+                Some(expected_error(&case["error"])),
+                "{}",
+                case["comment"]
+            );
+        }
+    }
+
+    #[test]
+    fn musig2_sign_verify_vectors() {
+        let vectors = load("sign_verify");
+        let private_key =
+            PrivateKey::from_bytes(&hex::decode(vectors["sk"].as_str().unwrap()).unwrap()).unwrap();
+        let (public_keys, public_nonces) = (&vectors["pubkeys"], &vectors["pnonces"]);
+        let message = |case: &Value| {
+            hex::decode(
+                vectors["msgs"][case["msg_index"].as_u64().unwrap() as usize]
+                    .as_str()
+                    .unwrap(),
+            )
+            .unwrap()
+        };
+        let aggregate_nonce = |case: &Value| -> [u8; 66] {
+            bytes(&vectors["aggnonces"][case["aggnonce_index"].as_u64().unwrap() as usize]).unwrap()
+        };
+        let secret_nonce = |index: u64| {
+            SecretNonce::from_bytes(&bytes(&vectors["secnonces"][index as usize]).unwrap())
+        };
+
+        for case in vectors["valid_test_cases"].as_array().unwrap() {
+            let keys = all_or_invalid(
+                pick::<33>(public_keys, &case["key_indices"]),
+                Contribution::PublicKey,
+            )
+            .unwrap();
+            let nonces = all_or_invalid(
+                pick::<66>(public_nonces, &case["nonce_indices"]),
+                Contribution::PublicNonce,
+            )
+            .unwrap();
+            assert_eq!(nonce_agg(&nonces).unwrap(), aggregate_nonce(case));
+
+            let key_agg = KeyAggContext::new(&keys).unwrap();
+            let session =
+                SessionContext::new(&aggregate_nonce(case), &key_agg, &message(case)).unwrap();
+            let partial_signature = sign(secret_nonce(0).unwrap(), &private_key, &session).unwrap();
+            assert_eq!(partial_signature, bytes::<32>(&case["expected"]).unwrap());
+
+            let signer = case["signer_index"].as_u64().unwrap() as usize;
+            assert!(partial_sig_verify(
+                &partial_signature,
+                &nonces[signer],
+                &keys[signer],
+                &session
+            ));
+        }
+
+        for case in vectors["sign_error_test_cases"].as_array().unwrap() {
+            let result = all_or_invalid(
+                pick::<33>(public_keys, &case["key_indices"]),
+                Contribution::PublicKey,
+            )
+            .and_then(|keys| KeyAggContext::new(&keys))
+            .and_then(|key_agg| {
+                SessionContext::new(&aggregate_nonce(case), &key_agg, &message(case))
+            })
+            .and_then(|session| {
+                sign(
+                    secret_nonce(case["secnonce_index"].as_u64().unwrap())?,
+                    &private_key,
+                    &session,
+                )
+            });
+            assert_eq!(
+                result.err(),
+                Some(expected_error(&case["error"])),
+                "{}",
+                case["comment"]
+            );
+        }
+
+        // partial signature checks that fail, and that can't even be run
+        let verify_case = |case: &Value| {
+            let keys = all_or_invalid(
+                pick::<33>(public_keys, &case["key_indices"]),
+                Contribution::PublicKey,
+            )?;
+            let nonces = all_or_invalid(
+                pick::<66>(public_nonces, &case["nonce_indices"]),
+                Contribution::PublicNonce,
+            )?;
+            let aggregate_nonce = nonce_agg(&nonces)?;
+            let session = SessionContext::new(
+                &aggregate_nonce,
+                &KeyAggContext::new(&keys)?,
+                &message(case),
+            )?;
+            let signer = case["signer_index"].as_u64().unwrap() as usize;
+            Ok(partial_sig_verify(
+                &bytes(&case["sig"]).unwrap(),
+                &nonces[signer],
+                &keys[signer],
+                &session,
+            ))
+        };
+        for case in vectors["verify_fail_test_cases"].as_array().unwrap() {
+            assert_eq!(verify_case(case), Ok(false), "{}", case["comment"]);
+        }
+        for case in vectors["verify_error_test_cases"].as_array().unwrap() {
+            assert_eq!(
+                verify_case(case),
+                Err(expected_error(&case["error"])),
+                "{}",
+                case["comment"]
+            );
+        }
+    }
+
+    #[test]
+    fn musig2_tweak_vectors() {
+        let vectors = load("tweak");
+        let private_key =
+            PrivateKey::from_bytes(&hex::decode(vectors["sk"].as_str().unwrap()).unwrap()).unwrap();
+        let message = hex::decode(vectors["msg"].as_str().unwrap()).unwrap();
+        let aggregate_nonce: [u8; 66] = bytes(&vectors["aggnonce"]).unwrap();
+
+        let sign_case = |case: &Value| {
+            let keys = all_or_invalid(
+                pick::<33>(&vectors["pubkeys"], &case["key_indices"]),
+                Contribution::PublicKey,
+            )?;
+            let nonces = all_or_invalid(
+                pick::<66>(&vectors["pnonces"], &case["nonce_indices"]),
+                Contribution::PublicNonce,
+            )?;
+            assert_eq!(nonce_agg(&nonces)?, aggregate_nonce);
+
+            let key_agg = key_agg_with_tweaks(&keys, &vectors["tweaks"], case)?;
+            let session = SessionContext::new(&aggregate_nonce, &key_agg, &message)?;
+            let secret_nonce = SecretNonce::from_bytes(&bytes(&vectors["secnonce"]).unwrap())?;
+            let partial_signature = sign(secret_nonce, &private_key, &session)?;
+
+            let signer = case["signer_index"].as_u64().unwrap() as usize;
+            assert!(partial_sig_verify(
+                &partial_signature,
+                &nonces[signer],
+                &keys[signer],
+                &session
+            ));
+            Ok(partial_signature)
+        };
+
+        for case in vectors["valid_test_cases"].as_array().unwrap() {
+            assert_eq!(
+                sign_case(case),
+                Ok(bytes(&case["expected"]).unwrap()),
+                "{}",
+                case["comment"]
+            );
+        }
+        for case in vectors["error_test_cases"].as_array().unwrap() {
+            assert_eq!(
+                sign_case(case),
+                Err(expected_error(&case["error"])),
+                "{}",
+                case["comment"]
+            );
+        }
+    }
+
+    #[test]
+    fn musig2_sig_agg_vectors() {
+        let vectors = load("sig_agg");
+        let message = hex::decode(vectors["msg"].as_str().unwrap()).unwrap();
+
+        let aggregate_case = |case: &Value| {
+            let keys = all_or_invalid(
+                pick::<33>(&vectors["pubkeys"], &case["key_indices"]),
+                Contribution::PublicKey,
+            )?;
+            let nonces = all_or_invalid(
+                pick::<66>(&vectors["pnonces"], &case["nonce_indices"]),
+                Contribution::PublicNonce,
+            )?;
+            let aggregate_nonce = nonce_agg(&nonces)?;
+            assert_eq!(aggregate_nonce, bytes(&case["aggnonce"]).unwrap());
+
+            let key_agg = key_agg_with_tweaks(&keys, &vectors["tweaks"], case)?;
+            let session = SessionContext::new(&aggregate_nonce, &key_agg, &message)?;
+            let partial_signatures = all_or_invalid(
+                pick::<32>(&vectors["psigs"], &case["psig_indices"]),
+                Contribution::PartialSignature,
+            )?;
+            let signature = partial_sig_agg(&partial_signatures, &session)?;
+
+            // the result is an ordinary BIP-340 signature for the aggregate key
+            assert!(verify(&message, &key_agg.x_only_public_key(), &signature));
+            Ok(signature.to_bytes())
+        };
+
+        for case in vectors["valid_test_cases"].as_array().unwrap() {
+            assert_eq!(aggregate_case(case), Ok(bytes(&case["expected"]).unwrap()));
+        }
+        for case in vectors["error_test_cases"].as_array().unwrap() {
+            assert_eq!(
+                aggregate_case(case),
+                Err(expected_error(&case["error"])),
+                "{}",
+                case["comment"]
+            );
+        }
+    }
+
+    #[test]
+    fn musig2_signing_session() {
+        let private_keys: Vec<PrivateKey> = (1..=3u8)
+            .map(|i| PrivateKey::from_bytes(&[i; 32]).unwrap())
+            .collect();
+        let public_keys: Vec<[u8; 33]> = private_keys
+            .iter()
+            .map(|key| {
+                SECP256K1::public_key(key.expose_secret())
+                    .to_compressed()
+                    .try_into()
+                    .unwrap()
+            })
+            .collect();
+        let mut key_agg = KeyAggContext::new(&key_sort(&public_keys)).unwrap();
+        key_agg.apply_tweak(&[7; 32], true).unwrap();
+        let message = b"spend from the 3-of-3 vault";
+
+        // first round, everyone shares their public nonce
+        let (secret_nonces, public_nonces): (Vec<SecretNonce>, Vec<[u8; 66]>) = private_keys
+            .iter()
+            .zip(public_keys.iter())
+            .map(|(key, public_key)| nonce_gen(Some(key), public_key, None, Some(message), None))
+            .unzip();
+        assert_eq!(format!("{:?}", secret_nonces[0]), "SecretNonce([REDACTED])");
+        let aggregate_nonce = nonce_agg(&public_nonces).unwrap();
+
+        // second round, everyone signs and the partial signatures are combined
+        let session = SessionContext::new(&aggregate_nonce, &key_agg, message).unwrap();
+        let partial_signatures: Vec<[u8; 32]> = secret_nonces
+            .into_iter()
+            .zip(private_keys.iter())
+            .map(|(secret_nonce, key)| sign(secret_nonce, key, &session).unwrap())
+            .collect();
+        for (i, partial_signature) in partial_signatures.iter().enumerate() {
+            assert!(partial_sig_verify(
+                partial_signature,
+                &public_nonces[i],
+                &public_keys[i],
+                &session
+            ));
+        }
+        assert!(!partial_sig_verify(
+            &partial_signatures[0],
+            &public_nonces[1],
+            &public_keys[1],
+            &session
+        ));
+
+        let signature = partial_sig_agg(&partial_signatures, &session).unwrap();
+        let aggregate_key: XOnlyPublicKey = key_agg.x_only_public_key();
+        assert!(verify(message, &aggregate_key, &signature));
+        assert!(!verify(b"another message", &aggregate_key, &signature));
+    }
+}
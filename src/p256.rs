@@ -0,0 +1,207 @@
+use crate::curve::{double_jacobian_point_a_minus_3, Curve, JacobianPoint, Point};
+use crate::hash::HashFunction;
+use crate::ru256::RU256;
+use crate::ruint::Montgomery;
+use primitive_types::U256;
+use std::str::FromStr;
+
+/// NIST P-256, also known as secp256r1 and prime256v1
+#[derive(Clone, Debug, PartialEq)]
+pub struct P256;
+
+impl Curve for P256 {
+    // Curve parameter specification
+    // see: https://www.secg.org/sec2-v2.pdf
+    // and FIPS 186-4 appendix D.1.2.3
+
+    const NAME: &'static str = "secp256r1";
+
+    /// iso(1) member-body(2) us(840) ansi-x962(10045) curves(3) prime(1) 7
+    const OID: &'static str = "1.2.840.10045.3.1.7";
+
+    const HASH: HashFunction = HashFunction::Sha256;
+
+    type Uint = RU256;
+
+    /// Prime value
+    /// 2^256 - 2^224 + 2^192 + 2^96 - 1
+    const P_MONTGOMERY: Montgomery<4> =
+        Montgomery::from_hex("FFFFFFFF00000001000000000000000000000000FFFFFFFFFFFFFFFFFFFFFFFF");
+
+    /// y^2 = x^3 - 3x + b, a = -3 = p - 3
+    fn a() -> RU256 {
+        RU256::from_str("FFFFFFFF00000001000000000000000000000000FFFFFFFFFFFFFFFFFFFFFFFC").unwrap()
+    }
+
+    fn b() -> RU256 {
+        RU256::from_str("5AC635D8AA3A93E7B3EBBD55769886BC651D06B0CC53B0F63BCE3C3E27D2604B").unwrap()
+    }
+
+    /// Generator point
+    fn g() -> Point<Self> {
+        Point::from_hex_coordinates(
+            "6B17D1F2E12C4247F8BCE6E563A440F277037D812DEB33A0F4A13945D898C296",
+            "4FE342E2FE1A7F9B8EE7EB4A7C0F9E162BCE33576B315ECECBB6406837BF51F5",
+        )
+    }
+
+    /// Group order
+    const N_MONTGOMERY: Montgomery<4> =
+        Montgomery::from_hex("FFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551");
+
+    /// Cofactor
+    fn h() -> RU256 {
+        RU256::one()
+    }
+
+    /// Multiply two reduced field elements using the fast NIST reduction
+    fn field_mul(a: &RU256, b: &RU256) -> RU256 {
+        // see: FIPS 186-4 appendix D.2.3
+        // split the 512 bit product into 32 bit words c0..c15 (c0 lowest)
+        // since 2^256 = 2^224 - 2^192 - 2^96 + 1 mod p, the high words fold
+        // back into 256 bit numbers (written high word first)
+        // t  = (c7,  c6,  c5,  c4,  c3,  c2,  c1,  c0)
+        // s1 = (c15, c14, c13, c12, c11, 0,   0,   0)
+        // s2 = (0,   c15, c14, c13, c12, 0,   0,   0)
+        // s3 = (c15, c14, 0,   0,   0,   c10, c9,  c8)
+        // s4 = (c8,  c13, c15, c14, c13, c11, c10, c9)
+        // d1 = (c10, c8,  0,   0,   0,   c13, c12, c11)
+        // d2 = (c11, c9,  0,   0,   c15, c14, c13, c12)
+        // d3 = (c12, 0,   c10, c9,  c8,  c15, c14, c13)
+        // d4 = (c13, 0,   c11, c10, c9,  0,   c15, c14)
+        // a * b = t + 2s1 + 2s2 + s3 + s4 - d1 - d2 - d3 - d4 mod p
+        let product = a.v.full_mul(b.v);
+        let mut c = [0i64; 16];
+        for (i, word) in c.iter_mut().enumerate() {
+            *word = ((product.0[i / 2] >> (32 * (i % 2))) & 0xffffffff) as i64;
+        }
+
+        // every column of the sum, lowest word first
+        let columns = [
+            c[0] + c[8] + c[9] - c[11] - c[12] - c[13] - c[14],
+            c[1] + c[9] + c[10] - c[12] - c[13] - c[14] - c[15],
+            c[2] + c[10] + c[11] - c[13] - c[14] - c[15],
+            c[3] + 2 * c[11] + 2 * c[12] + c[13] - c[15] - c[8] - c[9],
+            c[4] + 2 * c[12] + 2 * c[13] + c[14] - c[9] - c[10],
+            c[5] + 2 * c[13] + 2 * c[14] + c[15] - c[10] - c[11],
+            c[6] + 3 * c[14] + 2 * c[15] + c[13] - c[8] - c[9],
+            c[7] + 3 * c[15] + c[8] - c[10] - c[11] - c[12] - c[13],
+        ];
+
+        // propagate the carries, the columns are small enough that
+        // none of this overflows an i64
+        let mut limbs = [0u64; 4];
+        let mut carry = 0i64;
+        for (i, column) in columns.iter().enumerate() {
+            let sum = column + carry;
+            limbs[i / 2] |= ((sum & 0xffffffff) as u64) << (32 * (i % 2));
+            carry = sum >> 32;
+        }
+
+        // the result is now carry * 2^256 + value, within a few multiples of p
+        // bring it into [0, p) by adding or subtracting p a few times
+        let p = Self::p().v;
+        let mut value = U256(limbs);
+        while carry < 0 {
+            let (sum, overflow) = value.overflowing_add(p);
+            value = sum;
+            carry += overflow as i64;
+        }
+        while carry > 0 || value >= p {
+            let (difference, underflow) = value.overflowing_sub(p);
+            value = difference;
+            carry -= underflow as i64;
+        }
+
+        RU256 { v: value }
+    }
+
+    /// Double a curve point in jacobian coordinates, using a = -3
+    fn double_jacobian_point(p1: &JacobianPoint<Self>) -> JacobianPoint<Self> {
+        double_jacobian_point_a_minus_3(p1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Scalar;
+    use rand::Rng;
+
+    #[test]
+    fn p256_field_reduction_matches_generic() {
+        let p = P256::p();
+        let mut rng = rand::thread_rng();
+
+        // edge values around the word boundaries of the reduction
+        let mut values = vec![
+            RU256::zero(),
+            RU256::one(),
+            RU256 { v: p.v - 1 },
+            RU256::from_str("FFFFFFFF00000000000000000000000000000000000000000000000000000000")
+                .unwrap(),
+            RU256::from_str("00000000FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF")
+                .unwrap(),
+        ];
+        for _ in 0..200 {
+            let mut value = RU256::zero();
+            rng.fill(&mut value.v.0[..]);
+            values.push(RU256 { v: value.v % p.v });
+        }
+
+        for a in values.iter() {
+            for b in values.iter().take(8) {
+                assert_eq!(P256::field_mul(a, b), a.mul_mod(b, &p));
+            }
+        }
+    }
+
+    #[test]
+    fn p256_double_point() {
+        let g = P256::g();
+        assert!(P256::is_on_curve(&g));
+
+        // the a = -3 jacobian doubling agrees with the generic affine one
+        let mut affine = g.clone();
+        let mut jacobian = JacobianPoint::from(&g);
+        for _ in 0..8 {
+            affine = P256::double_point(&affine);
+            jacobian = P256::double_jacobian_point(&jacobian);
+            assert_eq!(P256::to_affine(&jacobian), affine);
+        }
+        assert!(P256::is_on_curve(&affine));
+
+        // 2G, see: http://point-at-infinity.org/ecc/nisttv
+        assert_eq!(
+            P256::double_point(&g),
+            Point::from_hex_coordinates(
+                "7CF27B188D034F7E8A52380304B51AC3C08969E277F21B35A60B48FC47669978",
+                "07775510DB8ED040293D9AC69F7430DBBA7DADE63CE982299E04B79D227873D1",
+            )
+        );
+    }
+
+    #[test]
+    fn p256_public_key_generation() {
+        // see: http://point-at-infinity.org/ecc/nisttv
+        // k = 5
+        let pub_key = P256::public_key(&Scalar::reduce(&RU256::from_str("5").unwrap()));
+        assert_eq!(
+            pub_key,
+            Point::from_hex_coordinates(
+                "51590B7A515140D2D784C85608668FDFEF8C82FD1F5BE52421554A0DC3D033ED",
+                "E0C17DA8904A727D8AE1BF36BF8A79260D012F00D4D80888D1D0BB44FDA16DA4",
+            )
+        );
+
+        // k = n - 1 = -G
+        let pub_key = P256::public_key(&Scalar::reduce(&RU256 { v: P256::n().v - 1 }));
+        assert_eq!(
+            pub_key,
+            Point::from_hex_coordinates(
+                "6B17D1F2E12C4247F8BCE6E563A440F277037D812DEB33A0F4A13945D898C296",
+                "B01CBD1C01E58065711814B583F061E9D431CCA994CEA1313449BF97C840AE0A",
+            )
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::curve::{double_jacobian_point_a_minus_3, Curve, JacobianPoint, Point};
+use crate::hash::HashFunction;
+use crate::ru256::{add_limbs, sub_limbs};
+use crate::ruint::{ModInt, Montgomery, RUint};
+use std::str::FromStr;
+
+/// NIST P-384, also known as secp384r1
+#[derive(Clone, Debug, PartialEq)]
+pub struct P384;
+
+impl Curve for P384 {
+    // Curve parameter specification
+    // see: https://www.secg.org/sec2-v2.pdf
+    // and FIPS 186-4 appendix D.1.2.4
+
+    const NAME: &'static str = "secp384r1";
+
+    /// iso(1) identified-organization(3) certicom(132) curve(0) 34
+    const OID: &'static str = "1.3.132.0.34";
+
+    const HASH: HashFunction = HashFunction::Sha384;
+
+    /// 384 bits needs 6 limbs
+    type Uint = RUint<6>;
+
+    /// Prime value
+    /// 2^384 - 2^128 - 2^96 + 2^32 - 1
+    const P_MONTGOMERY: Montgomery<6> = Montgomery::from_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFFFF0000000000000000FFFFFFFF");
+
+    /// y^2 = x^3 - 3x + b, a = -3 = p - 3
+    fn a() -> Self::Uint {
+        RUint::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFFFF0000000000000000FFFFFFFC").unwrap()
+    }
+
+    fn b() -> Self::Uint {
+        RUint::from_str("B3312FA7E23EE7E4988E056BE3F82D19181D9C6EFE8141120314088F5013875AC656398D8A2ED19D2A85C8EDD3EC2AEF").unwrap()
+    }
+
+    /// Generator point
+    fn g() -> Point<Self> {
+        Point::from_hex_coordinates(
+            "AA87CA22BE8B05378EB1C71EF320AD746E1D3B628BA79B9859F741E082542A385502F25DBF55296C3A545E3872760AB7",
+            "3617DE4A96262C6F5D9E98BF9292DC29F8F41DBD289A147CE9DA3113B5F0B8C00A60B1CE1D7E819D7A431D7C90EA0E5F",
+        )
+    }
+
+    /// Group order
+    const N_MONTGOMERY: Montgomery<6> = Montgomery::from_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFC7634D81F4372DDF581A0DB248B0A77AECEC196ACCC52973");
+
+    /// Cofactor
+    fn h() -> Self::Uint {
+        RUint::one()
+    }
+
+    /// Multiply two reduced field elements using the fast NIST reduction
+    fn field_mul(a: &Self::Uint, b: &Self::Uint) -> Self::Uint {
+        // see: FIPS 186-4 appendix D.2.4, and field_mul in p256.rs
+        // split the 768 bit product into 32 bit words c0..c23 (c0 lowest)
+        // (written high word first)
+        // t  = (c11, c10, c9,  c8,  c7,  c6,  c5,  c4,  c3,  c2,  c1,  c0)
+        // s1 = (0,   0,   0,   0,   0,   c23, c22, c21, 0,   0,   0,   0)
+        // s2 = (c23, c22, c21, c20, c19, c18, c17, c16, c15, c14, c13, c12)
+        // s3 = (c20, c19, c18, c17, c16, c15, c14, c13, c12, c23, c22, c21)
+        // s4 = (c19, c18, c17, c16, c15, c14, c13, c12, c20, 0,   c23, 0)
+        // s5 = (0,   0,   0,   0,   c23, c22, c21, c20, 0,   0,   0,   0)
+        // s6 = (0,   0,   0,   0,   0,   0,   c23, c22, c21, 0,   0,   c20)
+        // d1 = (c22, c21, c20, c19, c18, c17, c16, c15, c14, c13, c12, c23)
+        // d2 = (0,   0,   0,   0,   0,   0,   0,   c23, c22, c21, c20, 0)
+        // d3 = (0,   0,   0,   0,   0,   0,   0,   c23, c23, 0,   0,   0)
+        // a * b = t + 2s1 + s2 + s3 + s4 + s5 + s6 - d1 - d2 - d3 mod p
+        let product = a.full_mul(b);
+        let mut c = [0i64; 24];
+        for (i, word) in c.iter_mut().enumerate() {
+            *word = ((product[i / 2] >> (32 * (i % 2))) & 0xffffffff) as i64;
+        }
+
+        // every column of the sum, lowest word first
+        let columns = [
+            c[0] + c[12] + c[21] + c[20] - c[23],
+            c[1] + c[13] + c[22] + c[23] - c[12] - c[20],
+            c[2] + c[14] + c[23] - c[13] - c[21],
+            c[3] + c[15] + c[12] + c[20] + c[21] - c[14] - c[22] - c[23],
+            c[4] + 2 * c[21] + c[16] + c[13] + c[12] + c[20] + c[22] - c[15] - 2 * c[23],
+            c[5] + 2 * c[22] + c[17] + c[14] + c[13] + c[21] + c[23] - c[16],
+            c[6] + 2 * c[23] + c[18] + c[15] + c[14] + c[22] - c[17],
+            c[7] + c[19] + c[16] + c[15] + c[23] - c[18],
+            c[8] + c[20] + c[17] + c[16] - c[19],
+            c[9] + c[21] + c[18] + c[17] - c[20],
+            c[10] + c[22] + c[19] + c[18] - c[21],
+            c[11] + c[23] + c[20] + c[19] - c[22],
+        ];
+
+        // propagate the carries
+        let mut limbs = [0u64; 6];
+        let mut carry = 0i64;
+        for (i, column) in columns.iter().enumerate() {
+            let sum = column + carry;
+            limbs[i / 2] |= ((sum & 0xffffffff) as u64) << (32 * (i % 2));
+            carry = sum >> 32;
+        }
+
+        // the result is now carry * 2^384 + value, within a few multiples of p
+        // bring it into [0, p) by adding or subtracting p a few times
+        let p = Self::p();
+        let mut value = RUint { v: limbs };
+        while carry < 0 {
+            let (sum, overflow) = add_limbs(&value.v, &p.v);
+            value = RUint { v: sum };
+            carry += overflow as i64;
+        }
+        while carry > 0 || value >= p {
+            let (difference, underflow) = sub_limbs(&value.v, &p.v);
+            value = RUint { v: difference };
+            carry -= underflow as i64;
+        }
+
+        value
+    }
+
+    /// Double a curve point in jacobian coordinates, using a = -3
+    fn double_jacobian_point(p1: &JacobianPoint<Self>) -> JacobianPoint<Self> {
+        double_jacobian_point_a_minus_3(p1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Scalar;
+    use rand::Rng;
+
+    #[test]
+    fn p384_field_reduction_matches_generic() {
+        let p = P384::p();
+        let mut rng = rand::thread_rng();
+
+        // edge values around the word boundaries of the reduction
+        let mut values = vec![
+            RUint::zero(),
+            RUint::one(),
+            p.checked_sub(&RUint::one()),
+            RUint::from_str("FFFFFFFF000000000000000000000000000000000000000000000000000000000000000000000000FFFFFFFF")
+                .unwrap(),
+        ];
+        for _ in 0..100 {
+            let mut value = RUint::zero();
+            rng.fill(&mut value.v[..]);
+            values.push(value.reduce(&p));
+        }
+
+        for a in values.iter() {
+            for b in values.iter().take(8) {
+                assert_eq!(P384::field_mul(a, b), a.mul_mod(b, &p));
+            }
+        }
+    }
+
+    #[test]
+    fn p384_group_order() {
+        let g = P384::g();
+        assert!(P384::is_on_curve(&g));
+
+        // the a = -3 jacobian doubling agrees with the generic affine one
+        let double = P384::to_affine(&P384::double_jacobian_point(&JacobianPoint::from(&g)));
+        assert_eq!(double, P384::double_point(&g));
+
+        // (n - 1) * G = -G
+        let minus_one = Scalar::reduce(&P384::n().checked_sub(&RUint::one()));
+        let minus_g = P384::public_key(&minus_one);
+        assert_eq!(minus_g.x, g.x);
+        assert_eq!(minus_g.y, -g.y);
+    }
+}
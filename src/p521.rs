@@ -0,0 +1,126 @@
+use crate::curve::{double_jacobian_point_a_minus_3, Curve, JacobianPoint, Point};
+use crate::hash::HashFunction;
+use crate::ru256::add_limbs;
+use crate::ruint::{ModInt, Montgomery, RUint};
+use std::str::FromStr;
+
+/// NIST P-521, also known as secp521r1
+#[derive(Clone, Debug, PartialEq)]
+pub struct P521;
+
+impl Curve for P521 {
+    // Curve parameter specification
+    // see: https://www.secg.org/sec2-v2.pdf
+    // and FIPS 186-4 appendix D.1.2.5
+
+    const NAME: &'static str = "secp521r1";
+
+    /// iso(1) identified-organization(3) certicom(132) curve(0) 35
+    const OID: &'static str = "1.3.132.0.35";
+
+    const HASH: HashFunction = HashFunction::Sha512;
+
+    /// 521 bits needs 9 limbs
+    type Uint = RUint<9>;
+
+    /// Prime value
+    /// 2^521 - 1
+    const P_MONTGOMERY: Montgomery<9> = Montgomery::from_hex("01FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF");
+
+    /// y^2 = x^3 - 3x + b, a = -3 = p - 3
+    fn a() -> Self::Uint {
+        RUint::from_str("01FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFC").unwrap()
+    }
+
+    fn b() -> Self::Uint {
+        RUint::from_str("0051953EB9618E1C9A1F929A21A0B68540EEA2DA725B99B315F3B8B489918EF109E156193951EC7E937B1652C0BD3BB1BF073573DF883D2C34F1EF451FD46B503F00").unwrap()
+    }
+
+    /// Generator point
+    fn g() -> Point<Self> {
+        Point::from_hex_coordinates(
+            "00C6858E06B70404E9CD9E3ECB662395B4429C648139053FB521F828AF606B4D3DBAA14B5E77EFE75928FE1DC127A2FFA8DE3348B3C1856A429BF97E7E31C2E5BD66",
+            "011839296A789A3BC0045C8A5FB42C7D1BD998F54449579B446817AFBD17273E662C97EE72995EF42640C550B9013FAD0761353C7086A272C24088BE94769FD16650",
+        )
+    }
+
+    /// Group order
+    const N_MONTGOMERY: Montgomery<9> = Montgomery::from_hex("01FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFA51868783BF2F966B7FCC0148F709A5D03BB5C9B8899C47AEBB6FB71E91386409");
+
+    /// Cofactor
+    fn h() -> Self::Uint {
+        RUint::one()
+    }
+
+    /// Multiply two reduced field elements using the fast reduction for a mersenne prime
+    fn field_mul(a: &Self::Uint, b: &Self::Uint) -> Self::Uint {
+        // see: FIPS 186-4 appendix D.2.5
+        // p = 2^521 - 1, so 2^521 = 1 mod p and the bits above 521
+        // can simply be added back on to the low 521 bits
+        let product = a.full_mul(b);
+
+        let mut low = [0u64; 9];
+        let mut high = [0u64; 9];
+        for i in 0..9 {
+            low[i] = product[i];
+            high[i] = (product[8 + i] >> 9) | (product.get(9 + i).unwrap_or(&0) << 55);
+        }
+        low[8] &= 0x1ff;
+
+        // both halves are less than p, so one subtraction is enough
+        let (sum, _) = add_limbs(&low, &high);
+        let sum = RUint { v: sum };
+        let p = Self::p();
+        if sum >= p {
+            return sum.checked_sub(&p);
+        }
+        sum
+    }
+
+    /// Double a curve point in jacobian coordinates, using a = -3
+    fn double_jacobian_point(p1: &JacobianPoint<Self>) -> JacobianPoint<Self> {
+        double_jacobian_point_a_minus_3(p1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Scalar;
+    use rand::Rng;
+
+    #[test]
+    fn p521_field_reduction_matches_generic() {
+        let p = P521::p();
+        let mut rng = rand::thread_rng();
+
+        let mut values = vec![RUint::zero(), RUint::one(), p.checked_sub(&RUint::one())];
+        for _ in 0..100 {
+            let mut value = RUint::zero();
+            rng.fill(&mut value.v[..]);
+            values.push(value.reduce(&p));
+        }
+
+        for a in values.iter() {
+            for b in values.iter().take(8) {
+                assert_eq!(P521::field_mul(a, b), a.mul_mod(b, &p));
+            }
+        }
+    }
+
+    #[test]
+    fn p521_group_order() {
+        let g = P521::g();
+        assert!(P521::is_on_curve(&g));
+
+        // the a = -3 jacobian doubling agrees with the generic affine one
+        let double = P521::to_affine(&P521::double_jacobian_point(&JacobianPoint::from(&g)));
+        assert_eq!(double, P521::double_point(&g));
+
+        // (n - 1) * G = -G
+        let minus_one = Scalar::reduce(&P521::n().checked_sub(&RUint::one()));
+        let minus_g = P521::public_key(&minus_one);
+        assert_eq!(minus_g.x, g.x);
+        assert_eq!(minus_g.y, -g.y);
+    }
+}
@@ -0,0 +1,1250 @@
+use crate::curve::{Curve, Point};
+use crate::field::Scalar;
+use crate::hash::tagged_hash;
+use crate::ru256::{Choice, RU256};
+use crate::ruint::{ModInt, Montgomery, RUint};
+use crate::secp256k1::SECP256K1;
+use crate::secret::{wipe, wipe_bytes};
+use rand::Rng;
+use std::fmt;
+
+// Paillier encryption, see: https://link.springer.com/content/pdf/10.1007/3-540-48910-X_16.pdf
+// it is additively homomorphic, Enc(a) * Enc(b) = Enc(a + b) and Enc(a)^k = Enc(k * a)
+// mod N, which the threshold ECDSA share conversion is built on (see: threshold_ecdsa.rs)
+//
+// with g = N + 1 encryption is Enc(m, r) = (1 + m * N) * r^N mod N^2, and decryption
+// Dec(c) = L(c^φ mod N^2) / φ mod N for L(u) = (u - 1) / N, the two primes are
+// 1024 bits so N has 2048 and N^2 fills the 4096 bit integers exactly
+//
+// the range proofs are the ones of GG18 appendix A, after MacKenzie and Reiter
+// see: https://eprint.iacr.org/2019/114.pdf
+// they commit to the secrets with the verifier's ring-Pedersen parameters
+// (Ñ, h1, h2), a modulus whose factors the prover doesn't know and two squares
+// with h2 = h1^λ, here the verifier's own Paillier modulus like CGGMP does it
+//
+// GG18 trusts everyone's setup, which the range proofs are only sound for, so
+// the setup is proven well formed with CGGMP's proofs as well
+// see: https://eprint.iacr.org/2021/060.pdf
+// Π^mod that N is a Paillier-Blum modulus, Π^prm that h2 = h1^λ and Π^fac
+// that neither factor of N is small, the last against the verifier's parameters
+
+/// Unsigned integers wide enough for N^2 and the proofs' exponents
+pub type BigUint = RUint<64>;
+
+/// Unsigned integers wide enough for the exponents of Π^fac, which take
+/// the product of the two moduli with some slack
+type WideUint = RUint<80>;
+
+/// Unsigned integers as wide as one of the primes of N
+type PrimeUint = RUint<PRIME_LIMBS>;
+
+/// Bits of each of the two primes of N
+const PRIME_BITS: usize = 1024;
+
+const PRIME_LIMBS: usize = PRIME_BITS / 64;
+
+/// Rounds of Π^mod and Π^prm, each catches a cheating prover with probability 1/2
+const SETUP_PROOF_ROUNDS: usize = 80;
+
+/// Bits of the challenges and of the statistical slack of Π^fac, CGGMP's ℓ and ε
+const FACTOR_PROOF_BITS: usize = 256;
+const FACTOR_PROOF_SLACK: usize = 512;
+
+/// Miller-Rabin rounds, a composite passes with probability at most 4^-40
+const MILLER_RABIN_ROUNDS: usize = 40;
+
+/// Random number of at most the given number of bits
+fn random_bits<const LIMBS: usize>(bits: usize) -> RUint<LIMBS> {
+    let mut rng = rand::thread_rng();
+    let mut value = RUint::zero();
+    for (i, limb) in value.v.iter_mut().enumerate().take(bits.div_ceil(64)) {
+        *limb = rng.gen();
+        if bits < 64 * (i + 1) {
+            *limb >>= 64 * (i + 1) - bits;
+        }
+    }
+    value
+}
+
+/// Uniformly random number below the bound
+pub(crate) fn random_below<const LIMBS: usize>(bound: &RUint<LIMBS>) -> RUint<LIMBS> {
+    loop {
+        let value = random_bits(bound.bits());
+        if &value < bound {
+            return value;
+        }
+    }
+}
+
+/// Odd primes below 2000, for sieving candidates before Miller-Rabin
+fn small_primes() -> Vec<u64> {
+    (3..2000u64)
+        .step_by(2)
+        .filter(|n| {
+            (3..)
+                .step_by(2)
+                .take_while(|d| d * d <= *n)
+                .all(|d| n % d != 0)
+        })
+        .collect()
+}
+
+/// Miller-Rabin with random bases
+fn is_probable_prime(n: &BigUint) -> bool {
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n.checked_sub(&BigUint::one());
+    let s = (0..).find(|i| n_minus_one.bit(*i)).unwrap();
+    let d = n_minus_one.shr(s);
+
+    let three = BigUint::from_u64(3);
+    for _ in 0..MILLER_RABIN_ROUNDS {
+        // a base in [2, n - 2]
+        let a = random_below(&n.checked_sub(&three)).checked_add(&BigUint::from_u64(2));
+        let mut x = a.exp_mod(&d, n);
+        if x == BigUint::one() || x == n_minus_one {
+            continue;
+        }
+        let mut witness = true;
+        for _ in 1..s {
+            x = x.mul_mod(&x, n);
+            if x == n_minus_one {
+                witness = false;
+                break;
+            }
+        }
+        if witness {
+            return false;
+        }
+    }
+    true
+}
+
+/// Random prime of exactly the given number of bits, with the top two bits set
+/// so the product of two of them has exactly twice as many
+/// and 3 mod 4, which makes the product a Paillier-Blum modulus (see: ModulusProof)
+fn random_prime(bits: usize) -> BigUint {
+    let primes = small_primes();
+    loop {
+        let mut start: BigUint = random_bits(bits);
+        start.v[(bits - 1) / 64] |= 3 << ((bits - 2) % 64);
+        start.v[0] |= 3;
+
+        // walk the numbers that are 3 mod 4 from start, sieving out the multiples
+        // of small primes with their residues, which is much cheaper than a division each
+        let residues: Vec<u64> = primes
+            .iter()
+            .map(|prime| start.reduce(&BigUint::from_u64(*prime)).v[0])
+            .collect();
+        for delta in (0..1 << 16).step_by(4) {
+            if primes
+                .iter()
+                .zip(&residues)
+                .any(|(prime, residue)| (residue + delta) % prime == 0)
+            {
+                continue;
+            }
+            let candidate = start.checked_add(&BigUint::from_u64(delta));
+            if candidate.bits() != bits {
+                break;
+            }
+            if is_probable_prime(&candidate) {
+                return candidate;
+            }
+        }
+    }
+}
+
+/// Number as a big endian byte string as long as its type, for hashing
+fn encode<const LIMBS: usize>(value: &RUint<LIMBS>) -> Vec<u8> {
+    let mut bytes = vec![0u8; 8 * LIMBS];
+    value.to_bytes(&mut bytes);
+    bytes
+}
+
+/// A number in a type of another width, it has to fit
+fn resize<const FROM: usize, const TO: usize>(value: &RUint<FROM>) -> RUint<TO> {
+    let mut result = RUint::zero();
+    for (i, limb) in value.v.iter().enumerate() {
+        if i < TO {
+            result.v[i] = *limb;
+        } else {
+            assert!(*limb == 0, "value does not fit");
+        }
+    }
+    result
+}
+
+/// base^e mod the modulus for a public e too wide for BigUint
+fn exp_mod_wide(base: &BigUint, e: &WideUint, modulus: &BigUint) -> BigUint {
+    resize(&resize::<64, 80>(base).exp_mod(e, &resize(modulus)))
+}
+
+/// Scalar as an integer
+pub(crate) fn from_scalar(scalar: &Scalar<SECP256K1>) -> BigUint {
+    BigUint::from_bytes(&scalar.to_bytes())
+}
+
+/// Integer reduced mod n, as a scalar
+pub(crate) fn to_scalar(value: &BigUint) -> Scalar<SECP256K1> {
+    let mut bytes = encode(&value.reduce(&scalar_order()));
+    let scalar = Scalar::reduce(&RU256::from_bytes(&bytes[480..]));
+    wipe_bytes(&mut bytes);
+    scalar
+}
+
+/// The group order n, the q of the papers
+fn scalar_order() -> BigUint {
+    let mut bytes = [0u8; 32];
+    SECP256K1::n().to_bytes(&mut bytes);
+    BigUint::from_bytes(&bytes)
+}
+
+/// q^k, the bounds of the range proofs
+pub(crate) fn order_power(k: u32) -> BigUint {
+    let q = scalar_order();
+    (1..k).fold(q.clone(), |power, _| power.checked_mul(&q))
+}
+
+/// Whether a number is a unit candidate mod a modulus, in [1, modulus - 1]
+/// a number sharing a factor with N would give the factorization away, so
+/// nobody can actually find one to send
+fn in_range(value: &BigUint, modulus: &BigUint) -> bool {
+    !value.is_zero() && value < modulus
+}
+
+/// base^e mod the modulus in constant time, for a secret e below 2^bits
+fn ct_exp_mod<const E: usize>(
+    base: &BigUint,
+    e: &RUint<E>,
+    bits: usize,
+    modulus: &BigUint,
+) -> BigUint {
+    // exp_mod branches on the exponent's bits, this goes through
+    // Montgomery::pow instead, with constants as wide as the modulus needs
+    // since a product costs the square of the limbs, the base only has to fit them
+    match modulus.bits().max(base.bits()) {
+        0..=1024 => ct_exp_mod_limbs::<16, E>(base, e, bits, modulus),
+        1025..=2048 => ct_exp_mod_limbs::<32, E>(base, e, bits, modulus),
+        _ => ct_exp_mod_limbs::<64, E>(base, e, bits, modulus),
+    }
+}
+
+fn ct_exp_mod_limbs<const LIMBS: usize, const E: usize>(
+    base: &BigUint,
+    e: &RUint<E>,
+    bits: usize,
+    modulus: &BigUint,
+) -> BigUint {
+    debug_assert!(e.bits() <= bits);
+    let montgomery = Montgomery::<LIMBS>::new(modulus.v[..LIMBS].try_into().unwrap());
+    let mut result = BigUint::zero();
+    result.v[..LIMBS].copy_from_slice(&montgomery.pow(
+        base.v[..LIMBS].try_into().unwrap(),
+        &e.v,
+        bits,
+    ));
+    result
+}
+
+/// Paillier ciphertext, a unit mod N^2
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ciphertext {
+    value: BigUint,
+}
+
+/// Paillier public key, the modulus N
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncryptionKey {
+    n: BigUint,
+    n_squared: BigUint,
+}
+
+impl EncryptionKey {
+    fn new(n: BigUint) -> Self {
+        let n_squared = n.checked_mul(&n);
+        Self { n, n_squared }
+    }
+
+    /// Bits of the modulus N
+    pub fn bits(&self) -> usize {
+        self.n.bits()
+    }
+
+    /// (N + 1)^x = 1 + x * N mod N^2
+    fn gamma_pow(&self, x: &BigUint) -> BigUint {
+        x.mul_mod(&self.n, &self.n_squared)
+            .add_mod(&BigUint::one(), &self.n_squared)
+    }
+
+    /// Random r in [1, N - 1] for encrypting
+    pub(crate) fn encryption_randomness(&self) -> BigUint {
+        loop {
+            let r = random_below(&self.n);
+            if !r.is_zero() {
+                return r;
+            }
+        }
+    }
+
+    /// Encryption of m mod N with the randomness r
+    pub(crate) fn encrypt_with(&self, m: &BigUint, r: &BigUint) -> Ciphertext {
+        Ciphertext {
+            value: self
+                .gamma_pow(m)
+                .mul_mod(&r.exp_mod(&self.n, &self.n_squared), &self.n_squared),
+        }
+    }
+
+    /// Encryption of m mod N with fresh randomness
+    pub fn encrypt(&self, m: &BigUint) -> Ciphertext {
+        self.encrypt_with(m, &self.encryption_randomness())
+    }
+
+    /// Encryption of the sum of the plaintexts
+    pub fn add(&self, a: &Ciphertext, b: &Ciphertext) -> Ciphertext {
+        Ciphertext {
+            value: a.value.mul_mod(&b.value, &self.n_squared),
+        }
+    }
+
+    /// Encryption of the plaintext times k, for a secret k below n
+    pub(crate) fn mul(&self, c: &Ciphertext, k: &BigUint) -> Ciphertext {
+        Ciphertext {
+            value: ct_exp_mod(&c.value, k, scalar_order().bits(), &self.n_squared),
+        }
+    }
+
+    /// Whether the ciphertext is a number in [1, N^2 - 1]
+    pub fn is_valid(&self, c: &Ciphertext) -> bool {
+        in_range(&c.value, &self.n_squared)
+    }
+}
+
+/// Paillier private key, the primes p and q, φ(N) = (p - 1) * (q - 1) and its inverse mod N,
+/// and the ring-Pedersen parameters on N with their λ
+/// wiped from memory when dropped and never printed
+pub struct DecryptionKey {
+    encryption_key: EncryptionKey,
+    p: BigUint,
+    q: BigUint,
+    phi: BigUint,
+    phi_inverse: BigUint,
+    /// q^-1 mod p, for the Chinese remainder theorem
+    q_inverse: BigUint,
+    /// N^-1 mod p - 1 and N^-1 mod q - 1, the exponents of N-th roots
+    root_exponents: (BigUint, BigUint),
+    ring_pedersen: RingPedersen,
+    lambda: BigUint,
+}
+
+impl DecryptionKey {
+    /// Fresh key with a 2048 bit modulus
+    pub fn generate() -> Self {
+        loop {
+            let p = random_prime(PRIME_BITS);
+            let q = random_prime(PRIME_BITS);
+            if p != q {
+                return Self::from_primes(&p, &q);
+            }
+        }
+    }
+
+    fn from_primes(p: &BigUint, q: &BigUint) -> Self {
+        debug_assert!(p.v[0] & 3 == 3 && q.v[0] & 3 == 3);
+        let one = BigUint::one();
+        let encryption_key = EncryptionKey::new(p.checked_mul(q));
+        let phi = p.checked_sub(&one).checked_mul(&q.checked_sub(&one));
+        // φ^-1 = φ^(φ - 1) mod N by Euler's theorem, φ and N are coprime
+        // for primes of the same size
+        let n = &encryption_key.n;
+        let mut phi_minus_one = phi.checked_sub(&one);
+        let phi_inverse = ct_exp_mod(&phi.reduce(n), &phi_minus_one, n.bits(), n);
+        wipe(&mut phi_minus_one);
+
+        // worked out once with the primes, in variable time like the prime search
+        // N = q mod p - 1, so N^-1 mod p - 1 is q^-1 mod p - 1 and the other way round
+        let q_inverse = inverse_mod(q, p);
+        let root_exponents = (
+            inverse_mod(q, &p.checked_sub(&one)),
+            inverse_mod(p, &q.checked_sub(&one)),
+        );
+
+        // h1 a random square and h2 = h1^λ, λ is kept for proving it
+        let tau = encryption_key.encryption_randomness();
+        let h1 = tau.mul_mod(&tau, n);
+        let lambda = random_below(&phi);
+        let h2 = ct_exp_mod(&h1, &lambda, n.bits(), n);
+        let ring_pedersen = RingPedersen {
+            n: n.clone(),
+            h1,
+            h2,
+        };
+        Self {
+            encryption_key,
+            p: p.clone(),
+            q: q.clone(),
+            phi,
+            phi_inverse,
+            q_inverse,
+            root_exponents,
+            ring_pedersen,
+            lambda,
+        }
+    }
+
+    pub fn encryption_key(&self) -> &EncryptionKey {
+        &self.encryption_key
+    }
+
+    /// Decryption to the plaintext in [0, N - 1]
+    pub fn decrypt(&self, c: &Ciphertext) -> BigUint {
+        let key = &self.encryption_key;
+        // c^φ = (1 + m * φ * N) mod N^2, so L(c^φ) = m * φ mod N
+        // φ is secret, so in constant time
+        let u = ct_exp_mod(&c.value, &self.phi, key.n.bits(), &key.n_squared);
+        let (l, _) = u.checked_sub(&BigUint::one()).div_rem(&key.n);
+        l.mul_mod(&self.phi_inverse, &key.n)
+    }
+
+    /// Ring-Pedersen parameters on the Paillier modulus
+    /// h1 a random square and h2 = h1^λ, proven with ring_pedersen_proof
+    pub fn ring_pedersen(&self) -> RingPedersen {
+        self.ring_pedersen.clone()
+    }
+
+    /// The number mod N that is x_p mod p and x_q mod q
+    /// x_q + q * ((x_p - x_q) * q^-1 mod p), in constant time
+    fn crt(&self, p: &SecretPrime, x_p: &PrimeUint, x_q: &PrimeUint) -> BigUint {
+        // x_q is below q, which has as many bits as p, one product reduces it
+        let x_q_mod_p = RUint {
+            v: p.narrow.mul(&x_q.v, &PrimeUint::one().v),
+        };
+        let h = x_p
+            .ct_sub_mod(&x_q_mod_p, &p.p)
+            .ct_mul_mod_with(&resize(&self.q_inverse), &p.narrow);
+        resize::<PRIME_LIMBS, 64>(&h)
+            .checked_mul(&self.q)
+            .checked_add(&resize(x_q))
+    }
+
+    /// Π^mod, proof that N is a Paillier-Blum modulus (see: ModulusProof)
+    pub(crate) fn modulus_proof(&self) -> ModulusProof {
+        let n = &self.encryption_key.n;
+        let (p, q) = (SecretPrime::new(&self.p), SecretPrime::new(&self.q));
+
+        // w with Jacobi symbol -1, a square mod exactly one of the primes
+        let (w, w_square_mod_p) = loop {
+            let w = random_below(n);
+            let square_mod_p = p.is_square(&w);
+            if square_mod_p != q.is_square(&w) {
+                break (w, square_mod_p);
+            }
+        };
+
+        let root_p = resize(&self.root_exponents.0);
+        let root_q = resize(&self.root_exponents.1);
+        let rounds = (0..SETUP_PROOF_ROUNDS)
+            .map(|i| {
+                let y = hash_below(MODULUS_PROOF_TAG, &[n, &w], i, n);
+                // -1 is a square mod neither prime and w mod only one of them, so
+                // exactly one of ±y and ±w * y is a square mod both, with fourth roots
+                let (square_mod_p, square_mod_q) = (p.is_square(&y), q.is_square(&y));
+                let b = square_mod_p != square_mod_q;
+                let a = square_mod_p == (b && !w_square_mod_p);
+                let mut y_prime = if b { y.mul_mod(&w, n) } else { y.clone() };
+                if a {
+                    y_prime = BigUint::zero().sub_mod(&y_prime, n);
+                }
+                let x = self.crt(&p, &p.fourth_root(&y_prime), &q.fourth_root(&y_prime));
+                // z^N = y, with the exponents that invert N mod p - 1 and q - 1
+                let z_p = p.pow(&p.reduce(&y), &root_p);
+                let z_q = q.pow(&q.reduce(&y), &root_q);
+                (x, a, b, self.crt(&p, &z_p, &z_q))
+            })
+            .collect();
+        ModulusProof { w, rounds }
+    }
+
+    /// Π^prm, proof that h2 = h1^λ for the ring-Pedersen parameters (see: RingPedersenProof)
+    pub(crate) fn ring_pedersen_proof(&self) -> RingPedersenProof {
+        let setup = &self.ring_pedersen;
+        let n = &setup.n;
+        let mut masks: Vec<BigUint> = (0..SETUP_PROOF_ROUNDS)
+            .map(|_| random_below(&self.phi))
+            .collect();
+        let commitments: Vec<BigUint> = masks
+            .iter()
+            .map(|a| ct_exp_mod(&setup.h1, a, n.bits(), n))
+            .collect();
+        let e = ring_pedersen_challenge(setup, &commitments);
+
+        // z_i = a_i + e_i * λ mod φ, the challenge bit only selects λ or 0
+        let answers: Vec<BigUint> = masks
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let choice = Choice::from_bit(e.bit(i) as u64);
+                let mut lambda =
+                    BigUint::conditional_select(&BigUint::zero(), &self.lambda, choice);
+                let z = a.ct_add_mod(&lambda, &self.phi);
+                wipe(&mut lambda);
+                z
+            })
+            .collect();
+        for mask in &mut masks {
+            wipe(mask);
+        }
+        RingPedersenProof {
+            rounds: commitments.into_iter().zip(answers).collect(),
+        }
+    }
+
+    /// Π^fac, proof that neither prime is small, for the verifier's
+    /// ring-Pedersen parameters (see: FactorProof)
+    pub(crate) fn factor_proof(&self, setup: &RingPedersen) -> FactorProof {
+        let n0 = &self.encryption_key.n;
+        let n_hat = &setup.n;
+        // the masks are ℓ + ε bits wider than what they hide, with √N0 as
+        // the bound of the primes
+        let alpha_bits = FACTOR_PROOF_BITS + FACTOR_PROOF_SLACK + n0.bits().div_ceil(2);
+        let mu_bits = FACTOR_PROOF_BITS + n_hat.bits();
+        let x_bits = mu_bits + FACTOR_PROOF_SLACK;
+        let sigma_bits = FACTOR_PROOF_BITS + n0.bits() + n_hat.bits();
+        let r_bits = sigma_bits + FACTOR_PROOF_SLACK;
+        let mut alpha: BigUint = random_bits(alpha_bits);
+        let mut beta: BigUint = random_bits(alpha_bits);
+        let mut mu: BigUint = random_bits(mu_bits);
+        let mut nu: BigUint = random_bits(mu_bits);
+        let mut x: BigUint = random_bits(x_bits);
+        let mut y: BigUint = random_bits(x_bits);
+        let mut sigma_hat: WideUint = random_bits(sigma_bits);
+        let mut r: WideUint = random_bits(r_bits);
+
+        let commitment_p = setup.ct_commit(&self.p, PRIME_BITS, &mu, mu_bits);
+        let commitment_q = setup.ct_commit(&self.q, PRIME_BITS, &nu, mu_bits);
+        let a = setup.ct_commit(&alpha, alpha_bits, &x, x_bits);
+        let b = setup.ct_commit(&beta, alpha_bits, &y, x_bits);
+        let t = ct_exp_mod(&commitment_q, &alpha, alpha_bits, n_hat)
+            .mul_mod(&ct_exp_mod(&setup.h2, &r, r_bits, n_hat), n_hat);
+        // σ = σ^ + ν * p, so that Q^p * h2^σ^ = h1^N0 * h2^σ
+        let sigma = sigma_hat.checked_add(&resize::<64, 80>(&nu).checked_mul(&resize(&self.p)));
+        let e = factor_challenge(
+            n0,
+            setup,
+            [&commitment_p, &commitment_q, &a, &b, &t],
+            &sigma,
+        );
+
+        let proof = FactorProof {
+            z1: e.checked_mul(&self.p).checked_add(&alpha),
+            z2: e.checked_mul(&self.q).checked_add(&beta),
+            w1: e.checked_mul(&mu).checked_add(&x),
+            w2: e.checked_mul(&nu).checked_add(&y),
+            v: resize::<64, 80>(&e).checked_mul(&sigma_hat).checked_add(&r),
+            commitment_p,
+            commitment_q,
+            a,
+            b,
+            t,
+            sigma,
+        };
+        for secret in [&mut alpha, &mut beta, &mut mu, &mut nu, &mut x, &mut y] {
+            wipe(secret);
+        }
+        wipe(&mut sigma_hat);
+        wipe(&mut r);
+        proof
+    }
+}
+
+impl Drop for DecryptionKey {
+    fn drop(&mut self) {
+        wipe(&mut self.p);
+        wipe(&mut self.q);
+        wipe(&mut self.phi);
+        wipe(&mut self.phi_inverse);
+        wipe(&mut self.q_inverse);
+        wipe(&mut self.root_exponents.0);
+        wipe(&mut self.root_exponents.1);
+        wipe(&mut self.lambda);
+    }
+}
+
+impl fmt::Debug for DecryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DecryptionKey([REDACTED])")
+    }
+}
+
+/// a^-1 mod m for an a coprime to m, by the extended Euclidean algorithm
+/// variable time, only for numbers worked out once with a key
+fn inverse_mod(a: &BigUint, m: &BigUint) -> BigUint {
+    // r_i = t_i * a mod m throughout, with t_i kept mod m so it stays unsigned
+    let (mut r0, mut r1) = (m.clone(), a.reduce(m));
+    let (mut t0, mut t1) = (BigUint::zero(), BigUint::one());
+    while !r1.is_zero() {
+        let (quotient, remainder) = r0.div_rem(&r1);
+        let t = t0.sub_mod(&quotient.mul_mod(&t1, m), m);
+        (r0, r1) = (r1, remainder);
+        (t0, t1) = (t1, t);
+    }
+    assert!(r0 == BigUint::one(), "not invertible");
+    t0
+}
+
+/// One of the primes of N, with the Montgomery constants for working mod it
+/// in constant time, narrow ones for products of reduced numbers and wide
+/// ones for reducing numbers below N
+struct SecretPrime {
+    p: PrimeUint,
+    narrow: Montgomery<PRIME_LIMBS>,
+    wide: Montgomery<{ 2 * PRIME_LIMBS }>,
+}
+
+impl SecretPrime {
+    fn new(p: &BigUint) -> Self {
+        Self {
+            p: resize(p),
+            narrow: Montgomery::new(resize::<64, PRIME_LIMBS>(p).v),
+            wide: Montgomery::new(resize::<64, { 2 * PRIME_LIMBS }>(p).v),
+        }
+    }
+
+    /// x mod p for an x below N
+    fn reduce(&self, x: &BigUint) -> PrimeUint {
+        let one = RUint::<{ 2 * PRIME_LIMBS }>::one();
+        resize(&RUint {
+            v: self
+                .wide
+                .mul(&resize::<64, { 2 * PRIME_LIMBS }>(x).v, &one.v),
+        })
+    }
+
+    /// x^e mod p for a secret e below p
+    fn pow(&self, x: &PrimeUint, e: &PrimeUint) -> PrimeUint {
+        RUint {
+            v: self.narrow.pow(&x.v, &e.v, PRIME_BITS),
+        }
+    }
+
+    /// Whether x is a square mod p, x^((p - 1) / 2) = 1 by Euler's criterion
+    fn is_square(&self, x: &BigUint) -> bool {
+        self.pow(&self.reduce(x), &self.p.shr(1)) == PrimeUint::one()
+    }
+
+    /// A fourth root mod p of an x that has one
+    fn fourth_root(&self, x: &BigUint) -> PrimeUint {
+        // for p = 3 mod 4, x^((p + 1) / 4) is the square root of x that is
+        // a square itself, so taking it twice gives a fourth root
+        let e = self.p.shr(2).checked_add(&PrimeUint::one());
+        self.pow(&self.pow(&self.reduce(x), &e), &e)
+    }
+}
+
+const MODULUS_PROOF_TAG: &str = "Paillier/modulus";
+
+/// Π^mod, CGGMP figure 16: N is a Paillier-Blum modulus, the product of two
+/// primes that are 3 mod 4
+/// for a w with Jacobi symbol -1 and challenges y_i in Z_N hashed from N and w,
+/// x_i^4 = (-1)^a_i * w^b_i * y_i and z_i^N = y_i, which can't all be answered
+/// for any other N with probability over 2^-80
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModulusProof {
+    w: BigUint,
+    rounds: Vec<(BigUint, bool, bool, BigUint)>,
+}
+
+impl ModulusProof {
+    pub(crate) fn verify(&self, key: &EncryptionKey) -> bool {
+        let n = &key.n;
+        // an even N or a prime one passes the rounds but isn't a Paillier-Blum modulus
+        if !n.bit(0) || is_probable_prime(n) {
+            return false;
+        }
+        if !in_range(&self.w, n) || self.rounds.len() != SETUP_PROOF_ROUNDS {
+            return false;
+        }
+        self.rounds.iter().enumerate().all(|(i, (x, a, b, z))| {
+            if !in_range(x, n) || !in_range(z, n) {
+                return false;
+            }
+            let y = hash_below(MODULUS_PROOF_TAG, &[n, &self.w], i, n);
+            let mut y_prime = if *b { y.mul_mod(&self.w, n) } else { y.clone() };
+            if *a {
+                y_prime = BigUint::zero().sub_mod(&y_prime, n);
+            }
+            let x_squared = x.mul_mod(x, n);
+            z.exp_mod(n, n) == y && x_squared.mul_mod(&x_squared, n) == y_prime
+        })
+    }
+}
+
+/// Challenge of Π^prm, its bits are the rounds' challenges
+fn ring_pedersen_challenge(setup: &RingPedersen, commitments: &[BigUint]) -> BigUint {
+    let mut values = vec![&setup.n, &setup.h1, &setup.h2];
+    values.extend(commitments);
+    proof_challenge("Paillier/ring-Pedersen", &values, None)
+}
+
+/// Π^prm, CGGMP figure 17: h2 = h1^λ for some λ, so both generate the same
+/// subgroup and the commitments hide
+/// A_i = h1^a_i and z_i = a_i + e_i * λ mod φ(N) with h1^z_i = A_i * h2^e_i for
+/// challenge bits e_i, one guessed challenge per round lets a cheater through
+#[derive(Clone, Debug, PartialEq)]
+pub struct RingPedersenProof {
+    rounds: Vec<(BigUint, BigUint)>,
+}
+
+impl RingPedersenProof {
+    pub(crate) fn verify(&self, setup: &RingPedersen) -> bool {
+        let n = &setup.n;
+        if !setup.is_valid() || self.rounds.len() != SETUP_PROOF_ROUNDS {
+            return false;
+        }
+        let commitments: Vec<BigUint> = self.rounds.iter().map(|(a, _)| a.clone()).collect();
+        let e = ring_pedersen_challenge(setup, &commitments);
+        self.rounds.iter().enumerate().all(|(i, (a, z))| {
+            let expected = if e.bit(i) {
+                a.mul_mod(&setup.h2, n)
+            } else {
+                a.clone()
+            };
+            in_range(a, n) && z < n && setup.h1.exp_mod(z, n) == expected
+        })
+    }
+}
+
+/// Challenge of Π^fac, a hash of the statement and the commitments reduced mod n
+fn factor_challenge(
+    n0: &BigUint,
+    setup: &RingPedersen,
+    commitments: [&BigUint; 5],
+    sigma: &WideUint,
+) -> BigUint {
+    let mut data: Vec<Vec<u8>> = [n0, &setup.n, &setup.h1, &setup.h2]
+        .into_iter()
+        .chain(commitments)
+        .map(encode)
+        .collect();
+    data.push(encode(sigma));
+    hash_challenge("Paillier/factors", &data)
+}
+
+/// Π^fac, CGGMP figure 28: N0 = p * q for p and q of about √N0, which rules
+/// out small factors
+/// P and Q commit to p and q with the verifier's ring-Pedersen parameters, and
+/// Q^p * h2^σ^ = h1^N0 * h2^σ ties them to N0, the answers z1 and z2 reveal
+/// nothing of p and q beyond their size
+#[derive(Clone, Debug, PartialEq)]
+pub struct FactorProof {
+    commitment_p: BigUint,
+    commitment_q: BigUint,
+    a: BigUint,
+    b: BigUint,
+    t: BigUint,
+    sigma: WideUint,
+    z1: BigUint,
+    z2: BigUint,
+    w1: BigUint,
+    w2: BigUint,
+    v: WideUint,
+}
+
+impl FactorProof {
+    /// Verification against the verifier's own ring-Pedersen parameters
+    pub(crate) fn verify(&self, key: &EncryptionKey, setup: &RingPedersen) -> bool {
+        let n0 = &key.n;
+        let n_hat = &setup.n;
+        let commitments = [
+            &self.commitment_p,
+            &self.commitment_q,
+            &self.a,
+            &self.b,
+            &self.t,
+        ];
+        if !commitments.iter().all(|c| in_range(c, n_hat)) {
+            return false;
+        }
+        // ±√N0 * 2^(ℓ + ε), a z of a small prime fits as well but
+        // wouldn't multiply up to N0
+        let z_bits = FACTOR_PROOF_BITS + FACTOR_PROOF_SLACK + 1 + n0.bits().div_ceil(2);
+        if self.z1.bits() > z_bits || self.z2.bits() > z_bits {
+            return false;
+        }
+
+        let e = factor_challenge(n0, setup, commitments, &self.sigma);
+        let r = setup
+            .h1
+            .exp_mod(n0, n_hat)
+            .mul_mod(&exp_mod_wide(&setup.h2, &self.sigma, n_hat), n_hat);
+        setup.commit(&self.z1, &self.w1)
+            == self.a.mul_mod(&self.commitment_p.exp_mod(&e, n_hat), n_hat)
+            && setup.commit(&self.z2, &self.w2)
+                == self.b.mul_mod(&self.commitment_q.exp_mod(&e, n_hat), n_hat)
+            && self
+                .commitment_q
+                .exp_mod(&self.z1, n_hat)
+                .mul_mod(&exp_mod_wide(&setup.h2, &self.v, n_hat), n_hat)
+                == self.t.mul_mod(&r.exp_mod(&e, n_hat), n_hat)
+    }
+}
+
+/// Ring-Pedersen parameters (Ñ, h1, h2) the range proofs commit with
+#[derive(Clone, Debug, PartialEq)]
+pub struct RingPedersen {
+    n: BigUint,
+    h1: BigUint,
+    h2: BigUint,
+}
+
+impl RingPedersen {
+    /// Bits of the modulus Ñ
+    pub fn bits(&self) -> usize {
+        self.n.bits()
+    }
+
+    /// Whether h1 and h2 are numbers in [2, Ñ - 1]
+    pub fn is_valid(&self) -> bool {
+        let one = BigUint::one();
+        [&self.h1, &self.h2]
+            .iter()
+            .all(|h| in_range(h, &self.n) && *h != &one)
+    }
+
+    /// h1^x * h2^y mod Ñ
+    fn commit(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        self.h1
+            .exp_mod(x, &self.n)
+            .mul_mod(&self.h2.exp_mod(y, &self.n), &self.n)
+    }
+
+    /// commit in constant time, for the prover's secret x below 2^x_bits and y below 2^y_bits
+    fn ct_commit(&self, x: &BigUint, x_bits: usize, y: &BigUint, y_bits: usize) -> BigUint {
+        ct_exp_mod(&self.h1, x, x_bits, &self.n)
+            .mul_mod(&ct_exp_mod(&self.h2, y, y_bits, &self.n), &self.n)
+    }
+}
+
+/// Challenge of a proof, a hash of everything in it reduced mod n
+fn proof_challenge(tag: &str, values: &[&BigUint], point: Option<&Point<SECP256K1>>) -> BigUint {
+    let mut data: Vec<Vec<u8>> = values.iter().map(|value| encode(*value)).collect();
+    data.extend(point.map(|point| point.to_compressed()));
+    hash_challenge(tag, &data)
+}
+
+/// A hash of the encoded values reduced mod n
+fn hash_challenge(tag: &str, data: &[Vec<u8>]) -> BigUint {
+    let data: Vec<&[u8]> = data.iter().map(|bytes| bytes.as_slice()).collect();
+    from_scalar(&Scalar::reduce(&RU256::from_bytes(&tagged_hash(
+        tag, &data,
+    ))))
+}
+
+/// Number below a bound from a hash of the values and an index, for challenges
+/// spread over all of Z_N, hashes with a counter until one is below the bound
+fn hash_below(tag: &str, values: &[&BigUint], index: usize, bound: &BigUint) -> BigUint {
+    let encoded: Vec<Vec<u8>> = values.iter().map(|value| encode(*value)).collect();
+    let bits = bound.bits();
+    let length = bits.div_ceil(8);
+    for counter in 0u32.. {
+        // as many hashes as it takes to fill the bound's bytes
+        let mut bytes = vec![];
+        for block in 0..length.div_ceil(32) as u32 {
+            let suffix = [
+                (index as u32).to_be_bytes(),
+                counter.to_be_bytes(),
+                block.to_be_bytes(),
+            ]
+            .concat();
+            let mut data: Vec<&[u8]> = encoded.iter().map(|bytes| bytes.as_slice()).collect();
+            data.push(&suffix);
+            bytes.extend_from_slice(&tagged_hash(tag, &data));
+        }
+        bytes.truncate(length);
+        bytes[0] &= 0xff >> (8 * length - bits);
+        let value = BigUint::from_bytes(&bytes);
+        if &value < bound {
+            return value;
+        }
+    }
+    unreachable!("a value below the bound comes up with probability over 1/2 each time")
+}
+
+/// Proof that a ciphertext c = Enc(m, r) encrypts an m below q^3, GG18 appendix A.1
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeProof {
+    z: BigUint,
+    u: BigUint,
+    w: BigUint,
+    s: BigUint,
+    s1: BigUint,
+    s2: BigUint,
+}
+
+impl RangeProof {
+    /// Proof for the verifier's ring-Pedersen parameters, m has to be below n
+    pub(crate) fn prove(
+        key: &EncryptionKey,
+        setup: &RingPedersen,
+        c: &Ciphertext,
+        m: &BigUint,
+        r: &BigUint,
+    ) -> Self {
+        let q = scalar_order();
+        let q_n = q.checked_mul(&setup.n);
+        let q_cubed = order_power(3);
+        let q_cubed_n = q_cubed.checked_mul(&setup.n);
+        let mut alpha = random_below(&q_cubed);
+        let mut beta = key.encryption_randomness();
+        let mut gamma = random_below(&q_cubed_n);
+        let mut rho = random_below(&q_n);
+
+        // z commits to m, u and w to the masks
+        let z = setup.ct_commit(m, q_cubed.bits(), &rho, q_n.bits());
+        let u = key
+            .gamma_pow(&alpha)
+            .mul_mod(&beta.exp_mod(&key.n, &key.n_squared), &key.n_squared);
+        let w = setup.ct_commit(&alpha, q_cubed.bits(), &gamma, q_cubed_n.bits());
+        let e = proof_challenge(
+            "Paillier/range",
+            &[&key.n, &c.value, &setup.n, &setup.h1, &setup.h2, &z, &u, &w],
+            None,
+        );
+
+        // s = r^e * beta mod N, s1 = e * m + alpha and s2 = e * rho + gamma as integers
+        let s = r.exp_mod(&e, &key.n).mul_mod(&beta, &key.n);
+        let s1 = e.checked_mul(m).checked_add(&alpha);
+        let s2 = e.checked_mul(&rho).checked_add(&gamma);
+        for secret in [&mut alpha, &mut beta, &mut gamma, &mut rho] {
+            wipe(secret);
+        }
+        Self { z, u, w, s, s1, s2 }
+    }
+
+    /// Check the proof against the verifier's own ring-Pedersen parameters
+    pub(crate) fn verify(&self, key: &EncryptionKey, setup: &RingPedersen, c: &Ciphertext) -> bool {
+        if !key.is_valid(c)
+            || !in_range(&self.z, &setup.n)
+            || !in_range(&self.w, &setup.n)
+            || !in_range(&self.u, &key.n_squared)
+            || !in_range(&self.s, &key.n)
+            || self.s1 > order_power(3)
+        {
+            return false;
+        }
+        let e = proof_challenge(
+            "Paillier/range",
+            &[
+                &key.n, &c.value, &setup.n, &setup.h1, &setup.h2, &self.z, &self.u, &self.w,
+            ],
+            None,
+        );
+
+        // u * c^e = (N + 1)^s1 * s^N mod N^2
+        let lhs = self
+            .u
+            .mul_mod(&c.value.exp_mod(&e, &key.n_squared), &key.n_squared);
+        let rhs = key
+            .gamma_pow(&self.s1)
+            .mul_mod(&self.s.exp_mod(&key.n, &key.n_squared), &key.n_squared);
+        if lhs != rhs {
+            return false;
+        }
+
+        // w * z^e = h1^s1 * h2^s2 mod Ñ
+        let lhs = self.w.mul_mod(&self.z.exp_mod(&e, &setup.n), &setup.n);
+        lhs == setup.commit(&self.s1, &self.s2)
+    }
+}
+
+/// Proof that c2 = c1^x * Enc(y, r) with x below q^3 and y below q^7, GG18 appendix A.2
+/// and with a point X, that X = x * G as well (appendix A.3)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResponseProof {
+    u: Option<Point<SECP256K1>>,
+    z: BigUint,
+    z_prime: BigUint,
+    t: BigUint,
+    v: BigUint,
+    w: BigUint,
+    s: BigUint,
+    s1: BigUint,
+    s2: BigUint,
+    t1: BigUint,
+    t2: BigUint,
+}
+
+impl ResponseProof {
+    /// Proof for the verifier's key and ring-Pedersen parameters
+    /// x has to be below n and y below q^5, with_point also proves X = x * G
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn prove(
+        key: &EncryptionKey,
+        setup: &RingPedersen,
+        c1: &Ciphertext,
+        c2: &Ciphertext,
+        x: &BigUint,
+        y: &BigUint,
+        r: &BigUint,
+        with_point: bool,
+    ) -> Self {
+        let q = scalar_order();
+        let q_n = q.checked_mul(&setup.n);
+        let q_cubed = order_power(3);
+        let q_cubed_n = q_cubed.checked_mul(&setup.n);
+        let q_7 = order_power(7);
+        let mut alpha = random_below(&q_cubed);
+        let mut rho = random_below(&q_n);
+        let mut rho_prime = random_below(&q_cubed_n);
+        let mut sigma = random_below(&q_n);
+        let mut beta = key.encryption_randomness();
+        let mut gamma = random_below(&q_7);
+        let mut tau = random_below(&q_cubed_n);
+
+        let u = with_point.then(|| SECP256K1::public_key(&to_scalar(&alpha)));
+        let z = setup.ct_commit(x, q.bits(), &rho, q_n.bits());
+        let z_prime = setup.ct_commit(&alpha, q_cubed.bits(), &rho_prime, q_cubed_n.bits());
+        let t = setup.ct_commit(y, order_power(5).bits(), &sigma, q_n.bits());
+        let v = ct_exp_mod(&c1.value, &alpha, q_cubed.bits(), &key.n_squared)
+            .mul_mod(&key.gamma_pow(&gamma), &key.n_squared)
+            .mul_mod(&beta.exp_mod(&key.n, &key.n_squared), &key.n_squared);
+        let w = setup.ct_commit(&gamma, q_7.bits(), &tau, q_cubed_n.bits());
+        let e = proof_challenge(
+            "Paillier/response",
+            &[
+                &key.n, &c1.value, &c2.value, &setup.n, &setup.h1, &setup.h2, &z, &z_prime, &t, &v,
+                &w,
+            ],
+            u.as_ref(),
+        );
+
+        let s = r.exp_mod(&e, &key.n).mul_mod(&beta, &key.n);
+        let s1 = e.checked_mul(x).checked_add(&alpha);
+        let s2 = e.checked_mul(&rho).checked_add(&rho_prime);
+        let t1 = e.checked_mul(y).checked_add(&gamma);
+        let t2 = e.checked_mul(&sigma).checked_add(&tau);
+        for secret in [
+            &mut alpha,
+            &mut rho,
+            &mut rho_prime,
+            &mut sigma,
+            &mut beta,
+            &mut gamma,
+            &mut tau,
+        ] {
+            wipe(secret);
+        }
+        Self {
+            u,
+            z,
+            z_prime,
+            t,
+            v,
+            w,
+            s,
+            s1,
+            s2,
+            t1,
+            t2,
+        }
+    }
+
+    /// Check the proof against the verifier's own key and ring-Pedersen parameters
+    /// and X = x * G if the point is given, which the proof has to have been made for
+    pub(crate) fn verify(
+        &self,
+        key: &EncryptionKey,
+        setup: &RingPedersen,
+        c1: &Ciphertext,
+        c2: &Ciphertext,
+        point: Option<&Point<SECP256K1>>,
+    ) -> bool {
+        if self.u.is_some() != point.is_some()
+            || !key.is_valid(c1)
+            || !key.is_valid(c2)
+            || ![&self.z, &self.z_prime, &self.t, &self.w]
+                .iter()
+                .all(|value| in_range(value, &setup.n))
+            || !in_range(&self.v, &key.n_squared)
+            || !in_range(&self.s, &key.n)
+            || self.s1 > order_power(3)
+            || self.t1 > order_power(7)
+        {
+            return false;
+        }
+        let e = proof_challenge(
+            "Paillier/response",
+            &[
+                &key.n,
+                &c1.value,
+                &c2.value,
+                &setup.n,
+                &setup.h1,
+                &setup.h2,
+                &self.z,
+                &self.z_prime,
+                &self.t,
+                &self.v,
+                &self.w,
+            ],
+            self.u.as_ref(),
+        );
+
+        // s1 * G = e * X + u
+        if let (Some(u), Some(point)) = (&self.u, point) {
+            let lhs = SECP256K1::public_key(&to_scalar(&self.s1));
+            let rhs =
+                SECP256K1::add_points(&SECP256K1::scalar_multiplication(&to_scalar(&e), point), u);
+            if u.is_zero_point() || !SECP256K1::is_on_curve(u) || lhs != rhs {
+                return false;
+            }
+        }
+
+        // z^e * z' = h1^s1 * h2^s2 and t^e * w = h1^t1 * h2^t2 mod Ñ
+        let lhs = self
+            .z
+            .exp_mod(&e, &setup.n)
+            .mul_mod(&self.z_prime, &setup.n);
+        if lhs != setup.commit(&self.s1, &self.s2) {
+            return false;
+        }
+        let lhs = self.t.exp_mod(&e, &setup.n).mul_mod(&self.w, &setup.n);
+        if lhs != setup.commit(&self.t1, &self.t2) {
+            return false;
+        }
+
+        // c2^e * v = c1^s1 * (N + 1)^t1 * s^N mod N^2
+        let n_squared = &key.n_squared;
+        let lhs = c2.value.exp_mod(&e, n_squared).mul_mod(&self.v, n_squared);
+        let rhs = c1
+            .value
+            .exp_mod(&self.s1, n_squared)
+            .mul_mod(&key.gamma_pow(&self.t1), n_squared)
+            .mul_mod(&self.s.exp_mod(&key.n, n_squared), n_squared);
+        lhs == rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::Curve;
+    use crate::paillier::{
+        ct_exp_mod, is_probable_prime, order_power, random_below, random_prime, to_scalar, BigUint,
+        DecryptionKey, RangeProof, ResponseProof, SETUP_PROOF_ROUNDS,
+    };
+    use crate::ruint::ModInt;
+    use crate::secp256k1::SECP256K1;
+    use std::str::FromStr;
+
+    #[test]
+    fn paillier_homomorphic_encryption() {
+        // 2^1024 - 105, the largest 1024 bit prime, and a random one
+        let p = BigUint::from_str("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff97").unwrap();
+        assert!(is_probable_prime(&p));
+        assert!(!is_probable_prime(&p.checked_sub(&BigUint::from_u64(2))));
+        let q = random_prime(1024);
+        assert_eq!(q.bits(), 1024);
+        assert_eq!(q.v[0] & 3, 3);
+        let key = DecryptionKey::from_primes(&p, &q);
+        let public = key.encryption_key();
+        assert_eq!(public.bits(), 2048);
+
+        let a = BigUint::from_u64(123456789);
+        let b = random_below(&public.n);
+        let ca = public.encrypt(&a);
+        let cb = public.encrypt(&b);
+        assert_eq!(key.decrypt(&ca), a);
+        assert_eq!(key.decrypt(&cb), b);
+        // fresh randomness every time
+        assert_ne!(public.encrypt(&a), ca);
+
+        // Enc(a) * Enc(b) = Enc(a + b) and Enc(b)^k = Enc(k * b) mod N
+        let k = BigUint::from_u64(1000);
+        assert_eq!(key.decrypt(&public.add(&ca, &cb)), a.add_mod(&b, &public.n));
+        assert_eq!(key.decrypt(&public.mul(&cb, &k)), b.mul_mod(&k, &public.n));
+
+        // the constant time exponentiation agrees with exp_mod at every width
+        let e = random_below(&public.n);
+        for modulus in [&p, &public.n, &public.n_squared] {
+            let base = random_below(modulus);
+            assert_eq!(
+                ct_exp_mod(&base, &e, 2048, modulus),
+                base.exp_mod(&e, modulus)
+            );
+        }
+    }
+
+    #[test]
+    fn paillier_range_proofs() {
+        let prover = DecryptionKey::generate();
+        let verifier = DecryptionKey::generate();
+        let key = prover.encryption_key();
+        let setup = verifier.ring_pedersen();
+        let other_setup = prover.ring_pedersen();
+        assert!(setup.is_valid());
+        assert_eq!(setup.bits(), 2048);
+
+        // Enc(m) with m below q
+        let m = random_below(&order_power(1));
+        let r = key.encryption_randomness();
+        let c = key.encrypt_with(&m, &r);
+        let proof = RangeProof::prove(key, &setup, &c, &m, &r);
+        assert!(proof.verify(key, &setup, &c));
+        assert!(!proof.verify(key, &other_setup, &c));
+        assert!(!proof.verify(key, &setup, &key.encrypt(&m)));
+        // an m above q^3 can't be proven
+        let big = order_power(3).checked_add(&BigUint::one());
+        let c_big = key.encrypt_with(&big, &r);
+        assert!(!RangeProof::prove(key, &setup, &c_big, &big, &r).verify(key, &setup, &c_big));
+
+        // c2 = c^x * Enc(y), with and without X = x * G
+        let x = BigUint::from_u64(7);
+        let y = random_below(&order_power(5));
+        let r2 = key.encryption_randomness();
+        let c2 = key.add(&key.mul(&c, &x), &key.encrypt_with(&y, &r2));
+        let point = SECP256K1::public_key(&to_scalar(&x));
+        for with_point in [false, true] {
+            let proof = ResponseProof::prove(key, &setup, &c, &c2, &x, &y, &r2, with_point);
+            let check = with_point.then_some(&point);
+            assert!(proof.verify(key, &setup, &c, &c2, check));
+            assert!(!proof.verify(key, &setup, &c2, &c, check));
+            assert!(!proof.verify(key, &other_setup, &c, &c2, check));
+            let mut tampered = proof.clone();
+            tampered.t1 = tampered.t1.checked_add(&BigUint::one());
+            assert!(!tampered.verify(key, &setup, &c, &c2, check));
+        }
+        let proof = ResponseProof::prove(key, &setup, &c, &c2, &x, &y, &r2, true);
+        assert!(!proof.verify(key, &setup, &c, &c2, Some(&SECP256K1::g())));
+        assert!(!proof.verify(key, &setup, &c, &c2, None));
+    }
+
+    #[test]
+    fn paillier_setup_proofs() {
+        let prover = DecryptionKey::generate();
+        let verifier = DecryptionKey::generate();
+        let key = prover.encryption_key();
+        let setup = verifier.ring_pedersen();
+
+        let modulus_proof = prover.modulus_proof();
+        assert!(modulus_proof.verify(key));
+        assert!(!modulus_proof.verify(verifier.encryption_key()));
+        let mut tampered = modulus_proof.clone();
+        tampered.rounds[SETUP_PROOF_ROUNDS - 1].1 ^= true;
+        assert!(!tampered.verify(key));
+
+        let ring_pedersen_proof = prover.ring_pedersen_proof();
+        assert!(ring_pedersen_proof.verify(&prover.ring_pedersen()));
+        assert!(!ring_pedersen_proof.verify(&setup));
+        let mut tampered = ring_pedersen_proof.clone();
+        tampered.rounds.pop();
+        assert!(!tampered.verify(&prover.ring_pedersen()));
+
+        let factor_proof = prover.factor_proof(&setup);
+        assert!(factor_proof.verify(key, &setup));
+        assert!(!factor_proof.verify(verifier.encryption_key(), &setup));
+        assert!(!factor_proof.verify(key, &prover.ring_pedersen()));
+        let mut tampered = factor_proof.clone();
+        tampered.z1 = tampered.z1.checked_add(&BigUint::one());
+        assert!(!tampered.verify(key, &setup));
+    }
+}
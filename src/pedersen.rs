@@ -0,0 +1,192 @@
+use crate::curve::{Curve, JacobianPoint, Point};
+use crate::field::Scalar;
+use crate::hash_to_curve::hash_to_curve;
+use crate::ru256::RU256;
+use crate::ruint::ModInt;
+use crate::secp256k1::SECP256K1;
+use crate::secret::PrivateKey;
+use std::sync::OnceLock;
+
+// Pedersen commitments on secp256k1, C = v * G + r * H
+// see: https://link.springer.com/content/pdf/10.1007/3-540-46766-1_9.pdf
+// the value v is hidden by the blinding factor r, and the commitment binds
+// to v as long as nobody knows the discrete logarithm of H to the base G
+//
+// H is a nothing-up-my-sleeve point: the compressed G hashed to the curve with
+// secp256k1_XMD:SHA-256_SSWU_RO_ (see: hash_to_curve.rs), nobody knows its
+// discrete logarithm because the hash picks it, the domain separation tag
+// follows the format of RFC 9380 section 3.1
+//
+// commitments add up like their openings, (v1 + v2, r1 + r2), so the sum of
+// the inputs minus the sum of the outputs is the identity exactly when the
+// values balance and the blinding factors were picked to balance too
+
+/// Domain separation tag of the hash to curve that gives H
+pub const GENERATOR_H_DST: &[u8] = b"ECDSA-PEDERSEN-V01-CS01-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+
+/// The second generator H, hash_to_curve(G, GENERATOR_H_DST) with G compressed
+/// hashed once, every commitment needs it
+pub fn generator_h() -> &'static Point<SECP256K1> {
+    static H: OnceLock<Point<SECP256K1>> = OnceLock::new();
+    H.get_or_init(|| hash_to_curve(&SECP256K1::g().to_compressed(), GENERATOR_H_DST))
+}
+
+/// Pedersen commitment to a value
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commitment {
+    point: Point<SECP256K1>,
+}
+
+impl Commitment {
+    /// Commitment to a value with a blinding factor, v * G + r * H
+    pub fn new(value: u64, blinding: &PrivateKey) -> Self {
+        let value_point = SECP256K1::public_key(&Scalar::reduce(&RU256::from_u64(value)));
+        let blinding_point =
+            SECP256K1::ct_scalar_multiplication(blinding.expose_secret(), generator_h());
+        Self::from_jacobian(&SECP256K1::add_jacobian_points(
+            &JacobianPoint::from(&value_point),
+            &JacobianPoint::from(&blinding_point),
+        ))
+    }
+
+    /// Check that the commitment opens to a value and blinding factor
+    pub fn verify_opening(&self, value: u64, blinding: &PrivateKey) -> bool {
+        *self == Self::new(value, blinding)
+    }
+
+    /// Commitment from its 33 byte SEC1 compressed encoding
+    /// or a single zero byte for the identity, like to_bytes writes it
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes == [0] {
+            return Some(Self::from_jacobian(&SECP256K1::jacobian_zero_point()));
+        }
+        Point::from_compressed(bytes).map(|point| Self { point })
+    }
+
+    /// SEC1 compressed encoding, a single zero byte for the identity
+    /// which is only ever the result of adding or subtracting commitments
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.point.to_compressed()
+    }
+
+    /// Whether the commitment is the identity, a commitment to 0 with blinding 0
+    pub fn is_zero(&self) -> bool {
+        self.point.is_zero_point()
+    }
+
+    /// Commitment to the sum of the values, with the sum of the blinding factors
+    pub fn add(&self, other: &Self) -> Self {
+        Self::from_jacobian(&SECP256K1::add_jacobian_points(
+            &JacobianPoint::from(&self.point),
+            &JacobianPoint::from(&other.point),
+        ))
+    }
+
+    /// Commitment to the difference of the values, with the difference of the blinding factors
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&Self {
+            point: other.point.negate(),
+        })
+    }
+
+    fn from_jacobian(point: &JacobianPoint<SECP256K1>) -> Self {
+        Self {
+            point: SECP256K1::to_affine(point),
+        }
+    }
+}
+
+/// Sum of the positive blinding factors minus the negative ones
+/// the blinding factor of the last output that makes a transaction balance
+pub fn blinding_sum(positive: &[&PrivateKey], negative: &[&PrivateKey]) -> PrivateKey {
+    let mut sum = Scalar::zero();
+    for blinding in positive {
+        sum = &sum + blinding.expose_secret();
+    }
+    for blinding in negative {
+        sum = &sum - blinding.expose_secret();
+    }
+    let blinding = PrivateKey::new(sum.value());
+    sum.wipe();
+    blinding
+}
+
+/// Check that the input commitments and the output commitments sum to the same point
+/// true for committed values and blinding factors that both balance
+pub fn verify_balance(inputs: &[Commitment], outputs: &[Commitment]) -> bool {
+    let sum = |commitments: &[Commitment]| {
+        let zero = Commitment::from_jacobian(&SECP256K1::jacobian_zero_point());
+        commitments
+            .iter()
+            .fold(zero, |sum, commitment| sum.add(commitment))
+    };
+    sum(inputs).sub(&sum(outputs)).is_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::hash_to_curve::hash_to_curve;
+    use crate::pedersen::{blinding_sum, generator_h, verify_balance, Commitment, GENERATOR_H_DST};
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+
+    #[test]
+    fn generator_h_is_hashed_to_the_curve() {
+        let h = Point::from_hex_coordinates(
+            "9ae3c822645dfea77c3ec51bbd5ccca422cafdecf134835f0f92ab353a83a1e5",
+            "928701b7043cdc41c7e707882c625516d291ea15464f498f3b484b2037a4591e",
+        );
+        assert_eq!(generator_h(), &h);
+        assert_eq!(
+            hash_to_curve(&SECP256K1::g().to_compressed(), GENERATOR_H_DST),
+            h
+        );
+        assert!(SECP256K1::is_on_curve(&h));
+        assert_ne!(h, SECP256K1::g());
+    }
+
+    #[test]
+    fn commitments_balance() {
+        let blinding = |byte: u8| PrivateKey::from_bytes(&[byte; 32]).unwrap();
+        let (r1, r2, r3) = (blinding(1), blinding(2), blinding(3));
+
+        let c1 = Commitment::new(40, &r1);
+        assert!(c1.verify_opening(40, &r1));
+        assert!(!c1.verify_opening(41, &r1));
+        assert!(!c1.verify_opening(40, &r2));
+        assert_eq!(Commitment::from_bytes(&c1.to_bytes()), Some(c1.clone()));
+        assert_eq!(c1.to_bytes().len(), 33);
+
+        // the commitments are homomorphic in both the value and the blinding factor
+        let c2 = Commitment::new(2, &r2);
+        let r12 = blinding_sum(&[&r1, &r2], &[]);
+        assert_eq!(c1.add(&c2), Commitment::new(42, &r12));
+        assert_eq!(c1.add(&c2).sub(&c2), c1);
+        assert!(c1.sub(&c1).is_zero());
+        assert_eq!(c1.sub(&c1).to_bytes(), vec![0]);
+        assert_eq!(
+            Commitment::from_bytes(&c1.sub(&c1).to_bytes()),
+            Some(c1.sub(&c1))
+        );
+        assert_eq!(Commitment::from_bytes(&[0, 0]), None);
+
+        // 40 + 2 in, 30 + 12 out, the last output blinding balances the rest
+        let r4 = blinding_sum(&[&r1, &r2], &[&r3]);
+        let inputs = [c1.clone(), c2.clone()];
+        let outputs = [Commitment::new(30, &r3), Commitment::new(12, &r4)];
+        assert!(verify_balance(&inputs, &outputs));
+        assert!(verify_balance(&outputs, &inputs));
+        assert!(verify_balance(&[], &[]));
+        // minting a coin or picking the wrong blinding factor breaks the balance
+        assert!(!verify_balance(
+            &inputs,
+            &[Commitment::new(31, &r3), Commitment::new(12, &r4)]
+        ));
+        assert!(!verify_balance(
+            &inputs,
+            &[Commitment::new(30, &r3), Commitment::new(12, &r3)]
+        ));
+        assert!(!verify_balance(&inputs[..1], &outputs));
+    }
+}
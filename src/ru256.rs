@@ -1,9 +1,13 @@
-use hex;
-use primitive_types::U256;
+use crate::ruint::{ModInt, Montgomery};
+use primitive_types::{U256, U512};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hint::black_box;
+use std::ops::{BitAnd, BitOr, Not};
 use std::str::FromStr;
 
 #[derive(Clone, Debug)]
-pub(crate) struct RU256 {
+pub struct RU256 {
     pub(crate) v: U256,
 }
 
@@ -19,17 +23,31 @@ impl FromStr for RU256 {
     }
 }
 
-impl ToString for RU256 {
-    fn to_string(&self) -> String {
+impl fmt::Display for RU256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut bytes: [u8; 32] = [0; 32];
         self.v.to_big_endian(&mut bytes);
-        hex::encode(bytes)
+        write!(f, "{}", hex::encode(bytes))
     }
 }
 
 impl PartialEq for RU256 {
     fn eq(&self, other: &Self) -> bool {
-        return self.v == other.v;
+        self.v == other.v
+    }
+}
+
+impl Eq for RU256 {}
+
+impl PartialOrd for RU256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RU256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.v.cmp(&other.v)
     }
 }
 
@@ -136,47 +154,22 @@ impl RU256 {
         let x2 = b.v.checked_rem(p.v).expect("mod");
 
         let x2_complement = Self { v: p.v - x2 };
-        let x3 = Self { v: x1 }.add_mod(&x2_complement, p);
-
-        x3
+        Self { v: x1 }.add_mod(&x2_complement, p)
     }
 
     /// Modular multiplication
     pub fn mul_mod(&self, b: &RU256, p: &RU256) -> Self {
-        // multiplication can be thought of a repeated addition
-        // were a * n = a + a + a .. + a n times
-        // the above above algorithm is linear in n
-        // we can do this in log(n) time using the
-        // double-add algorithm: see: https://en.wikipedia.org/wiki/Elliptic_curve_point_multiplication#Double-and-add
-
-        // modularize each input first
-        let x1 = self.v.checked_rem(p.v).expect("mod");
-        let x2 = b.v.checked_rem(p.v).expect("mod");
-
-        // n * b = b * n
-        // we can either repeat b n times or n b times
-        // to reduce the number of operations we should repeat the smaller of the 2
-        let (seq, adder) = match x1 < x2 {
-            true => (x1, x2),
-            _ => (x2, x1),
-        };
-
-        // set the result to the additive identity element
-        let mut result = Self::zero();
-        let mut adder = Self { v: adder };
-
-        // Double-Add algorithm
-        let seq_bit_size = seq.bits();
-        for i in 0..seq_bit_size {
-            if seq.bit(i) {
-                // current bit is set, add to result
-                result = result.add_mod(&adder, &p);
-            }
-            // double the adder
-            adder = adder.add_mod(&adder, &p);
+        // the product of two 256 bit numbers fits in 512 bits, so we
+        // multiply into a U512 and reduce once, rather than reducing
+        // after every step of a double-add loop
+        // this turns out to be the hot spot of every curve operation
+        // (inversions are ~512 multiplications each)
+        let product = self.v.full_mul(b.v) % U512::from(p.v);
+
+        // the remainder is less than p, so it always fits back into 256 bits
+        Self {
+            v: U256::try_from(product).expect("reduced product fits in 256 bits"),
         }
-
-        result
     }
 
     /// Modular exponentiation
@@ -196,9 +189,9 @@ impl RU256 {
         let seq_bit_size = e.v.bits();
         for i in 0..seq_bit_size {
             if e.v.bit(i) {
-                result = result.mul_mod(&multiplier, &p);
+                result = result.mul_mod(&multiplier, p);
             }
-            multiplier = multiplier.mul_mod(&multiplier, &p);
+            multiplier = multiplier.mul_mod(&multiplier, p);
         }
 
         result
@@ -218,13 +211,421 @@ impl RU256 {
         // p must be greater than 2
         assert!(p.v - 2 > U256::from_big_endian(&[0]));
 
-        return self.mul_mod(&b.exp_mod(&RU256 { v: p.v - 2 }, &p), &p);
+        self.mul_mod(&b.exp_mod(&RU256 { v: p.v - 2 }, p), p)
+    }
+}
+
+/// Result of a constant time comparison
+/// holds 1 for true and 0 for false, so it can be turned into a limb mask
+/// without branching on the value
+#[derive(Clone, Copy, Debug)]
+pub struct Choice(u64);
+
+impl Choice {
+    /// Choice from a single bit (must be 0 or 1)
+    pub(crate) fn from_bit(bit: u64) -> Self {
+        // black_box stops the compiler from reasoning about the value
+        // and turning later selects back into branches
+        Choice(black_box(bit & 1))
+    }
+
+    /// All ones if true, all zeros if false
+    pub(crate) fn mask(self) -> u64 {
+        0u64.wrapping_sub(self.0)
+    }
+
+    /// Leave constant time land, only use when the result is not secret
+    pub fn is_true(self) -> bool {
+        self.0 == 1
+    }
+}
+
+impl From<bool> for Choice {
+    fn from(value: bool) -> Self {
+        Choice::from_bit(value as u64)
+    }
+}
+
+impl BitAnd for Choice {
+    type Output = Choice;
+
+    fn bitand(self, rhs: Choice) -> Choice {
+        Choice::from_bit(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Choice {
+    type Output = Choice;
+
+    fn bitor(self, rhs: Choice) -> Choice {
+        Choice::from_bit(self.0 | rhs.0)
+    }
+}
+
+impl Not for Choice {
+    type Output = Choice;
+
+    fn not(self) -> Choice {
+        Choice::from_bit(self.0 ^ 1)
+    }
+}
+
+/// Add with carry: a + b + carry, returns (sum, carry out)
+pub(crate) fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let wide = a as u128 + b as u128 + carry as u128;
+    (wide as u64, (wide >> 64) as u64)
+}
+
+/// Subtract with borrow: a - b - borrow, returns (difference, borrow out)
+pub(crate) fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let wide = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (wide as u64, (wide >> 127) as u64)
+}
+
+/// Limb wise addition, returns the wrapped sum and the carry out
+pub(crate) fn add_limbs<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], u64) {
+    let mut sum = [0u64; N];
+    let mut carry = 0;
+    for i in 0..N {
+        (sum[i], carry) = adc(a[i], b[i], carry);
+    }
+    (sum, carry)
+}
+
+/// Limb wise subtraction, returns the wrapped difference and the borrow out
+pub(crate) fn sub_limbs<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], u64) {
+    let mut diff = [0u64; N];
+    let mut borrow = 0;
+    for i in 0..N {
+        (diff[i], borrow) = sbb(a[i], b[i], borrow);
+    }
+    (diff, borrow)
+}
+
+/// Pick b if choice is set, otherwise a
+pub(crate) fn select_limbs<const N: usize>(a: &[u64; N], b: &[u64; N], choice: Choice) -> [u64; N] {
+    let mask = choice.mask();
+    let mut result = [0u64; N];
+    for i in 0..N {
+        result[i] = a[i] ^ (mask & (a[i] ^ b[i]));
+    }
+    result
+}
+
+impl RU256 {
+    // Constant time operations
+    // the operations above branch on their inputs (overflow checks, bit by bit loops,
+    // variable time division) which leaks information about secret values through timing
+    // the versions below touch every limb and every bit the same way regardless of the
+    // values, choosing results with masks instead of branches
+    // they expect their inputs to already be reduced mod p (except ct_mul_mod)
+
+    /// Constant time equality
+    pub fn ct_eq(&self, other: &RU256) -> Choice {
+        let mut diff = 0u64;
+        for i in 0..4 {
+            diff |= self.v.0[i] ^ other.v.0[i];
+        }
+        // diff | -diff has its top bit set iff diff != 0
+        Choice::from_bit(!((diff | diff.wrapping_neg()) >> 63))
+    }
+
+    /// Constant time less than
+    pub fn ct_lt(&self, other: &RU256) -> Choice {
+        // self < other iff self - other borrows
+        let (_, borrow) = sub_limbs(&self.v.0, &other.v.0);
+        Choice::from_bit(borrow)
+    }
+
+    /// Returns b if choice is set, otherwise a
+    pub fn conditional_select(a: &RU256, b: &RU256, choice: Choice) -> RU256 {
+        RU256 {
+            v: U256(select_limbs(&a.v.0, &b.v.0, choice)),
+        }
+    }
+
+    /// Swaps a and b if choice is set
+    pub fn conditional_swap(a: &mut RU256, b: &mut RU256, choice: Choice) {
+        let mask = choice.mask();
+        for i in 0..4 {
+            let t = mask & (a.v.0[i] ^ b.v.0[i]);
+            a.v.0[i] ^= t;
+            b.v.0[i] ^= t;
+        }
+    }
+
+    /// Constant time modular addition
+    pub fn ct_add_mod(&self, b: &RU256, p: &RU256) -> Self {
+        // both inputs are less than p, so the sum is less than 2p
+        // and at most one subtraction of p brings it back into range
+        let (sum, carry) = add_limbs(&self.v.0, &b.v.0);
+        let (reduced, borrow) = sub_limbs(&sum, &p.v.0);
+
+        // use the reduced value if the sum overflowed 256 bits
+        // or if it did not need to borrow (sum >= p)
+        let use_reduced = Choice::from_bit(carry) | !Choice::from_bit(borrow);
+
+        RU256 {
+            v: U256(select_limbs(&sum, &reduced, use_reduced)),
+        }
+    }
+
+    /// Constant time modular subtraction
+    pub fn ct_sub_mod(&self, b: &RU256, p: &RU256) -> Self {
+        // if a - b borrows, the wrapped result is off by 2^256 - p
+        // adding p (and dropping the carry) fixes it
+        let (diff, borrow) = sub_limbs(&self.v.0, &b.v.0);
+        let (corrected, _) = add_limbs(&diff, &p.v.0);
+
+        RU256 {
+            v: U256(select_limbs(&diff, &corrected, Choice::from_bit(borrow))),
+        }
+    }
+
+    /// Constant time modular multiplication
+    pub fn ct_mul_mod(&self, b: &RU256, p: &RU256) -> Self {
+        // schoolbook multiplication into 8 limbs
+        // every limb pair is multiplied, no matter the values
+        let mut product = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u64;
+            for j in 0..4 {
+                let wide =
+                    self.v.0[i] as u128 * b.v.0[j] as u128 + product[i + j] as u128 + carry as u128;
+                product[i + j] = wide as u64;
+                carry = (wide >> 64) as u64;
+            }
+            product[i + 4] = carry;
+        }
+
+        // reduce one bit at a time from the top, the same idea as long division
+        // result = 2 * result + bit, then subtract p if result >= p
+        // result stays below p, so 2 * result + 1 fits in 257 bits
+        // (the extra bit is the carry out of the shift)
+        let mut result = [0u64; 4];
+        for i in (0..512).rev() {
+            let bit = (product[i / 64] >> (i % 64)) & 1;
+
+            let mut shifted = [0u64; 4];
+            let mut carry = bit;
+            for j in 0..4 {
+                shifted[j] = (result[j] << 1) | carry;
+                carry = result[j] >> 63;
+            }
+
+            let (reduced, borrow) = sub_limbs(&shifted, &p.v.0);
+            let use_reduced = Choice::from_bit(carry) | !Choice::from_bit(borrow);
+            result = select_limbs(&shifted, &reduced, use_reduced);
+        }
+
+        RU256 { v: U256(result) }
+    }
+
+    /// Constant time reduction of any 256 bit value mod p
+    pub fn ct_reduce(&self, p: &RU256) -> Self {
+        // ct_mul_mod already reduces arbitrary products
+        self.ct_mul_mod(&Self::one(), p)
+    }
+
+    /// Constant time modular division
+    pub fn ct_div_mod(&self, b: &RU256, p: &RU256) -> Self {
+        // same as div_mod: a / b mod p = a * b^(p-2) mod p
+        // the square-multiply loop only branches on the bits of p - 2
+        // which is public, the secret b only goes through ct_mul_mod
+        assert!(p.v - 2 > U256::from_big_endian(&[0]));
+
+        let e = p.v - 2;
+        let mut result = Self::one();
+        let mut multiplier = b.clone();
+        for i in 0..e.bits() {
+            if e.bit(i) {
+                result = result.ct_mul_mod(&multiplier, p);
+            }
+            multiplier = multiplier.ct_mul_mod(&multiplier, p);
+        }
+
+        self.ct_mul_mod(&result, p)
+    }
+}
+
+// the shared modular API, see: ruint.rs
+// RU256's own methods take priority over the trait ones, so these just forward
+impl ModInt for RU256 {
+    fn zero() -> Self {
+        RU256::zero()
+    }
+
+    fn one() -> Self {
+        RU256::one()
+    }
+
+    fn from_u64(value: u64) -> Self {
+        Self {
+            v: U256::from(value),
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        RU256::from_bytes(bytes)
+    }
+
+    fn to_bytes(&self, bytes: &mut [u8]) {
+        // U256 only writes exactly 32 bytes, pad or trim to the slice
+        let mut full = [0u8; 32];
+        self.v.to_big_endian(&mut full);
+        if bytes.len() >= 32 {
+            let padding = bytes.len() - 32;
+            bytes[..padding].fill(0);
+            bytes[padding..].copy_from_slice(&full);
+        } else {
+            assert!(self.bits() <= 8 * bytes.len(), "value does not fit");
+            bytes.copy_from_slice(&full[32 - bytes.len()..]);
+        }
+    }
+
+    fn limbs(&self) -> &[u64] {
+        &self.v.0
+    }
+
+    fn limbs_mut(&mut self) -> &mut [u64] {
+        &mut self.v.0
+    }
+
+    fn is_zero(&self) -> bool {
+        RU256::is_zero(self)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Self {
+        Self {
+            v: self.v.checked_sub(other.v).expect("subtraction underflow"),
+        }
+    }
+
+    fn reduce(&self, p: &Self) -> Self {
+        Self {
+            v: self.v.checked_rem(p.v).expect("mod"),
+        }
+    }
+
+    fn add_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::add_mod(self, b, p)
+    }
+
+    fn sub_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::sub_mod(self, b, p)
+    }
+
+    fn mul_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::mul_mod(self, b, p)
+    }
+
+    fn exp_mod(&self, e: &Self, p: &Self) -> Self {
+        RU256::exp_mod(self, e, p)
+    }
+
+    fn div_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::div_mod(self, b, p)
+    }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        RU256::ct_eq(self, other)
+    }
+
+    fn ct_lt(&self, other: &Self) -> Choice {
+        RU256::ct_lt(self, other)
+    }
+
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        RU256::conditional_select(a, b, choice)
+    }
+
+    fn conditional_swap(a: &mut Self, b: &mut Self, choice: Choice) {
+        RU256::conditional_swap(a, b, choice)
+    }
+
+    fn ct_add_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::ct_add_mod(self, b, p)
+    }
+
+    fn ct_sub_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::ct_sub_mod(self, b, p)
+    }
+
+    fn ct_mul_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::ct_mul_mod(self, b, p)
+    }
+
+    fn ct_reduce(&self, p: &Self) -> Self {
+        RU256::ct_reduce(self, p)
+    }
+
+    fn ct_div_mod(&self, b: &Self, p: &Self) -> Self {
+        RU256::ct_div_mod(self, b, p)
+    }
+
+    type Constants = Montgomery<4>;
+
+    fn modulus(constants: &Self::Constants) -> Self {
+        Self {
+            v: U256(*constants.modulus()),
+        }
+    }
+
+    fn ct_mul_mod_with(&self, b: &Self, constants: &Self::Constants) -> Self {
+        Self {
+            v: U256(constants.mul(&self.v.0, &b.v.0)),
+        }
+    }
+
+    fn ct_div_mod_with(&self, b: &Self, constants: &Self::Constants) -> Self {
+        Self {
+            v: U256(constants.div(&self.v.0, &b.v.0)),
+        }
+    }
+}
+
+/// Batch modular inversion
+/// replaces every element with its inverse mod p using a single division
+/// elements congruent to zero have no inverse and are set to zero
+pub fn batch_inv_mod<T: ModInt>(values: &mut [T], p: &T) {
+    // Montgomery's trick: see https://en.wikipedia.org/wiki/Modular_multiplicative_inverse#Multiple_inverses
+    // with running products a, ab, abc
+    // 1 / abc gives us 1 / c when multiplied by ab
+    // and multiplying 1 / abc by c gives 1 / ab, which we can keep peeling
+
+    // zero elements are skipped, otherwise they would zero out the
+    // running product and every other inverse along with it
+    for value in values.iter_mut() {
+        *value = value.reduce(p);
+    }
+
+    // prefix[i] holds the product of all non-zero elements before i
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut acc = T::one();
+    for value in values.iter() {
+        prefix.push(acc.clone());
+        if !value.is_zero() {
+            acc = acc.mul_mod(value, p);
+        }
+    }
+
+    // the only division in the whole batch
+    let mut acc_inv = T::one().div_mod(&acc, p);
+
+    // walk backwards, peeling one element off the inverted product at a time
+    for (value, prefix) in values.iter_mut().zip(prefix).rev() {
+        if value.is_zero() {
+            continue;
+        }
+        let value_inv = acc_inv.mul_mod(&prefix, p);
+        acc_inv = acc_inv.mul_mod(value, p);
+        *value = value_inv;
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ru256::RU256;
+    use crate::ru256::{batch_inv_mod, Choice, RU256};
     use std::str::FromStr;
 
     #[test]
@@ -341,4 +742,87 @@ mod tests {
             "0000000000000000000000000000000000000000000000000000000000061f57"
         );
     }
+
+    #[test]
+    fn ru256_batch_inversion_case() {
+        let p = RU256::from_str("0xf3fa3").unwrap(); // p = 99933.unwrap();
+        let values = [
+            RU256::from_str("0x1ce606").unwrap(),
+            RU256::zero(),
+            RU256::from_str("0xacc12484").unwrap(),
+            RU256::from_str("0xf3fa3").unwrap(), // congruent to zero
+            RU256::one(),
+        ];
+
+        let mut inverses = values.clone();
+        batch_inv_mod(&mut inverses, &p);
+
+        for (value, inverse) in values.iter().zip(inverses.iter()) {
+            if value.v % p.v == RU256::zero().v {
+                assert!(inverse.is_zero());
+            } else {
+                assert_eq!(inverse, &RU256::one().div_mod(value, &p));
+            }
+        }
+
+        // nothing to invert
+        batch_inv_mod(&mut [], &p);
+    }
+
+    #[test]
+    fn ru256_constant_time_comparison() {
+        let a = RU256::from_str("0x1ce606").unwrap();
+        let b = RU256::from_str("0xacc12484").unwrap();
+
+        assert!(a.ct_eq(&a.clone()).is_true());
+        assert!(!a.ct_eq(&b).is_true());
+        assert!(a.ct_lt(&b).is_true());
+        assert!(!b.ct_lt(&a).is_true());
+        assert!(!a.ct_lt(&a).is_true());
+
+        assert_eq!(RU256::conditional_select(&a, &b, Choice::from(false)), a);
+        assert_eq!(RU256::conditional_select(&a, &b, Choice::from(true)), b);
+
+        let (mut x, mut y) = (a.clone(), b.clone());
+        RU256::conditional_swap(&mut x, &mut y, Choice::from(false));
+        assert_eq!((&x, &y), (&a, &b));
+        RU256::conditional_swap(&mut x, &mut y, Choice::from(true));
+        assert_eq!((&x, &y), (&b, &a));
+    }
+
+    #[test]
+    fn ru256_constant_time_arithmetic_matches() {
+        let moduli = [
+            RU256::from_str("0xf3fa3").unwrap(),
+            RU256::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F")
+                .unwrap(),
+            RU256::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141")
+                .unwrap(),
+        ];
+        let values = [
+            RU256::zero(),
+            RU256::one(),
+            RU256::from_str("0x1ce606").unwrap(),
+            RU256::from_str("0xacc12484").unwrap(),
+            RU256::from_str("79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798")
+                .unwrap(),
+            RU256::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2E")
+                .unwrap(),
+        ];
+
+        for p in moduli.iter() {
+            let reduced: Vec<RU256> = values.iter().map(|v| v.ct_reduce(p)).collect();
+            for a in reduced.iter() {
+                assert_eq!(a, &a.add_mod(&RU256::zero(), p));
+                for b in reduced.iter() {
+                    assert_eq!(a.ct_add_mod(b, p), a.add_mod(b, p));
+                    assert_eq!(a.ct_sub_mod(b, p), a.sub_mod(b, p));
+                    assert_eq!(a.ct_mul_mod(b, p), a.mul_mod(b, p));
+                    if !b.is_zero() {
+                        assert_eq!(a.ct_div_mod(b, p), a.div_mod(b, p));
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,884 @@
+use crate::ru256::{add_limbs, sbb, select_limbs, sub_limbs, Choice, RU256ParseError};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Unsigned integer with the modular arithmetic the curves are built on
+///
+/// RU256 covers the 256 bit curves, RUint the wider ones, and the field,
+/// curve and signature code only talks to this trait so it works with either
+/// the variable time and constant time operations follow the RU256 ones
+/// (see: ru256.rs), including which inputs have to be reduced
+pub trait ModInt:
+    Clone + fmt::Debug + fmt::Display + Eq + Ord + FromStr<Err = RU256ParseError>
+{
+    /// Additive Identity
+    fn zero() -> Self;
+
+    /// Multiplicative Identity
+    fn one() -> Self;
+
+    /// Small constant
+    fn from_u64(value: u64) -> Self;
+
+    /// From big endian bytes, panics if they don't fit
+    fn from_bytes(bytes: &[u8]) -> Self;
+
+    /// To big endian bytes, filling the whole slice
+    fn to_bytes(&self, bytes: &mut [u8]);
+
+    /// Little endian 64 bit limbs
+    fn limbs(&self) -> &[u64];
+
+    /// Little endian 64 bit limbs, mutable
+    fn limbs_mut(&mut self) -> &mut [u64];
+
+    /// Check if additive identity
+    fn is_zero(&self) -> bool {
+        self.limbs().iter().all(|limb| *limb == 0)
+    }
+
+    /// Number of significant bits
+    fn bits(&self) -> usize {
+        let limbs = self.limbs();
+        match limbs.iter().rposition(|limb| *limb != 0) {
+            Some(i) => 64 * i + 64 - limbs[i].leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// Value of the i-th bit, from the least significant
+    fn bit(&self, i: usize) -> bool {
+        let limbs = self.limbs();
+        i / 64 < limbs.len() && (limbs[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    /// Shift right by the given number of bits
+    fn shr(&self, bits: usize) -> Self {
+        let mut result = Self::zero();
+        let limbs = self.limbs();
+        let (limb_shift, bit_shift) = (bits / 64, bits % 64);
+        for (i, limb) in result.limbs_mut().iter_mut().enumerate() {
+            let low = limbs.get(i + limb_shift).copied().unwrap_or(0);
+            let high = limbs.get(i + limb_shift + 1).copied().unwrap_or(0);
+            *limb = if bit_shift == 0 {
+                low
+            } else {
+                (low >> bit_shift) | (high << (64 - bit_shift))
+            };
+        }
+        result
+    }
+
+    /// Subtraction without a modulus, panics if other is larger
+    fn checked_sub(&self, other: &Self) -> Self;
+
+    /// Reduce mod p
+    fn reduce(&self, p: &Self) -> Self;
+
+    fn add_mod(&self, b: &Self, p: &Self) -> Self;
+    fn sub_mod(&self, b: &Self, p: &Self) -> Self;
+    fn mul_mod(&self, b: &Self, p: &Self) -> Self;
+    fn exp_mod(&self, e: &Self, p: &Self) -> Self;
+    fn div_mod(&self, b: &Self, p: &Self) -> Self;
+
+    fn ct_eq(&self, other: &Self) -> Choice;
+    fn ct_lt(&self, other: &Self) -> Choice;
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self;
+    fn conditional_swap(a: &mut Self, b: &mut Self, choice: Choice);
+    fn ct_add_mod(&self, b: &Self, p: &Self) -> Self;
+    fn ct_sub_mod(&self, b: &Self, p: &Self) -> Self;
+    fn ct_mul_mod(&self, b: &Self, p: &Self) -> Self;
+    fn ct_reduce(&self, p: &Self) -> Self;
+    fn ct_div_mod(&self, b: &Self, p: &Self) -> Self;
+
+    /// Precomputed constants of a modulus for the constant time multiplication
+    /// and division, so a fixed modulus only pays for them once (see: Montgomery)
+    type Constants;
+
+    /// The modulus the constants were computed for
+    fn modulus(constants: &Self::Constants) -> Self;
+
+    /// ct_mul_mod with the constants of p, one of the inputs has to be reduced
+    fn ct_mul_mod_with(&self, b: &Self, constants: &Self::Constants) -> Self;
+
+    /// ct_div_mod with the constants of p
+    fn ct_div_mod_with(&self, b: &Self, constants: &Self::Constants) -> Self;
+}
+
+/// Fixed width unsigned integer made of LIMBS 64 bit limbs
+/// same modular API as RU256, for moduli wider than 256 bits
+/// e.g. RUint<6> for P-384 and RUint<9> for P-521
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RUint<const LIMBS: usize> {
+    /// little endian limbs
+    pub(crate) v: [u64; LIMBS],
+}
+
+impl<const LIMBS: usize> FromStr for RUint<LIMBS> {
+    type Err = RU256ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // conversion from a hex string
+        Self::from_str_radix(s, 16)
+    }
+}
+
+impl<const LIMBS: usize> fmt::Display for RUint<LIMBS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = vec![0u8; 8 * LIMBS];
+        self.to_bytes(&mut bytes);
+        write!(f, "{}", hex::encode(bytes))
+    }
+}
+
+impl<const LIMBS: usize> PartialOrd for RUint<LIMBS> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const LIMBS: usize> Ord for RUint<LIMBS> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // compare from the most significant limb down
+        self.v.iter().rev().cmp(other.v.iter().rev())
+    }
+}
+
+impl<const LIMBS: usize> RUint<LIMBS> {
+    /// RUint from number string
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, RU256ParseError> {
+        let s = match radix {
+            16 => s.trim_start_matches("0x").trim_start_matches("0X"),
+            _ => s,
+        };
+        if s.is_empty() {
+            return Err(RU256ParseError);
+        }
+
+        // result = result * radix + digit, failing on overflow
+        let mut result = Self::zero();
+        for c in s.chars() {
+            let mut carry = c.to_digit(radix).ok_or(RU256ParseError)? as u128;
+            for limb in result.v.iter_mut() {
+                let wide = *limb as u128 * radix as u128 + carry;
+                *limb = wide as u64;
+                carry = wide >> 64;
+            }
+            if carry != 0 {
+                return Err(RU256ParseError);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Full product, 2 * LIMBS little endian limbs
+    pub(crate) fn full_mul(&self, other: &Self) -> Vec<u64> {
+        mul_limbs(&self.v, &other.v)
+    }
+
+    /// Sum, panics if it doesn't fit
+    pub(crate) fn checked_add(&self, other: &Self) -> Self {
+        let (v, carry) = add_limbs(&self.v, &other.v);
+        assert!(carry == 0, "addition overflow");
+        Self { v }
+    }
+
+    /// Product, panics if it doesn't fit
+    pub(crate) fn checked_mul(&self, other: &Self) -> Self {
+        Self::from_limbs(&mul_limbs(
+            significant_limbs(&self.v),
+            significant_limbs(&other.v),
+        ))
+    }
+
+    /// Quotient and remainder, variable time
+    pub(crate) fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        let (quotient, remainder) = div_rem_limbs(&self.v, &divisor.v);
+        (Self::from_limbs(&quotient), Self::from_limbs(&remainder))
+    }
+
+    /// RUint from little endian limbs, the extra ones must be zero
+    fn from_limbs(limbs: &[u64]) -> Self {
+        let mut result = Self::zero();
+        for (i, limb) in limbs.iter().enumerate() {
+            if i < LIMBS {
+                result.v[i] = *limb;
+            } else {
+                assert!(*limb == 0, "value does not fit");
+            }
+        }
+        result
+    }
+}
+
+impl<const LIMBS: usize> ModInt for RUint<LIMBS> {
+    fn zero() -> Self {
+        Self { v: [0; LIMBS] }
+    }
+
+    fn one() -> Self {
+        Self::from_u64(1)
+    }
+
+    fn from_u64(value: u64) -> Self {
+        let mut result = Self::zero();
+        result.v[0] = value;
+        result
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        // can't be more than 8 * LIMBS bytes
+        assert!(bytes.len() <= 8 * LIMBS);
+        let mut result = Self::zero();
+        for (i, byte) in bytes.iter().rev().enumerate() {
+            result.v[i / 8] |= (*byte as u64) << (8 * (i % 8));
+        }
+        result
+    }
+
+    fn to_bytes(&self, bytes: &mut [u8]) {
+        assert!(self.bits() <= 8 * bytes.len(), "value does not fit");
+        for (i, byte) in bytes.iter_mut().rev().enumerate() {
+            *byte = match self.v.get(i / 8) {
+                Some(limb) => (limb >> (8 * (i % 8))) as u8,
+                None => 0,
+            };
+        }
+    }
+
+    fn limbs(&self) -> &[u64] {
+        &self.v
+    }
+
+    fn limbs_mut(&mut self) -> &mut [u64] {
+        &mut self.v
+    }
+
+    fn checked_sub(&self, other: &Self) -> Self {
+        let (v, borrow) = sub_limbs(&self.v, &other.v);
+        assert!(borrow == 0, "subtraction underflow");
+        Self { v }
+    }
+
+    fn reduce(&self, p: &Self) -> Self {
+        Self::from_limbs(&rem_limbs(&self.v, &p.v))
+    }
+
+    fn add_mod(&self, b: &Self, p: &Self) -> Self {
+        // modularize each input first, so the sum is less than 2p
+        // and at most one subtraction brings it back
+        let (x1, x2) = (self.reduce(p), b.reduce(p));
+        let (sum, carry) = add_limbs(&x1.v, &x2.v);
+        let sum = Self { v: sum };
+        if carry == 1 || &sum >= p {
+            let (v, _) = sub_limbs(&sum.v, &p.v);
+            return Self { v };
+        }
+        sum
+    }
+
+    fn sub_mod(&self, b: &Self, p: &Self) -> Self {
+        // if a - b borrows, adding p (and dropping the carry) fixes it
+        let (x1, x2) = (self.reduce(p), b.reduce(p));
+        let (diff, borrow) = sub_limbs(&x1.v, &x2.v);
+        if borrow == 1 {
+            let (v, _) = add_limbs(&diff, &p.v);
+            return Self { v };
+        }
+        Self { v: diff }
+    }
+
+    fn mul_mod(&self, b: &Self, p: &Self) -> Self {
+        // multiply into 2 * LIMBS limbs and reduce once, leaving out the
+        // zero top limbs, which matters for small numbers in wide types
+        let product = mul_limbs(significant_limbs(&self.v), significant_limbs(&b.v));
+        Self::from_limbs(&rem_limbs(&product, &p.v))
+    }
+
+    fn exp_mod(&self, e: &Self, p: &Self) -> Self {
+        // square-multiply, see: exp_mod in ru256.rs
+        let mut result = Self::one().reduce(p);
+        let mut multiplier = self.reduce(p);
+        for i in 0..e.bits() {
+            if e.bit(i) {
+                result = result.mul_mod(&multiplier, p);
+            }
+            multiplier = multiplier.mul_mod(&multiplier, p);
+        }
+        result
+    }
+
+    fn div_mod(&self, b: &Self, p: &Self) -> Self {
+        // Fermat's little theorem, see: div_mod in ru256.rs
+        let e = p.checked_sub(&Self::from_u64(2));
+        self.mul_mod(&b.exp_mod(&e, p), p)
+    }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let mut diff = 0u64;
+        for i in 0..LIMBS {
+            diff |= self.v[i] ^ other.v[i];
+        }
+        // diff | -diff has its top bit set iff diff != 0
+        Choice::from_bit(!((diff | diff.wrapping_neg()) >> 63))
+    }
+
+    fn ct_lt(&self, other: &Self) -> Choice {
+        // self < other iff self - other borrows
+        let (_, borrow) = sub_limbs(&self.v, &other.v);
+        Choice::from_bit(borrow)
+    }
+
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self {
+            v: select_limbs(&a.v, &b.v, choice),
+        }
+    }
+
+    fn conditional_swap(a: &mut Self, b: &mut Self, choice: Choice) {
+        let mask = choice.mask();
+        for i in 0..LIMBS {
+            let t = mask & (a.v[i] ^ b.v[i]);
+            a.v[i] ^= t;
+            b.v[i] ^= t;
+        }
+    }
+
+    fn ct_add_mod(&self, b: &Self, p: &Self) -> Self {
+        // see: ct_add_mod in ru256.rs
+        let (sum, carry) = add_limbs(&self.v, &b.v);
+        let (reduced, borrow) = sub_limbs(&sum, &p.v);
+        let use_reduced = Choice::from_bit(carry) | !Choice::from_bit(borrow);
+        Self {
+            v: select_limbs(&sum, &reduced, use_reduced),
+        }
+    }
+
+    fn ct_sub_mod(&self, b: &Self, p: &Self) -> Self {
+        // see: ct_sub_mod in ru256.rs
+        let (diff, borrow) = sub_limbs(&self.v, &b.v);
+        let (corrected, _) = add_limbs(&diff, &p.v);
+        Self {
+            v: select_limbs(&diff, &corrected, Choice::from_bit(borrow)),
+        }
+    }
+
+    fn ct_mul_mod(&self, b: &Self, p: &Self) -> Self {
+        // the bit by bit reduction of ru256.rs gets slow with wide numbers
+        // so this goes through montgomery form instead, the constants
+        // are worked out for this one product, see: ct_mul_mod_with
+        self.ct_mul_mod_with(b, &Montgomery::new(p.v))
+    }
+
+    fn ct_reduce(&self, p: &Self) -> Self {
+        // a * 1 mod p
+        self.ct_mul_mod(&Self::one(), p)
+    }
+
+    fn ct_div_mod(&self, b: &Self, p: &Self) -> Self {
+        self.ct_div_mod_with(b, &Montgomery::new(p.v))
+    }
+
+    type Constants = Montgomery<LIMBS>;
+
+    fn modulus(constants: &Self::Constants) -> Self {
+        Self {
+            v: *constants.modulus(),
+        }
+    }
+
+    fn ct_mul_mod_with(&self, b: &Self, constants: &Self::Constants) -> Self {
+        Self {
+            v: constants.mul(&self.v, &b.v),
+        }
+    }
+
+    fn ct_div_mod_with(&self, b: &Self, constants: &Self::Constants) -> Self {
+        Self {
+            v: constants.div(&self.v, &b.v),
+        }
+    }
+}
+
+/// Constants for constant time Montgomery multiplication mod an odd p,
+/// -p^-1 mod 2^64 and R^2 mod p with R = 2^(64 * LIMBS)
+/// they only depend on the (public) modulus, so the curves work them out
+/// at compile time (see: Curve::P_MONTGOMERY)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Montgomery<const LIMBS: usize> {
+    p: [u64; LIMBS],
+    p_inv: u64,
+    r_squared: [u64; LIMBS],
+}
+
+impl<const LIMBS: usize> Montgomery<LIMBS> {
+    /// Constants for a modulus given as big endian hex digits
+    pub const fn from_hex(hex: &str) -> Self {
+        Self::new(limbs_from_hex(hex))
+    }
+
+    /// Constants for a modulus given as little endian limbs
+    pub const fn new(p: [u64; LIMBS]) -> Self {
+        assert!(
+            p[0] & 1 == 1,
+            "montgomery multiplication needs an odd modulus"
+        );
+
+        // newton iteration, every step doubles the number of correct bits
+        let mut inv = 1u64;
+        let mut i = 0;
+        while i < 6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p[0].wrapping_mul(inv)));
+            i += 1;
+        }
+
+        // R^2 mod p by doubling 1 mod p 2 * 64 * LIMBS times
+        // there is no division in a const fn, and this only runs once per modulus
+        let mut r_squared = [0u64; LIMBS];
+        r_squared[0] = 1;
+        let mut i = 0;
+        while i < 128 * LIMBS {
+            let mut carry = 0;
+            let mut j = 0;
+            while j < LIMBS {
+                let limb = r_squared[j];
+                r_squared[j] = (limb << 1) | carry;
+                carry = limb >> 63;
+                j += 1;
+            }
+            let (reduced, borrow) = const_sub_limbs(&r_squared, &p);
+            if carry == 1 || borrow == 0 {
+                r_squared = reduced;
+            }
+            i += 1;
+        }
+
+        Self {
+            p,
+            p_inv: inv.wrapping_neg(),
+            r_squared,
+        }
+    }
+
+    /// The modulus p
+    pub fn modulus(&self) -> &[u64; LIMBS] {
+        &self.p
+    }
+
+    /// a * b mod p, constant time
+    /// one of the inputs has to be reduced for the first product to be in range
+    pub fn mul(&self, a: &[u64; LIMBS], b: &[u64; LIMBS]) -> [u64; LIMBS] {
+        // (a * b / R) * R^2 / R = a * b mod p
+        let product = self.montgomery_mul(a, b);
+        self.montgomery_mul(&product, &self.r_squared)
+    }
+
+    /// a / b mod p, constant time, p has to be prime
+    pub fn div(&self, a: &[u64; LIMBS], b: &[u64; LIMBS]) -> [u64; LIMBS] {
+        // a / b mod p = a * b^(p-2) mod p
+        // the exponentiation stays in montgomery form (x * R) the whole time
+        // and only branches on the bits of p - 2, which is public
+        let mut two = [0u64; LIMBS];
+        two[0] = 2;
+        let (e, _) = sub_limbs(&self.p, &two);
+        let e = RUint { v: e };
+
+        let mut one = [0u64; LIMBS];
+        one[0] = 1;
+        let mut result = self.montgomery_mul(&one, &self.r_squared);
+        let mut multiplier = self.montgomery_mul(b, &self.r_squared);
+        for i in 0..e.bits() {
+            if e.bit(i) {
+                result = self.montgomery_mul(&result, &multiplier);
+            }
+            multiplier = self.montgomery_mul(&multiplier, &multiplier);
+        }
+
+        // multiplying by a (not in montgomery form) drops the extra R
+        self.montgomery_mul(a, &result)
+    }
+
+    /// a^e mod p in constant time, for a secret e below 2^bits
+    /// a has to fit the limbs, e.g. be reduced
+    pub fn pow(&self, a: &[u64; LIMBS], e: &[u64], bits: usize) -> [u64; LIMBS] {
+        // left to right square-multiply, but every bit costs a squaring and
+        // a multiplication whatever its value, the bit only selects which
+        // of the two is kept, through a mask
+        let mut one = [0u64; LIMBS];
+        one[0] = 1;
+        let base = self.montgomery_mul(a, &self.r_squared);
+        let mut result = self.montgomery_mul(&one, &self.r_squared);
+        for i in (0..bits).rev() {
+            result = self.montgomery_mul(&result, &result);
+            let product = self.montgomery_mul(&result, &base);
+            let bit = e.get(i / 64).map_or(0, |limb| (limb >> (i % 64)) & 1);
+            result = select_limbs(&result, &product, Choice::from_bit(bit));
+        }
+
+        // multiplying by 1 drops the extra R
+        self.montgomery_mul(&result, &one)
+    }
+
+    /// Montgomery multiplication: a * b / R mod p
+    /// constant time, a * b has to be less than p * R
+    fn montgomery_mul(&self, a: &[u64; LIMBS], b: &[u64; LIMBS]) -> [u64; LIMBS] {
+        // see: https://www.microsoft.com/en-us/research/wp-content/uploads/1996/01/j37acmon.pdf (CIOS)
+        // interleaves the schoolbook product with the reduction, every step
+        // adds a multiple of p that clears the lowest limb so it can be shifted out
+        // t holds LIMBS + 2 limbs, the top two as t_top and t_carry
+        let p = &self.p;
+        let mut t = [0u64; LIMBS];
+        let mut t_top = 0u64;
+        for b_limb in b.iter() {
+            let mut carry = 0u128;
+            for j in 0..LIMBS {
+                let wide = t[j] as u128 + a[j] as u128 * *b_limb as u128 + carry;
+                t[j] = wide as u64;
+                carry = wide >> 64;
+            }
+            let wide = t_top as u128 + carry;
+            t_top = wide as u64;
+            let t_carry = (wide >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.p_inv);
+            let mut carry = (t[0] as u128 + m as u128 * p[0] as u128) >> 64;
+            for j in 1..LIMBS {
+                let wide = t[j] as u128 + m as u128 * p[j] as u128 + carry;
+                t[j - 1] = wide as u64;
+                carry = wide >> 64;
+            }
+            let wide = t_top as u128 + carry;
+            t[LIMBS - 1] = wide as u64;
+            t_top = t_carry + (wide >> 64) as u64;
+        }
+
+        // the result is less than 2p, subtract p once if needed
+        let (reduced, borrow) = sub_limbs(&t, p);
+        let use_reduced = Choice::from_bit(t_top) | !Choice::from_bit(borrow);
+        select_limbs(&t, &reduced, use_reduced)
+    }
+}
+
+/// a - b on little endian limbs and the borrow out, for const fns
+const fn const_sub_limbs<const N: usize>(a: &[u64; N], b: &[u64; N]) -> ([u64; N], u64) {
+    let mut result = [0u64; N];
+    let mut borrow = 0u64;
+    let mut i = 0;
+    while i < N {
+        let (diff, b1) = a[i].overflowing_sub(b[i]);
+        let (diff, b2) = diff.overflowing_sub(borrow);
+        result[i] = diff;
+        borrow = (b1 | b2) as u64;
+        i += 1;
+    }
+    (result, borrow)
+}
+
+/// Little endian limbs from big endian hex digits, for constants
+/// panics (at compile time for a const) on anything else or if they don't fit
+pub const fn limbs_from_hex<const N: usize>(hex: &str) -> [u64; N] {
+    let bytes = hex.as_bytes();
+    let mut limbs = [0u64; N];
+    let mut i = 0;
+    while i < bytes.len() {
+        // the i-th digit from the end goes into limb i / 16
+        let digit = match bytes[bytes.len() - 1 - i] {
+            c @ b'0'..=b'9' => c - b'0',
+            c @ b'a'..=b'f' => c - b'a' + 10,
+            c @ b'A'..=b'F' => c - b'A' + 10,
+            _ => panic!("not a hex digit"),
+        } as u64;
+        if i / 16 < N {
+            limbs[i / 16] |= digit << (4 * (i % 16));
+        } else {
+            assert!(digit == 0, "value does not fit");
+        }
+        i += 1;
+    }
+    limbs
+}
+
+/// The limbs up to the most significant non-zero one
+fn significant_limbs(limbs: &[u64]) -> &[u64] {
+    let len = limbs
+        .iter()
+        .rposition(|limb| *limb != 0)
+        .map_or(0, |i| i + 1);
+    &limbs[..len]
+}
+
+/// Schoolbook multiplication of two little endian limb slices
+fn mul_limbs(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut product = vec![0u64; a.len() + b.len()];
+    for (i, a_limb) in a.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, b_limb) in b.iter().enumerate() {
+            let wide = *a_limb as u128 * *b_limb as u128 + product[i + j] as u128 + carry;
+            product[i + j] = wide as u64;
+            carry = wide >> 64;
+        }
+        product[i + b.len()] = carry as u64;
+    }
+    product
+}
+
+/// Remainder of u / v on little endian limb slices, variable time
+/// the result has as many limbs as v
+fn rem_limbs(u: &[u64], v: &[u64]) -> Vec<u64> {
+    div_rem_limbs(u, v).1
+}
+
+/// Quotient and remainder of u / v on little endian limb slices, variable time
+/// the quotient has as many limbs as u and the remainder as many as v
+fn div_rem_limbs(u: &[u64], v: &[u64]) -> (Vec<u64>, Vec<u64>) {
+    // Knuth's long division, see: The Art of Computer Programming vol 2, 4.3.1 algorithm D
+    // it guesses every quotient limb from the top two limbs of the remainder
+    // and the top limb of the divisor, the guess is at most 2 too big and
+    // gets corrected before moving on
+    let n = v
+        .iter()
+        .rposition(|limb| *limb != 0)
+        .expect("division by zero")
+        + 1;
+    let mut quotient = vec![0u64; u.len()];
+    let mut remainder = vec![0u64; v.len()];
+
+    // the divisor is a single limb, no guessing needed
+    if n == 1 {
+        let mut rem = 0u128;
+        for (i, limb) in u.iter().enumerate().rev() {
+            let wide = (rem << 64) | *limb as u128;
+            quotient[i] = (wide / v[0] as u128) as u64;
+            rem = wide % v[0] as u128;
+        }
+        remainder[0] = rem as u64;
+        return (quotient, remainder);
+    }
+
+    // the dividend is already smaller than the divisor
+    let m = u.len();
+    if m < n {
+        remainder[..m].copy_from_slice(u);
+        return (quotient, remainder);
+    }
+
+    // normalize so the top bit of the divisor is set, which keeps the guesses close
+    let shift = v[n - 1].leading_zeros();
+    let shift_limbs = |limbs: &[u64], out_len: usize| -> Vec<u64> {
+        let mut out = vec![0u64; out_len];
+        for i in 0..out_len {
+            let low = limbs.get(i).copied().unwrap_or(0);
+            let below = if i == 0 { 0 } else { limbs[i - 1] };
+            out[i] = if shift == 0 {
+                low
+            } else {
+                (low << shift) | (below >> (64 - shift))
+            };
+        }
+        out
+    };
+    let vn = shift_limbs(&v[..n], n);
+    let mut un = shift_limbs(u, m + 1);
+
+    for j in (0..=m - n).rev() {
+        // estimate the quotient limb
+        let top = ((un[j + n] as u128) << 64) | un[j + n - 1] as u128;
+        let mut q_hat = top / vn[n - 1] as u128;
+        let mut r_hat = top % vn[n - 1] as u128;
+        while q_hat >> 64 != 0
+            || q_hat * vn[n - 2] as u128 > ((r_hat << 64) | un[j + n - 2] as u128)
+        {
+            q_hat -= 1;
+            r_hat += vn[n - 1] as u128;
+            if r_hat >> 64 != 0 {
+                break;
+            }
+        }
+
+        // subtract q_hat * divisor from the current window
+        let mut borrow = 0u64;
+        let mut carry = 0u128;
+        for i in 0..n {
+            let product = q_hat * vn[i] as u128 + carry;
+            carry = product >> 64;
+            (un[i + j], borrow) = sbb(un[i + j], product as u64, borrow);
+        }
+        let (top_limb, top_borrow) = sbb(un[j + n], carry as u64, borrow);
+        un[j + n] = top_limb;
+
+        // the guess was one too big, add the divisor back
+        quotient[j] = q_hat as u64;
+        if top_borrow == 1 {
+            quotient[j] -= 1;
+            let mut carry = 0u128;
+            for i in 0..n {
+                let sum = un[i + j] as u128 + vn[i] as u128 + carry;
+                un[i + j] = sum as u64;
+                carry = sum >> 64;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u64);
+        }
+    }
+
+    // undo the normalization
+    for i in 0..n {
+        remainder[i] = if shift == 0 {
+            un[i]
+        } else {
+            (un[i] >> shift) | (un[i + 1] << (64 - shift))
+        };
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ru256::RU256;
+    use crate::ruint::{limbs_from_hex, ModInt, Montgomery, RUint};
+    use rand::Rng;
+    use std::str::FromStr;
+
+    #[test]
+    fn ruint_matches_ru256() {
+        // RUint<4> and RU256 are the same size, so they have to agree
+        let mut rng = rand::thread_rng();
+        let moduli = [
+            "0xf3fa3",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            "FFFFFFFF00000001000000000000000000000000FFFFFFFFFFFFFFFFFFFFFFFF",
+        ];
+
+        for p in moduli {
+            let p256 = RU256::from_str(p).unwrap();
+            let p4 = RUint::<4>::from_str(p).unwrap();
+            assert_eq!(p4.to_string(), p256.to_string());
+
+            for _ in 0..8 {
+                let mut a = RU256::zero();
+                let mut b = RU256::zero();
+                rng.fill(&mut a.v.0[..]);
+                rng.fill(&mut b.v.0[..]);
+                let a4 = RUint::<4> { v: a.v.0 };
+                let b4 = RUint::<4> { v: b.v.0 };
+
+                let check = |x: RUint<4>, y: RU256| assert_eq!(x.to_string(), y.to_string());
+                check(a4.reduce(&p4), RU256::reduce(&a, &p256));
+                check(a4.add_mod(&b4, &p4), a.add_mod(&b, &p256));
+                check(a4.sub_mod(&b4, &p4), a.sub_mod(&b, &p256));
+                check(a4.mul_mod(&b4, &p4), a.mul_mod(&b, &p256));
+                check(a4.ct_reduce(&p4), a.ct_reduce(&p256));
+
+                let (ar4, br4) = (a4.reduce(&p4), b4.reduce(&p4));
+                let (ar, br) = (a.ct_reduce(&p256), b.ct_reduce(&p256));
+                check(ar4.ct_add_mod(&br4, &p4), ar.ct_add_mod(&br, &p256));
+                check(ar4.ct_sub_mod(&br4, &p4), ar.ct_sub_mod(&br, &p256));
+                check(ar4.ct_mul_mod(&br4, &p4), ar.ct_mul_mod(&br, &p256));
+                if !br.is_zero() {
+                    check(ar4.div_mod(&br4, &p4), ar.div_mod(&br, &p256));
+                    check(ar4.ct_div_mod(&br4, &p4), ar.ct_div_mod(&br, &p256));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ruint_wide_arithmetic() {
+        // P-521 prime, 2^521 - 1
+        let p = RUint::<9>::from_str(
+            "01FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+        )
+        .unwrap();
+        assert_eq!(p.bits(), 521);
+
+        // -1 * -1 = 1
+        let minus_one = p.checked_sub(&RUint::one());
+        assert_eq!(minus_one.mul_mod(&minus_one, &p), RUint::one());
+        assert_eq!(minus_one.ct_mul_mod(&minus_one, &p), RUint::one());
+        assert_eq!(minus_one.add_mod(&RUint::one(), &p), RUint::zero());
+        assert_eq!(RUint::zero().sub_mod(&RUint::one(), &p), minus_one);
+
+        // 2^521 = 1 mod p
+        let two = RUint::<9>::from_u64(2);
+        assert_eq!(two.exp_mod(&RUint::from_u64(521), &p), RUint::one());
+
+        let a = RUint::<9>::from_str_radix("123456789012345678901234567890", 10).unwrap();
+        let a_inv = RUint::one().div_mod(&a, &p);
+        assert_eq!(a.mul_mod(&a_inv, &p), RUint::one());
+        assert_eq!(RUint::one().ct_div_mod(&a, &p), a_inv);
+
+        // bytes round trip, and shifting
+        let mut bytes = [0u8; 66];
+        minus_one.to_bytes(&mut bytes);
+        assert_eq!(bytes[0], 0x01);
+        assert_eq!(bytes[65], 0xfe);
+        assert_eq!(RUint::<9>::from_bytes(&bytes), minus_one);
+        assert_eq!(p.shr(500).to_string().trim_start_matches('0'), "1fffff");
+
+        // u = q * v + r with r < v, for a multi limb and a single limb divisor
+        let mut rng = rand::thread_rng();
+        for divisor in [p.shr(200), RUint::from_u64(0xfffffffb)] {
+            let u = RUint::<9> {
+                v: rng.gen::<[u64; 9]>(),
+            };
+            let (quotient, remainder) = u.div_rem(&divisor);
+            assert!(remainder < divisor);
+            assert_eq!(remainder, u.reduce(&divisor));
+            assert_eq!(quotient.checked_mul(&divisor).checked_add(&remainder), u);
+        }
+
+        // overflow and bad digits fail to parse
+        assert!(RUint::<1>::from_str("10000000000000000").is_err());
+        assert!(RUint::<1>::from_str("xyz").is_err());
+        assert!(RUint::<1>::from_str("").is_err());
+    }
+
+    #[test]
+    fn montgomery_constants_match_mul_mod() {
+        // the curve constants are built at compile time, check them against
+        // the variable time operations for the field and group moduli
+        const P384_N: Montgomery<6> = Montgomery::from_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFC7634D81F4372DDF581A0DB248B0A77AECEC196ACCC52973");
+        let n = RUint::<6>::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFC7634D81F4372DDF581A0DB248B0A77AECEC196ACCC52973").unwrap();
+        assert_eq!(RUint::modulus(&P384_N), n);
+        assert_eq!(Montgomery::new(n.v), P384_N);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let a = RUint::<6> { v: rng.gen() }.reduce(&n);
+            let b = RUint::<6> { v: rng.gen() }.reduce(&n);
+            assert_eq!(a.ct_mul_mod_with(&b, &P384_N), a.mul_mod(&b, &n));
+            assert_eq!(a.ct_div_mod_with(&b, &P384_N), a.div_mod(&b, &n));
+        }
+
+        // RU256 shares the 4 limb constants
+        let p = RU256::from_str("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F")
+            .unwrap();
+        let constants = Montgomery::new(p.v.0);
+        assert_eq!(RU256::modulus(&constants), p);
+        for _ in 0..20 {
+            let a = RU256::from_bytes(&rng.gen::<[u8; 32]>()).reduce(&p);
+            let b = RU256::from_bytes(&rng.gen::<[u8; 32]>()).reduce(&p);
+            assert_eq!(a.ct_mul_mod_with(&b, &constants), a.mul_mod(&b, &p));
+            assert_eq!(a.ct_div_mod_with(&b, &constants), a.div_mod(&b, &p));
+        }
+
+        // the constant time exponentiation, with an exponent shorter than its bound
+        for _ in 0..5 {
+            let a = RUint::<6> { v: rng.gen() }.reduce(&n);
+            let e = RUint::<6> { v: rng.gen() }.shr(100);
+            assert_eq!(
+                RUint {
+                    v: P384_N.pow(&a.v, &e.v, 384)
+                },
+                a.exp_mod(&e, &n)
+            );
+        }
+
+        assert_eq!(
+            limbs_from_hex::<2>("0123456789abcdef00000000000000ff"),
+            [0xff, 0x0123456789abcdef]
+        );
+    }
+}
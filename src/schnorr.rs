@@ -0,0 +1,420 @@
+use crate::curve::{Curve, JacobianPoint, Point};
+use crate::field::{FieldElement, Scalar};
+use crate::hash::tagged_hash;
+use crate::msm::multi_scalar_multiplication;
+use crate::ru256::RU256;
+use crate::secp256k1::SECP256K1;
+use crate::secret::{Nonce, PrivateKey};
+use rand::Rng;
+
+// BIP-340 Schnorr signatures on secp256k1
+// see: https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+// public keys and the signature's R are encoded by their x coordinate alone
+// the y coordinate is implicitly the even one of the two possible,
+// and signing negates the private key or nonce whenever that isn't the case
+
+/// BIP-340 public key, the x coordinate of a point with even y
+#[derive(Clone, Debug, PartialEq)]
+pub struct XOnlyPublicKey {
+    point: Point<SECP256K1>,
+}
+
+impl XOnlyPublicKey {
+    /// Public key of the given private key
+    pub fn from_private_key(private_key: &PrivateKey) -> Self {
+        let point = SECP256K1::public_key(private_key.expose_secret());
+        Self::from_point(&point)
+    }
+
+    /// Public key from a point, dropping the parity of y
+    pub(crate) fn from_point(point: &Point<SECP256K1>) -> Self {
+        let point = if point.y.is_even() {
+            point.clone()
+        } else {
+            Point {
+                x: point.x.clone(),
+                y: -&point.y,
+            }
+        };
+        XOnlyPublicKey { point }
+    }
+
+    /// Public key from its 32 byte encoding
+    /// returns None if it isn't the x coordinate of a point on the curve
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let point = lift_x(bytes)?;
+        Some(XOnlyPublicKey { point })
+    }
+
+    /// 32 byte encoding of the public key
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.point.x.to_bytes().try_into().unwrap()
+    }
+
+    /// The point with even y the key stands for
+    pub(crate) fn point(&self) -> &Point<SECP256K1> {
+        &self.point
+    }
+}
+
+/// BIP-340 signature, the x coordinate of the nonce point R and s
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    pub(crate) r: FieldElement<SECP256K1>,
+    pub(crate) s: Scalar<SECP256K1>,
+}
+
+impl Signature {
+    /// Signature from its 64 byte encoding
+    /// r has to be less than p and s less than n, anything else is rejected
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 64 {
+            return None;
+        }
+
+        let r = RU256::from_bytes(&bytes[..32]);
+        let s = RU256::from_bytes(&bytes[32..]);
+        if r >= SECP256K1::p() || s >= SECP256K1::n() {
+            return None;
+        }
+
+        Some(Signature {
+            r: FieldElement::reduce(&r),
+            s: Scalar::reduce(&s),
+        })
+    }
+
+    /// 64 byte encoding of the signature, r followed by s
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r.to_bytes());
+        bytes[32..].copy_from_slice(&self.s.to_bytes());
+        bytes
+    }
+}
+
+/// Point with the given x coordinate and even y
+/// returns None if x is not less than p or not on the curve
+pub(crate) fn lift_x(bytes: &[u8]) -> Option<Point<SECP256K1>> {
+    if bytes.len() != 32 {
+        return None;
+    }
+
+    let x = RU256::from_bytes(bytes);
+    if x >= SECP256K1::p() {
+        return None;
+    }
+
+    Point::from_x(&FieldElement::reduce(&x), false)
+}
+
+/// The challenge e = hash(R || P || m) mod n
+pub(crate) fn challenge(r: &[u8], public_key: &[u8], message: &[u8]) -> Scalar<SECP256K1> {
+    let hash = tagged_hash("BIP0340/challenge", &[r, public_key, message]);
+    Scalar::reduce(&RU256::from_bytes(&hash))
+}
+
+/// BIP-340 signing of a message, with fresh auxiliary randomness
+/// returns None if the private key is zero
+pub fn sign(message: &[u8], private_key: &PrivateKey) -> Option<Signature> {
+    // a zero key would fail every time below
+    if private_key.expose_secret().is_zero() {
+        return None;
+    }
+
+    loop {
+        let aux_rand: [u8; 32] = rand::thread_rng().gen();
+
+        // the derived nonce is zero with negligible probability
+        // just try again with other randomness
+        if let Some(signature) = sign_with_aux_rand(message, private_key, &aux_rand) {
+            return Some(signature);
+        }
+    }
+}
+
+/// BIP-340 signing of a message with the given auxiliary randomness
+/// returns None if the private key or the derived nonce is zero
+pub fn sign_with_aux_rand(
+    message: &[u8],
+    private_key: &PrivateKey,
+    aux_rand: &[u8; 32],
+) -> Option<Signature> {
+    if private_key.expose_secret().is_zero() {
+        return None;
+    }
+
+    // the key signs for the point with even y, so negate it if
+    // its public key has odd y
+    let public_point = SECP256K1::public_key(private_key.expose_secret());
+    let d = private_key.conditional_negate(!public_point.y.is_even());
+    let x_only_key = XOnlyPublicKey::from_point(&public_point);
+    let public_key = x_only_key.to_bytes();
+
+    // the nonce is derived from the key, the message and the auxiliary
+    // randomness, so it is never reused for different messages even with a
+    // broken rng, and the randomness still protects against side channels
+    let aux_hash = tagged_hash("BIP0340/aux", &[aux_rand]);
+    let mut masked_key = [0u8; 32];
+    for (masked, (key, aux)) in masked_key
+        .iter_mut()
+        .zip(d.expose_secret().to_bytes().iter().zip(aux_hash.iter()))
+    {
+        *masked = key ^ aux;
+    }
+    let nonce_hash = tagged_hash("BIP0340/nonce", &[&masked_key, &public_key, message]);
+    let nonce = Nonce::new(&RU256::from_bytes(&nonce_hash));
+    if nonce.expose_secret().is_zero() {
+        return None;
+    }
+
+    // same as the key, the nonce has to give an R with even y
+    let nonce_point = SECP256K1::public_key(nonce.expose_secret());
+    let k = nonce.conditional_negate(!nonce_point.y.is_even());
+    let r = nonce_point.x.to_bytes();
+
+    // s = k + e * d
+    let e = challenge(&r, &public_key, message);
+    let s = k.expose_secret() + &e * d.expose_secret();
+
+    let signature = Signature {
+        r: nonce_point.x,
+        s,
+    };
+
+    // a faulty computation can leak the key, check the signature before
+    // handing it out
+    if !verify(message, &x_only_key, &signature) {
+        return None;
+    }
+
+    Some(signature)
+}
+
+/// BIP-340 verification of a signed message
+pub fn verify(message: &[u8], public_key: &XOnlyPublicKey, signature: &Signature) -> bool {
+    // s * G = R + e * P for a valid signature, so R = s * G - e * P
+    let e = challenge(&signature.r.to_bytes(), &public_key.to_bytes(), message);
+    let s_point = SECP256K1::scalar_multiplication(&signature.s, &SECP256K1::g());
+    let e_point = SECP256K1::scalar_multiplication(&-e, public_key.point());
+    let r_point = SECP256K1::to_affine(&SECP256K1::add_jacobian_points(
+        &JacobianPoint::from(&s_point),
+        &JacobianPoint::from(&e_point),
+    ));
+
+    // R can't be the identity, has to have even y and the x from the signature
+    !r_point.is_zero_point() && r_point.y.is_even() && r_point.x == signature.r
+}
+
+/// BIP-340 batch verification of many signed messages at once
+/// returns the indices of the invalid signatures if there are any
+pub fn verify_batch(batch: &[(&[u8], &XOnlyPublicKey, &Signature)]) -> Result<(), Vec<usize>> {
+    if batch_is_valid(batch) {
+        return Ok(());
+    }
+
+    // split the batch in halves until the failing signatures are isolated
+    // with only a few bad signatures most halves pass as a whole
+    let mut invalid = vec![];
+    find_invalid(batch, 0, &mut invalid);
+    Err(invalid)
+}
+
+/// Check a whole batch at once
+fn batch_is_valid(batch: &[(&[u8], &XOnlyPublicKey, &Signature)]) -> bool {
+    // see: https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki#batch-verification
+    // every valid signature has s_i * G = R_i + e_i * P_i, so with weights a_i
+    // (a_1 * s_1 + ... + a_u * s_u) * G - a_1 * R_1 - a_1 * e_1 * P_1 - ... = O
+    // the weights are random so invalid signatures can't be made to cancel
+    // each other out, the first one can be 1 as only the ratios matter
+    let mut scalars = vec![];
+    let mut points = vec![];
+    let mut s_sum = Scalar::zero();
+
+    for (i, (message, public_key, signature)) in batch.iter().enumerate() {
+        // R is only known by its x coordinate, take the even y like for keys
+        let r_point = match Point::from_x(&signature.r, false) {
+            Some(point) => point,
+            None => return false,
+        };
+        let e = challenge(&signature.r.to_bytes(), &public_key.to_bytes(), message);
+
+        let mut weight = Scalar::one();
+        if i > 0 {
+            weight.randomize();
+        }
+
+        s_sum = &s_sum + &weight * &signature.s;
+        scalars.push(-(&weight * &e));
+        points.push(public_key.point().clone());
+        scalars.push(-weight);
+        points.push(r_point);
+    }
+
+    scalars.push(s_sum);
+    points.push(SECP256K1::g());
+
+    multi_scalar_multiplication(&scalars, &points).is_zero_point()
+}
+
+/// Collect the indices of the invalid signatures in a batch known to fail
+fn find_invalid(
+    batch: &[(&[u8], &XOnlyPublicKey, &Signature)],
+    offset: usize,
+    invalid: &mut Vec<usize>,
+) {
+    if let [(message, public_key, signature)] = batch {
+        if !verify(message, public_key, signature) {
+            invalid.push(offset);
+        }
+        return;
+    }
+
+    let (left, right) = batch.split_at(batch.len() / 2);
+    for (half, half_offset) in [(left, offset), (right, offset + left.len())] {
+        if !batch_is_valid(half) {
+            find_invalid(half, half_offset, invalid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ru256::RU256;
+    use crate::schnorr::{
+        sign, sign_with_aux_rand, verify, verify_batch, Signature, XOnlyPublicKey,
+    };
+    use crate::secret::PrivateKey;
+
+    #[test]
+    fn schnorr_signing_and_verification() {
+        let private_key = PrivateKey::from_bytes(&[0x34, 0x24]).unwrap();
+        let public_key = XOnlyPublicKey::from_private_key(&private_key);
+
+        let signature = sign(b"hello-world", &private_key).unwrap();
+        assert!(verify(b"hello-world", &public_key, &signature));
+        assert!(!verify(b"different-message", &public_key, &signature));
+
+        // encodings round trip
+        let signature = Signature::from_bytes(&signature.to_bytes()).unwrap();
+        let public_key = XOnlyPublicKey::from_bytes(&public_key.to_bytes()).unwrap();
+        assert!(verify(b"hello-world", &public_key, &signature));
+
+        // should not verify if we use a different public key
+        let other_key =
+            XOnlyPublicKey::from_private_key(&PrivateKey::from_bytes(&[0x34, 0x25]).unwrap());
+        assert!(!verify(b"hello-world", &other_key, &signature));
+
+        // a zero key can't sign
+        let zero_key = PrivateKey::new(&RU256::zero());
+        assert!(sign(b"hello-world", &zero_key).is_none());
+        assert!(sign_with_aux_rand(b"hello-world", &zero_key, &[0; 32]).is_none());
+    }
+
+    #[test]
+    fn schnorr_bip340_test_vectors() {
+        // see: https://github.com/bitcoin/bips/blob/master/bip-0340/test-vectors.csv
+        let vectors = include_str!("../test_vectors/bip340_vectors.csv");
+        let mut count = 0;
+
+        for line in vectors.lines().skip(1) {
+            let fields: Vec<&str> = line.splitn(8, ',').collect();
+            let (index, secret_key, public_key, aux_rand) =
+                (fields[0], fields[1], fields[2], fields[3]);
+            let message = hex::decode(fields[4]).unwrap();
+            let signature = hex::decode(fields[5]).unwrap();
+            let valid = fields[6] == "TRUE";
+
+            // signing vectors come with the key and the randomness used
+            if !secret_key.is_empty() {
+                let private_key =
+                    PrivateKey::from_bytes(&hex::decode(secret_key).unwrap()).unwrap();
+                let aux_rand: [u8; 32] = hex::decode(aux_rand).unwrap().try_into().unwrap();
+                assert_eq!(
+                    hex::encode_upper(XOnlyPublicKey::from_private_key(&private_key).to_bytes()),
+                    public_key,
+                    "vector {}",
+                    index
+                );
+                let produced = sign_with_aux_rand(&message, &private_key, &aux_rand).unwrap();
+                assert_eq!(produced.to_bytes().to_vec(), signature, "vector {}", index);
+            }
+
+            // keys and signatures that can't be decoded don't verify
+            let verified = match (
+                XOnlyPublicKey::from_bytes(&hex::decode(public_key).unwrap()),
+                Signature::from_bytes(&signature),
+            ) {
+                (Some(public_key), Some(signature)) => verify(&message, &public_key, &signature),
+                _ => false,
+            };
+            assert_eq!(verified, valid, "vector {}", index);
+            count += 1;
+        }
+
+        assert_eq!(count, 19);
+    }
+
+    #[test]
+    fn schnorr_batch_verification() {
+        let keys: Vec<PrivateKey> = (1..=24u8)
+            .map(|i| PrivateKey::from_bytes(&[i, 0x42]).unwrap())
+            .collect();
+        let public_keys: Vec<XOnlyPublicKey> =
+            keys.iter().map(XOnlyPublicKey::from_private_key).collect();
+        let messages: Vec<Vec<u8>> = (0..24u8).map(|i| vec![i; i as usize]).collect();
+        let mut signatures: Vec<Signature> = keys
+            .iter()
+            .zip(messages.iter())
+            .map(|(key, message)| sign(message, key).unwrap())
+            .collect();
+
+        let batch = |signatures: &[Signature]| {
+            let items: Vec<(&[u8], &XOnlyPublicKey, &Signature)> = messages
+                .iter()
+                .zip(public_keys.iter())
+                .zip(signatures.iter())
+                .map(|((message, public_key), signature)| {
+                    (message.as_slice(), public_key, signature)
+                })
+                .collect();
+            verify_batch(&items)
+        };
+
+        assert_eq!(verify_batch(&[]), Ok(()));
+        assert_eq!(batch(&signatures), Ok(()));
+
+        // a signature for another message, and a signature whose R
+        // isn't on the curve at all
+        signatures[3] = signatures[4].clone();
+        let mut bytes = signatures[17].to_bytes();
+        bytes[..32].copy_from_slice(
+            &hex::decode("4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D")
+                .unwrap(),
+        );
+        signatures[17] = Signature::from_bytes(&bytes).unwrap();
+        assert_eq!(batch(&signatures), Err(vec![3, 17]));
+
+        // the BIP-340 vectors give the same answers batched as one by one
+        let vectors = include_str!("../test_vectors/bip340_vectors.csv");
+        let mut items = vec![];
+        let mut expected_invalid = vec![];
+        for line in vectors.lines().skip(1) {
+            let fields: Vec<&str> = line.splitn(8, ',').collect();
+            if let (Some(public_key), Some(signature)) = (
+                XOnlyPublicKey::from_bytes(&hex::decode(fields[2]).unwrap()),
+                Signature::from_bytes(&hex::decode(fields[5]).unwrap()),
+            ) {
+                if fields[6] != "TRUE" {
+                    expected_invalid.push(items.len());
+                }
+                items.push((hex::decode(fields[4]).unwrap(), public_key, signature));
+            }
+        }
+        let items: Vec<(&[u8], &XOnlyPublicKey, &Signature)> = items
+            .iter()
+            .map(|(message, public_key, signature)| (message.as_slice(), public_key, signature))
+            .collect();
+        assert_eq!(verify_batch(&items), Err(expected_invalid));
+    }
+}
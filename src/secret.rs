@@ -0,0 +1,391 @@
+use crate::curve::Curve;
+use crate::field::Scalar;
+use crate::ruint::ModInt;
+use crate::secp256k1::SECP256K1;
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ptr;
+use std::str::FromStr;
+use std::sync::atomic::{compiler_fence, Ordering};
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+use std::sync::Mutex;
+
+/// Heap allocated secret scalar
+/// the value lives behind a box so it has a stable address we can lock
+/// and wipe, moving the wrapper around never copies the secret itself
+struct Secret<C: Curve> {
+    value: Box<Scalar<C>>,
+}
+
+impl<C: Curve> Secret<C> {
+    /// Secret from a number, reduced mod n
+    fn new(value: &C::Uint) -> Self {
+        let mut secret = Self::zero();
+        *secret.value = Scalar::reduce(value);
+        secret
+    }
+
+    /// Secret initialized to zero, ready to be filled in place
+    fn zero() -> Self {
+        let secret = Secret {
+            value: Box::new(Scalar::zero()),
+        };
+        secret.lock();
+        secret
+    }
+
+    /// Random non-zero secret, generated straight into the locked memory
+    fn random() -> Self {
+        let mut secret = Self::zero();
+        secret.value.randomize();
+        secret
+    }
+
+    /// Copy of the secret, negated mod n if negate is set
+    /// the copy is locked and wiped like the original, so there's no need
+    /// to clone the scalar
+    fn conditional_negate(&self, negate: bool) -> Self {
+        let mut secret = Self::zero();
+        *secret.value = if negate {
+            -self.value.as_ref()
+        } else {
+            Scalar::reduce(self.value.value())
+        };
+        secret
+    }
+
+    /// Keep the secret out of swap
+    #[cfg(all(feature = "mlock", target_os = "linux"))]
+    fn lock(&self) {
+        let mut locked_pages = LOCKED_PAGES.lock().unwrap_or_else(|e| e.into_inner());
+        for page in self.pages() {
+            let count = locked_pages.entry(page).or_insert(0);
+            if *count == 0 {
+                // best effort, mlock can fail if we are over RLIMIT_MEMLOCK
+                // in which case the secret is still wiped on drop
+                unsafe {
+                    libc::mlock(page as *const libc::c_void, page_size());
+                }
+            }
+            *count += 1;
+        }
+    }
+
+    #[cfg(not(all(feature = "mlock", target_os = "linux")))]
+    fn lock(&self) {}
+
+    #[cfg(all(feature = "mlock", target_os = "linux"))]
+    fn unlock(&self) {
+        let mut locked_pages = LOCKED_PAGES.lock().unwrap_or_else(|e| e.into_inner());
+        for page in self.pages() {
+            let Some(count) = locked_pages.get_mut(&page) else {
+                continue;
+            };
+            *count -= 1;
+            // other secrets can live on the same page, it stays locked
+            // until the last one of them is gone
+            if *count == 0 {
+                locked_pages.remove(&page);
+                unsafe {
+                    libc::munlock(page as *const libc::c_void, page_size());
+                }
+            }
+        }
+    }
+
+    #[cfg(not(all(feature = "mlock", target_os = "linux")))]
+    fn unlock(&self) {}
+
+    /// Start addresses of the pages the secret lies on
+    #[cfg(all(feature = "mlock", target_os = "linux"))]
+    fn pages(&self) -> impl Iterator<Item = usize> {
+        let page_size = page_size();
+        let start = self.value.as_ref() as *const Scalar<C> as usize;
+        let end = start + std::mem::size_of::<Scalar<C>>() - 1;
+        (start & !(page_size - 1)..=end & !(page_size - 1)).step_by(page_size)
+    }
+}
+
+/// Number of live secrets on each locked page, by page address
+/// mlock and munlock work on whole pages and the allocator packs small
+/// boxes together, unlocking a page for one secret would unlock its neighbours
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+static LOCKED_PAGES: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+impl<C: Curve> Drop for Secret<C> {
+    fn drop(&mut self) {
+        self.value.wipe();
+        self.unlock();
+    }
+}
+
+/// Overwrite a number with zeros in a way the compiler can't optimize out
+pub(crate) fn wipe<T: ModInt>(value: &mut T) {
+    // a plain assignment to memory that is about to be freed is a dead store
+    // the compiler is free to remove, volatile writes are never elided
+    // and the fence stops them from being reordered past the free
+    for limb in value.limbs_mut().iter_mut() {
+        unsafe { ptr::write_volatile(limb, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Overwrite bytes with zeros, like wipe, e.g. for derived symmetric keys
+pub(crate) fn wipe_bytes(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Errors when building a private key
+#[derive(Debug, PartialEq, Eq)]
+pub enum PrivateKeyError {
+    /// The string is not a hex number
+    InvalidHex,
+
+    /// The key is zero, or not less than the group order n
+    OutOfRange,
+}
+
+/// ECDSA private key
+/// wiped from memory when dropped and never printed
+pub struct PrivateKey<C: Curve = SECP256K1> {
+    secret: Secret<C>,
+}
+
+impl<C: Curve> PrivateKey<C> {
+    /// Private key from a number, reduced mod n
+    pub(crate) fn new(value: &C::Uint) -> Self {
+        Self {
+            secret: Secret::new(value),
+        }
+    }
+
+    /// Private key from a number, which has to be in 1..n-1
+    pub(crate) fn from_uint(value: &C::Uint) -> Result<Self, PrivateKeyError> {
+        // zero has no public key and anything from n up would be
+        // reduced to some other key, possibly zero
+        if value.is_zero() || *value >= C::n() {
+            return Err(PrivateKeyError::OutOfRange);
+        }
+        Ok(Self::new(value))
+    }
+
+    /// Private key from big endian bytes, which have to be a number in 1..n-1
+    pub fn from_bytes(byte_slice: &[u8]) -> Result<Self, PrivateKeyError> {
+        // at most as many bytes as n takes
+        if byte_slice.len() > C::n().bits().div_ceil(8) {
+            return Err(PrivateKeyError::OutOfRange);
+        }
+        let mut value = C::Uint::from_bytes(byte_slice);
+        let private_key = Self::from_uint(&value);
+        wipe(&mut value);
+        private_key
+    }
+
+    /// Copy of the key, negated if negate is set
+    /// e.g. for signing with the even y version of the public key
+    pub(crate) fn conditional_negate(&self, negate: bool) -> Self {
+        Self {
+            secret: self.secret.conditional_negate(negate),
+        }
+    }
+
+    /// Access the underlying scalar, e.g. expose_secret().to_hex() to print it
+    /// keep the borrow short and avoid cloning it, clones are not wiped
+    pub fn expose_secret(&self) -> &Scalar<C> {
+        &self.secret.value
+    }
+}
+
+impl<C: Curve> FromStr for PrivateKey<C> {
+    type Err = PrivateKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // conversion from a hex string
+        let mut value = C::Uint::from_str(s).map_err(|_| PrivateKeyError::InvalidHex)?;
+        let private_key = Self::from_uint(&value);
+        wipe(&mut value);
+        private_key
+    }
+}
+
+impl<C: Curve> fmt::Debug for PrivateKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrivateKey([REDACTED])")
+    }
+}
+
+/// ECDSA signing nonce
+/// wiped from memory when dropped and never printed
+pub(crate) struct Nonce<C: Curve = SECP256K1> {
+    secret: Secret<C>,
+}
+
+impl<C: Curve> Nonce<C> {
+    /// Fresh random nonce
+    pub(crate) fn random() -> Self {
+        Self {
+            secret: Secret::random(),
+        }
+    }
+
+    /// Nonce from a known number, reduced mod n
+    /// for test vectors and RFC 6979 derived nonces, a reused or
+    /// predictable nonce gives the private key away
+    pub(crate) fn new(value: &C::Uint) -> Self {
+        Self {
+            secret: Secret::new(value),
+        }
+    }
+
+    /// Copy of the nonce, negated if negate is set
+    /// e.g. for a nonce point that has to have even y
+    pub(crate) fn conditional_negate(&self, negate: bool) -> Self {
+        Self {
+            secret: self.secret.conditional_negate(negate),
+        }
+    }
+
+    /// Access the underlying scalar
+    pub(crate) fn expose_secret(&self) -> &Scalar<C> {
+        &self.secret.value
+    }
+}
+
+impl<C: Curve> fmt::Debug for Nonce<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Nonce([REDACTED])")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::Curve;
+    use crate::p256::P256;
+    use crate::ru256::RU256;
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::{wipe, Nonce, PrivateKey, PrivateKeyError};
+    use std::str::FromStr;
+
+    #[test]
+    fn secret_debug_output_is_redacted() {
+        let private_key: PrivateKey = PrivateKey::from_str("3424").unwrap();
+        assert_eq!(format!("{:?}", private_key), "PrivateKey([REDACTED])");
+        assert!(!format!("{:#?}", private_key).contains("3424"));
+
+        let nonce: Nonce = Nonce::random();
+        assert_eq!(format!("{:?}", nonce), "Nonce([REDACTED])");
+
+        // the scalar underneath is redacted too
+        assert_eq!(
+            format!("{:?}", private_key.expose_secret()),
+            "Scalar([REDACTED])"
+        );
+    }
+
+    #[test]
+    fn secret_conditional_negate() {
+        let private_key: PrivateKey = PrivateKey::from_str("3424").unwrap();
+        assert!(
+            private_key.conditional_negate(false).expose_secret() == private_key.expose_secret()
+        );
+
+        let negated = private_key.conditional_negate(true);
+        assert!((negated.expose_secret() + private_key.expose_secret()).is_zero());
+
+        let nonce: Nonce = Nonce::new(&RU256::from_str("3424").unwrap());
+        assert!(nonce.conditional_negate(true).expose_secret() == negated.expose_secret());
+    }
+
+    #[test]
+    fn secret_value_is_preserved() {
+        let private_key: PrivateKey = PrivateKey::from_str("3424").unwrap();
+        assert_eq!(
+            private_key.expose_secret().to_hex(),
+            "0000000000000000000000000000000000000000000000000000000000003424"
+        );
+
+        let private_key: PrivateKey = PrivateKey::from_bytes(&[0x34, 0x24]).unwrap();
+        assert_eq!(
+            private_key.expose_secret().to_hex(),
+            "0000000000000000000000000000000000000000000000000000000000003424"
+        );
+    }
+
+    #[cfg(all(feature = "mlock", target_os = "linux"))]
+    #[test]
+    fn secret_pages_stay_locked_while_shared() {
+        use crate::secp256k1::SECP256K1;
+        use crate::secret::{Secret, LOCKED_PAGES};
+
+        // small boxes allocated back to back end up on the same page
+        let first: Secret<SECP256K1> = Secret::random();
+        let second: Secret<SECP256K1> = Secret::random();
+        let second_pages: Vec<usize> = second.pages().collect();
+
+        drop(first);
+        let locked_pages = LOCKED_PAGES.lock().unwrap();
+        for page in &second_pages {
+            assert!(locked_pages.get(page).is_some_and(|count| *count > 0));
+        }
+        drop(locked_pages);
+
+        drop(second);
+    }
+
+    #[test]
+    fn secret_rejects_keys_out_of_range() {
+        let n = SECP256K1::n();
+        let mut n_bytes = [0u8; 32];
+        n.to_bytes(&mut n_bytes);
+
+        for key in [
+            PrivateKey::<SECP256K1>::from_str("0"),
+            PrivateKey::from_str(&n.to_string()),
+        ] {
+            assert_eq!(key.unwrap_err(), PrivateKeyError::OutOfRange);
+        }
+        assert_eq!(
+            PrivateKey::<SECP256K1>::from_str("34g4").unwrap_err(),
+            PrivateKeyError::InvalidHex
+        );
+
+        assert_eq!(
+            PrivateKey::<SECP256K1>::from_bytes(&[0; 32]).unwrap_err(),
+            PrivateKeyError::OutOfRange
+        );
+        assert_eq!(
+            PrivateKey::<SECP256K1>::from_bytes(&n_bytes).unwrap_err(),
+            PrivateKeyError::OutOfRange
+        );
+        assert_eq!(
+            PrivateKey::<SECP256K1>::from_bytes(&[1; 33]).unwrap_err(),
+            PrivateKeyError::OutOfRange
+        );
+
+        // n - 1 is the largest key, and the order is the curve's own
+        n_bytes[31] -= 1;
+        assert!(PrivateKey::<SECP256K1>::from_bytes(&n_bytes).is_ok());
+        assert_eq!(
+            PrivateKey::<P256>::from_bytes(&n_bytes).unwrap_err(),
+            PrivateKeyError::OutOfRange
+        );
+    }
+
+    #[test]
+    fn secret_wipe_clears_every_limb() {
+        let mut value = RU256::from_str("3424").unwrap();
+        value.v.0 = [1, 2, 3, 4];
+
+        wipe(&mut value);
+        assert!(value.is_zero());
+    }
+}
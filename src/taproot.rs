@@ -0,0 +1,399 @@
+use crate::bech32::encode_segwit_address;
+use crate::curve::{Curve, Point};
+use crate::hash::tagged_hash;
+use crate::schnorr::XOnlyPublicKey;
+use crate::secp256k1::SECP256K1;
+use crate::secret::PrivateKey;
+
+// Taproot outputs, see: https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki
+// an output commits to an internal key P and optionally a Merkle tree of scripts,
+// its key is Q = P + t * G with t = hash_TapTweak(P || merkle root), so it can be
+// spent either by a signature for Q (the key path, the private key tweaked by t)
+// or by revealing one script along with a control block proving it is in the tree
+//
+// leaves hash as hash_TapLeaf(version || compact size || script) and branches as
+// hash_TapBranch of their children's hashes in sorted order, so a control block
+// only needs the sibling hashes on the way up, not which side they were on
+
+/// Leaf version of BIP-342 tapscript
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+// at most 128 levels, so a control block has at most 33 + 32 * 128 bytes
+const MAX_DEPTH: usize = 128;
+
+/// Errors building Taproot outputs and control blocks
+#[derive(Debug, PartialEq, Eq)]
+pub enum TaprootError {
+    /// A leaf version with the lowest bit set, or the annex tag 0x50
+    InvalidLeafVersion,
+    /// The tweak is not below n, or the tweaked key is zero or the identity
+    InvalidTweak,
+    /// A control block of the wrong length, or with an invalid internal key
+    InvalidControlBlock,
+    /// The script is not a leaf of the tree, or the output has no tree
+    LeafNotFound,
+}
+
+/// A leaf's version and script, with the sibling hashes from it up to the root
+pub type LeafWithPath<'a> = (u8, &'a [u8], Vec<[u8; 32]>);
+
+/// Script tree, leaves of versioned scripts joined by branches
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TapTree {
+    Leaf { version: u8, script: Vec<u8> },
+    Branch(Box<TapTree>, Box<TapTree>),
+}
+
+impl TapTree {
+    /// Tapscript leaf
+    pub fn leaf(script: &[u8]) -> Self {
+        TapTree::Leaf {
+            version: TAPSCRIPT_LEAF_VERSION,
+            script: script.to_vec(),
+        }
+    }
+
+    /// Leaf with another version, for future script systems
+    pub fn leaf_with_version(version: u8, script: &[u8]) -> Result<Self, TaprootError> {
+        // the lowest bit of the control block's first byte holds the parity of Q,
+        // and 0x50 would make the control block look like an annex
+        if version & 1 != 0 || version == 0x50 {
+            return Err(TaprootError::InvalidLeafVersion);
+        }
+        Ok(TapTree::Leaf {
+            version,
+            script: script.to_vec(),
+        })
+    }
+
+    /// Branch with the two subtrees as children
+    pub fn branch(left: TapTree, right: TapTree) -> Self {
+        TapTree::Branch(Box::new(left), Box::new(right))
+    }
+
+    /// Hash of the tree, the Merkle root for the whole of it
+    pub fn hash(&self) -> [u8; 32] {
+        match self {
+            TapTree::Leaf { version, script } => leaf_hash(*version, script),
+            TapTree::Branch(left, right) => branch_hash(&left.hash(), &right.hash()),
+        }
+    }
+
+    /// Leaves depth first with the sibling hashes from each up to the root
+    pub fn leaves(&self) -> Vec<LeafWithPath<'_>> {
+        match self {
+            TapTree::Leaf { version, script } => vec![(*version, &script[..], vec![])],
+            TapTree::Branch(left, right) => {
+                let (left_hash, right_hash) = (left.hash(), right.hash());
+                let mut leaves = left.leaves();
+                for (_, _, path) in leaves.iter_mut() {
+                    path.push(right_hash);
+                }
+                for (version, script, mut path) in right.leaves() {
+                    path.push(left_hash);
+                    leaves.push((version, script, path));
+                }
+                leaves
+            }
+        }
+    }
+}
+
+/// hash_TapLeaf(version || compact size of the script || script)
+pub fn leaf_hash(version: u8, script: &[u8]) -> [u8; 32] {
+    // bitcoin's compact size, one byte below 0xfd and a marker and little endian length above
+    let length = match script.len() {
+        length @ 0..=0xfc => vec![length as u8],
+        length @ 0xfd..=0xffff => [&[0xfd], &(length as u16).to_le_bytes()[..]].concat(),
+        length => [&[0xfe], &(length as u32).to_le_bytes()[..]].concat(),
+    };
+    tagged_hash("TapLeaf", &[&[version], &length, script])
+}
+
+/// hash_TapBranch of two child hashes, smallest first
+pub fn branch_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    tagged_hash("TapBranch", &[first, second])
+}
+
+/// hash_TapTweak(internal key || merkle root), without a root for key path only outputs
+pub fn tap_tweak(internal_key: &XOnlyPublicKey, merkle_root: Option<&[u8; 32]>) -> [u8; 32] {
+    tagged_hash(
+        "TapTweak",
+        &[
+            &internal_key.to_bytes(),
+            merkle_root.map_or(&[], |root| &root[..]),
+        ],
+    )
+}
+
+/// Q = P + hash_TapTweak(P || merkle root) * G
+fn output_point(
+    internal_key: &XOnlyPublicKey,
+    merkle_root: Option<&[u8; 32]>,
+) -> Result<Point<SECP256K1>, TaprootError> {
+    internal_key
+        .point()
+        .tweak_add(&tap_tweak(internal_key, merkle_root))
+        .map_err(|_| TaprootError::InvalidTweak)
+}
+
+/// Private key for key path spending, the one of the output key
+/// the internal private key is negated first if its public key has odd y
+pub fn tweak_private_key(
+    private_key: &PrivateKey,
+    merkle_root: Option<&[u8; 32]>,
+) -> Result<PrivateKey, TaprootError> {
+    let point = SECP256K1::public_key(private_key.expose_secret());
+    let tweak = tap_tweak(&XOnlyPublicKey::from_point(&point), merkle_root);
+    let result = if point.y.is_even() {
+        private_key.tweak_add(&tweak)
+    } else {
+        private_key.negate().tweak_add(&tweak)
+    };
+    result.map_err(|_| TaprootError::InvalidTweak)
+}
+
+/// Taproot output, the internal key and script tree behind an output key
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaprootOutput {
+    internal_key: XOnlyPublicKey,
+    tree: Option<TapTree>,
+    output_key: Point<SECP256K1>,
+}
+
+impl TaprootOutput {
+    /// Output of the internal key committing to the tree, if there is one
+    pub fn new(internal_key: &XOnlyPublicKey, tree: Option<TapTree>) -> Result<Self, TaprootError> {
+        let output_key =
+            output_point(internal_key, tree.as_ref().map(|tree| tree.hash()).as_ref())?;
+        Ok(Self {
+            internal_key: internal_key.clone(),
+            tree,
+            output_key,
+        })
+    }
+
+    /// Merkle root of the script tree
+    pub fn merkle_root(&self) -> Option<[u8; 32]> {
+        self.tree.as_ref().map(|tree| tree.hash())
+    }
+
+    /// The tweaked key, which key path signatures verify for
+    pub fn output_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_point(&self.output_key)
+    }
+
+    /// Witness version 1 scriptPubKey, OP_1 followed by a push of the output key
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        [&[0x51, 0x20], &self.output_key().to_bytes()[..]].concat()
+    }
+
+    /// bech32m address, bc1p... with the human readable part "bc" on mainnet
+    pub fn address(&self, hrp: &str) -> String {
+        encode_segwit_address(hrp, 1, &self.output_key().to_bytes())
+    }
+
+    /// Control block for spending the output with one of the tree's scripts
+    pub fn control_block(&self, version: u8, script: &[u8]) -> Result<ControlBlock, TaprootError> {
+        let tree = self.tree.as_ref().ok_or(TaprootError::LeafNotFound)?;
+        let (_, _, merkle_path) = tree
+            .leaves()
+            .into_iter()
+            .find(|leaf| leaf.0 == version && leaf.1 == script)
+            .ok_or(TaprootError::LeafNotFound)?;
+        Ok(ControlBlock {
+            leaf_version: version,
+            output_key_odd: !self.output_key.y.is_even(),
+            internal_key: self.internal_key.clone(),
+            merkle_path,
+        })
+    }
+}
+
+/// Control block, what a script path spend reveals besides the script
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControlBlock {
+    pub leaf_version: u8,
+    pub output_key_odd: bool,
+    pub internal_key: XOnlyPublicKey,
+    pub merkle_path: Vec<[u8; 32]>,
+}
+
+impl ControlBlock {
+    /// Control block from its encoding, leaf version | parity, internal key, merkle path
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TaprootError> {
+        if bytes.len() < 33
+            || !(bytes.len() - 33).is_multiple_of(32)
+            || bytes.len() > 33 + 32 * MAX_DEPTH
+        {
+            return Err(TaprootError::InvalidControlBlock);
+        }
+        let internal_key =
+            XOnlyPublicKey::from_bytes(&bytes[1..33]).ok_or(TaprootError::InvalidControlBlock)?;
+        Ok(Self {
+            leaf_version: bytes[0] & 0xfe,
+            output_key_odd: bytes[0] & 1 == 1,
+            internal_key,
+            merkle_path: bytes[33..]
+                .chunks(32)
+                .map(|hash| hash.try_into().unwrap())
+                .collect(),
+        })
+    }
+
+    /// Encoding of the control block
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.leaf_version | self.output_key_odd as u8];
+        bytes.extend(self.internal_key.to_bytes());
+        for hash in &self.merkle_path {
+            bytes.extend(hash);
+        }
+        bytes
+    }
+
+    /// Whether the script is committed to by the output key, the consensus check
+    /// of a script path spend before the script runs
+    pub fn verify(&self, output_key: &XOnlyPublicKey, script: &[u8]) -> bool {
+        let merkle_root = self
+            .merkle_path
+            .iter()
+            .fold(leaf_hash(self.leaf_version, script), |hash, sibling| {
+                branch_hash(&hash, sibling)
+            });
+        match output_point(&self.internal_key, Some(&merkle_root)) {
+            Ok(point) => {
+                point.x == output_key.point().x && point.y.is_even() != self.output_key_odd
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schnorr::{sign, verify, XOnlyPublicKey};
+    use crate::secret::PrivateKey;
+    use crate::taproot::{
+        leaf_hash, tap_tweak, tweak_private_key, ControlBlock, TapTree, TaprootError,
+        TaprootOutput, TAPSCRIPT_LEAF_VERSION,
+    };
+    use serde_json::Value;
+    use std::str::FromStr;
+
+    fn tree(value: &Value) -> TapTree {
+        match value {
+            Value::Array(children) => TapTree::branch(tree(&children[0]), tree(&children[1])),
+            leaf => TapTree::leaf_with_version(
+                leaf["leafVersion"].as_u64().unwrap() as u8,
+                &hex::decode(leaf["script"].as_str().unwrap()).unwrap(),
+            )
+            .unwrap(),
+        }
+    }
+
+    fn strings(value: &Value) -> Vec<&str> {
+        value.as_array().map_or(vec![], |values| {
+            values.iter().map(|value| value.as_str().unwrap()).collect()
+        })
+    }
+
+    #[test]
+    fn bip341_wallet_vectors() {
+        // the scriptPubKey half of the BIP-341 wallet vectors, the key path spending half
+        // signs whole transactions
+        // see: https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json
+        let vectors: Value =
+            serde_json::from_str(include_str!("../test_vectors/bip341_wallet_vectors.json"))
+                .unwrap();
+        for vector in vectors["scriptPubKey"].as_array().unwrap() {
+            let (given, intermediary, expected) = (
+                &vector["given"],
+                &vector["intermediary"],
+                &vector["expected"],
+            );
+            let internal_key = XOnlyPublicKey::from_bytes(
+                &hex::decode(given["internalPubkey"].as_str().unwrap()).unwrap(),
+            )
+            .unwrap();
+            let tree = (!given["scriptTree"].is_null()).then(|| tree(&given["scriptTree"]));
+            let output = TaprootOutput::new(&internal_key, tree.clone()).unwrap();
+
+            let merkle_root = output.merkle_root();
+            assert_eq!(
+                merkle_root.map(hex::encode),
+                intermediary["merkleRoot"].as_str().map(String::from)
+            );
+            assert_eq!(
+                hex::encode(tap_tweak(&internal_key, merkle_root.as_ref())),
+                intermediary["tweak"].as_str().unwrap()
+            );
+            assert_eq!(
+                hex::encode(output.output_key().to_bytes()),
+                intermediary["tweakedPubkey"].as_str().unwrap()
+            );
+            assert_eq!(
+                hex::encode(output.script_pubkey()),
+                expected["scriptPubKey"].as_str().unwrap()
+            );
+            assert_eq!(
+                output.address("bc"),
+                expected["bip350Address"].as_str().unwrap()
+            );
+
+            // leaves and control blocks are both listed depth first
+            let leaves = tree.as_ref().map_or(vec![], |tree| tree.leaves());
+            let leaf_hashes = leaves
+                .iter()
+                .map(|(version, script, _)| hex::encode(leaf_hash(*version, script)))
+                .collect::<Vec<_>>();
+            assert_eq!(leaf_hashes, strings(&intermediary["leafHashes"]));
+            let control_blocks = strings(&expected["scriptPathControlBlocks"]);
+            assert_eq!(leaves.len(), control_blocks.len());
+            for ((version, script, _), expected) in leaves.iter().zip(control_blocks) {
+                let control_block = output.control_block(*version, script).unwrap();
+                assert_eq!(hex::encode(control_block.to_bytes()), expected);
+
+                let parsed = ControlBlock::from_bytes(&hex::decode(expected).unwrap()).unwrap();
+                assert_eq!(parsed, control_block);
+                assert!(parsed.verify(&output.output_key(), script));
+                assert!(!parsed.verify(&output.output_key(), b"another script"));
+                assert!(!parsed.verify(&internal_key, script));
+            }
+            if tree.is_none() {
+                assert_eq!(
+                    output.control_block(TAPSCRIPT_LEAF_VERSION, &[]),
+                    Err(TaprootError::LeafNotFound)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn key_path_spending() {
+        // the internal key has odd y, so the private key is negated before the tweak
+        let private_key: PrivateKey = PrivateKey::from_str("3424").unwrap();
+        let tree = TapTree::branch(TapTree::leaf(b"script a"), TapTree::leaf(b"script b"));
+        for tree in [None, Some(tree)] {
+            let internal_key = XOnlyPublicKey::from_private_key(&private_key);
+            let output = TaprootOutput::new(&internal_key, tree).unwrap();
+            let tweaked = tweak_private_key(&private_key, output.merkle_root().as_ref()).unwrap();
+            assert_eq!(
+                XOnlyPublicKey::from_private_key(&tweaked),
+                output.output_key()
+            );
+
+            let signature = sign(b"spend", &tweaked).unwrap();
+            assert!(verify(b"spend", &output.output_key(), &signature));
+        }
+
+        assert_eq!(
+            TapTree::leaf_with_version(0xc1, &[]),
+            Err(TaprootError::InvalidLeafVersion)
+        );
+        assert_eq!(
+            ControlBlock::from_bytes(&[0xc0; 34]),
+            Err(TaprootError::InvalidControlBlock)
+        );
+    }
+}
@@ -0,0 +1,923 @@
+use crate::curve::{Curve, Point};
+use crate::ecdsa::{digest_to_scalar, verify_message, Signature};
+use crate::field::Scalar;
+use crate::frost::{lagrange_coefficient, KeyPackage, PublicKeyPackage};
+use crate::hash::tagged_hash;
+use crate::msm::multi_scalar_multiplication;
+use crate::paillier::{
+    from_scalar, order_power, random_below, to_scalar, BigUint, Ciphertext, DecryptionKey,
+    EncryptionKey, FactorProof, ModulusProof, RangeProof, ResponseProof, RingPedersen,
+    RingPedersenProof,
+};
+use crate::ru256::RU256;
+use crate::ruint::ModInt;
+use crate::secp256k1::SECP256K1;
+use crate::secret::{wipe, Nonce, PrivateKey};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Threshold ECDSA in the style of GG18, see: https://eprint.iacr.org/2019/114.pdf
+// any t of the n participants sign together without anyone learning the
+// private key, and the result is an ordinary ECDSA signature for the group key,
+// two-party ECDSA is the 2-of-2 case
+//
+// the key shares come from the FROST DKG (see: frost.rs), participant i holds
+// x_i = f(i) for the group secret x = f(0), and for a set of signers w_i = λ_i * x_i
+// with their Lagrange coefficients are additive shares of x, W_i = λ_i * X_i public
+// on top of that everyone makes a Paillier key (see: paillier.rs) in three setup rounds:
+// the keys and ring-Pedersen parameters go out with the proofs of their form,
+// then everyone proves their primes aren't small for each other participant's
+// parameters, which needs a round of its own
+//
+// signing picks k = Σ k_i and γ = Σ γ_i, and turns products of shares into sums
+// with MtA, the multiplicative to additive share conversion: i sends Enc_i(a),
+// j answers Enc_i(a * b + β') homomorphically and keeps β = -β', i decrypts
+// α = a * b + β', so α + β = a * b, both with range proofs so nobody can wrap N
+// 1. every signer sends Enc_i(k_i) with a range proof for every other signer,
+//    and commits to Γ_i = γ_i * G
+// 2. every pair runs MtA for k_i * γ_j and, checked against W_j, for k_i * w_j
+// 3. δ_i = k_i γ_i + Σ (α_ij + β_ji) and σ_i = k_i w_i + Σ (μ_ij + ν_ji) are
+//    additive shares of k γ and k x, signers broadcast δ_i and open Γ_i
+// 4. R = (Σ Γ_i) / δ = k^-1 * G, r = x(R) mod n and s_i = m k_i + r σ_i
+// 5. s = Σ s_i = k (m + r x), the signature for the nonce k^-1
+//
+// unlike GG18's phase 5 the shares s_i are only checked through the final
+// signature, a bad one makes aggregation fail without naming anyone
+
+/// Errors of the threshold ECDSA protocol, naming the participant at fault where there is one
+#[derive(Debug, PartialEq, Eq)]
+pub enum ThresholdEcdsaError {
+    /// The packages don't come from the expected participants
+    UnexpectedParticipants,
+    /// Fewer signers than the threshold, or the signer isn't one of them
+    NotEnoughSigners,
+    /// A participant's Paillier key or ring-Pedersen parameters are too small or malformed
+    InvalidSetup { participant: u16 },
+    /// A participant's proof that their Paillier key or ring-Pedersen parameters are well formed fails
+    InvalidSetupProof { participant: u16 },
+    /// A participant's range proof doesn't verify
+    InvalidRangeProof { participant: u16 },
+    /// A participant's Γ_i doesn't open their commitment, or their proof of knowledge fails
+    InvalidCommitment { participant: u16 },
+    /// The nonce came out as zero, signing has to start over
+    InvalidNonce,
+    /// The signature shares don't add up to a valid signature
+    InvalidSignature,
+}
+
+/// Bits of the Paillier moduli, k_i * γ_j + β' < q^2 + q^5 has to stay below N
+const MODULUS_BITS: usize = 2048;
+
+/// A participant's public Paillier key and ring-Pedersen parameters,
+/// with the proofs that both are well formed
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuxiliaryInfo {
+    encryption_key: EncryptionKey,
+    ring_pedersen: RingPedersen,
+    modulus_proof: ModulusProof,
+    ring_pedersen_proof: RingPedersenProof,
+}
+
+/// A participant's Paillier key, kept between the setup rounds
+#[derive(Debug)]
+pub struct SetupSecret {
+    decryption_key: DecryptionKey,
+    info: AuxiliaryInfo,
+}
+
+/// First setup round, run after the DKG
+/// returns the Paillier key to keep and its public part to broadcast
+pub fn setup_part1() -> (SetupSecret, AuxiliaryInfo) {
+    let decryption_key = DecryptionKey::generate();
+    let info = AuxiliaryInfo {
+        encryption_key: decryption_key.encryption_key().clone(),
+        ring_pedersen: decryption_key.ring_pedersen(),
+        modulus_proof: decryption_key.modulus_proof(),
+        ring_pedersen_proof: decryption_key.ring_pedersen_proof(),
+    };
+    let secret = SetupSecret {
+        decryption_key,
+        info: info.clone(),
+    };
+    (secret, info)
+}
+
+/// Second setup round, checks the other participants' public Paillier data
+/// and returns the proofs that this participant's primes aren't small, one
+/// for each other participant as it is made with their ring-Pedersen parameters
+pub fn setup_part2(
+    secret: &SetupSecret,
+    key_package: &KeyPackage,
+    public_key_package: &PublicKeyPackage,
+    infos: &BTreeMap<u16, AuxiliaryInfo>,
+) -> Result<BTreeMap<u16, FactorProof>, ThresholdEcdsaError> {
+    check_infos(key_package, public_key_package, infos)?;
+    for (participant, info) in infos {
+        if !info.modulus_proof.verify(&info.encryption_key)
+            || !info.ring_pedersen_proof.verify(&info.ring_pedersen)
+        {
+            return Err(ThresholdEcdsaError::InvalidSetupProof {
+                participant: *participant,
+            });
+        }
+    }
+
+    Ok(infos
+        .iter()
+        .map(|(participant, info)| {
+            let proof = secret.decryption_key.factor_proof(&info.ring_pedersen);
+            (*participant, proof)
+        })
+        .collect())
+}
+
+/// Third setup round, checks the proofs the other participants made for this
+/// participant's ring-Pedersen parameters and puts everything needed for signing together
+/// the infos are the ones of the second round
+pub fn setup_part3(
+    secret: SetupSecret,
+    key_package: KeyPackage,
+    public_key_package: PublicKeyPackage,
+    infos: &BTreeMap<u16, AuxiliaryInfo>,
+    proofs: &BTreeMap<u16, FactorProof>,
+) -> Result<KeyShare, ThresholdEcdsaError> {
+    check_infos(&key_package, &public_key_package, infos)?;
+    if !proofs.keys().eq(infos.keys()) {
+        return Err(ThresholdEcdsaError::UnexpectedParticipants);
+    }
+    let own_setup = &secret.info.ring_pedersen;
+    for (participant, proof) in proofs {
+        if !proof.verify(&infos[participant].encryption_key, own_setup) {
+            return Err(ThresholdEcdsaError::InvalidSetupProof {
+                participant: *participant,
+            });
+        }
+    }
+
+    let identifier = key_package.identifier;
+    let mut auxiliary = infos.clone();
+    auxiliary.insert(identifier, secret.info);
+    Ok(KeyShare {
+        key_package,
+        public_key_package,
+        decryption_key: secret.decryption_key,
+        auxiliary,
+    })
+}
+
+/// Check that the infos come from exactly the other participants and that
+/// their Paillier keys and ring-Pedersen parameters are big enough
+fn check_infos(
+    key_package: &KeyPackage,
+    public_key_package: &PublicKeyPackage,
+    infos: &BTreeMap<u16, AuxiliaryInfo>,
+) -> Result<(), ThresholdEcdsaError> {
+    let participants: Vec<u16> = public_key_package
+        .verifying_shares
+        .keys()
+        .copied()
+        .collect();
+    check_participants(key_package.identifier, &participants, infos)?;
+
+    for (participant, info) in infos {
+        if info.encryption_key.bits() < MODULUS_BITS
+            || info.ring_pedersen.bits() < MODULUS_BITS
+            || !info.ring_pedersen.is_valid()
+        {
+            return Err(ThresholdEcdsaError::InvalidSetup {
+                participant: *participant,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// A participant's key share with the Paillier keys, everything needed to sign
+#[derive(Debug)]
+pub struct KeyShare {
+    key_package: KeyPackage,
+    public_key_package: PublicKeyPackage,
+    decryption_key: DecryptionKey,
+    /// everyone's public Paillier data, this participant's own included
+    auxiliary: BTreeMap<u16, AuxiliaryInfo>,
+}
+
+impl KeyShare {
+    pub fn identifier(&self) -> u16 {
+        self.key_package.identifier
+    }
+
+    /// Group public key, which the signatures verify for
+    pub fn public_key(&self) -> Point<SECP256K1> {
+        self.key_package.group_public_key.clone()
+    }
+
+    fn own_info(&self) -> &AuxiliaryInfo {
+        &self.auxiliary[&self.identifier()]
+    }
+
+    /// W_j = λ_j * X_j, the public part of a signer's additive share
+    fn additive_verifying_share(&self, participant: u16, signers: &[u16]) -> Point<SECP256K1> {
+        SECP256K1::scalar_multiplication(
+            &lagrange_coefficient(participant, signers),
+            &self.public_key_package.verifying_shares[&participant],
+        )
+    }
+}
+
+/// Check that the packages come from exactly the other signers
+fn check_participants<T>(
+    identifier: u16,
+    signers: &[u16],
+    packages: &BTreeMap<u16, T>,
+) -> Result<(), ThresholdEcdsaError> {
+    let others = signers.iter().copied().filter(|other| *other != identifier);
+    if !packages.keys().copied().eq(others) {
+        return Err(ThresholdEcdsaError::UnexpectedParticipants);
+    }
+    Ok(())
+}
+
+/// Commitment to Γ_i, opened in the third round
+fn gamma_commitment(identifier: u16, gamma_point: &Point<SECP256K1>, blind: &[u8; 32]) -> [u8; 32] {
+    tagged_hash(
+        "tECDSA/commitment",
+        &[
+            &identifier.to_be_bytes(),
+            &gamma_point.to_compressed(),
+            blind,
+        ],
+    )
+}
+
+/// Challenge of the proof of knowledge of γ_i, bound to the participant
+fn gamma_challenge(
+    identifier: u16,
+    gamma_point: &Point<SECP256K1>,
+    r: &Point<SECP256K1>,
+) -> Scalar<SECP256K1> {
+    let hash = tagged_hash(
+        "tECDSA/gamma",
+        &[
+            &identifier.to_be_bytes(),
+            &gamma_point.to_compressed(),
+            &r.to_compressed(),
+        ],
+    );
+    Scalar::reduce(&RU256::from_bytes(&hash))
+}
+
+/// A signer's secrets after the first round
+/// wiped from memory when dropped and never printed
+pub struct Round1Secret {
+    identifier: u16,
+    signers: Vec<u16>,
+    k: Nonce<SECP256K1>,
+    gamma: Nonce<SECP256K1>,
+    /// w_i = λ_i * x_i
+    w: PrivateKey,
+    /// Enc_i(k_i) and its randomness
+    ciphertext: Ciphertext,
+    randomness: BigUint,
+    blind: [u8; 32],
+}
+
+impl Drop for Round1Secret {
+    fn drop(&mut self) {
+        wipe(&mut self.randomness);
+    }
+}
+
+impl fmt::Debug for Round1Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Round1Secret([REDACTED])")
+    }
+}
+
+/// What a signer broadcasts in the first round, Enc_i(k_i) and a commitment to Γ_i
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round1Broadcast {
+    commitment: [u8; 32],
+    ciphertext: Ciphertext,
+}
+
+/// What a signer sends each other signer in the first round
+/// the range proof of Enc_i(k_i) for the receiver's ring-Pedersen parameters
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round1Message {
+    proof: RangeProof,
+}
+
+/// First signing round, for a set of at least t signers this signer is one of
+#[allow(clippy::type_complexity)]
+pub fn sign_round1(
+    key_share: &KeyShare,
+    signers: &[u16],
+) -> Result<(Round1Secret, Round1Broadcast, BTreeMap<u16, Round1Message>), ThresholdEcdsaError> {
+    let identifier = key_share.identifier();
+    let mut signers = signers.to_vec();
+    signers.sort_unstable();
+    signers.dedup();
+    if signers.len() < key_share.public_key_package.min_signers as usize
+        || !signers.contains(&identifier)
+    {
+        return Err(ThresholdEcdsaError::NotEnoughSigners);
+    }
+    if signers
+        .iter()
+        .any(|signer| !key_share.auxiliary.contains_key(signer))
+    {
+        return Err(ThresholdEcdsaError::UnexpectedParticipants);
+    }
+
+    let mut w = &lagrange_coefficient(identifier, &signers)
+        * key_share.key_package.signing_share.expose_secret();
+    let k = Nonce::<SECP256K1>::random();
+    let gamma = Nonce::<SECP256K1>::random();
+    let blind: [u8; 32] = rand::thread_rng().gen();
+    let gamma_point = SECP256K1::public_key(gamma.expose_secret());
+    let commitment = gamma_commitment(identifier, &gamma_point, &blind);
+
+    let key = &key_share.own_info().encryption_key;
+    let mut k_value = from_scalar(k.expose_secret());
+    let randomness = key.encryption_randomness();
+    let ciphertext = key.encrypt_with(&k_value, &randomness);
+    let messages = signers
+        .iter()
+        .filter(|signer| **signer != identifier)
+        .map(|signer| {
+            let setup = &key_share.auxiliary[signer].ring_pedersen;
+            let proof = RangeProof::prove(key, setup, &ciphertext, &k_value, &randomness);
+            (*signer, Round1Message { proof })
+        })
+        .collect();
+    wipe(&mut k_value);
+
+    let secret = Round1Secret {
+        identifier,
+        signers,
+        k,
+        gamma,
+        w: PrivateKey::new(w.value()),
+        ciphertext: ciphertext.clone(),
+        randomness,
+        blind,
+    };
+    w.wipe();
+    let broadcast = Round1Broadcast {
+        commitment,
+        ciphertext,
+    };
+    Ok((secret, broadcast, messages))
+}
+
+/// The answering side of MtA: Enc(a * b + β') from Enc(a) with a range proof
+/// and -β' mod n, this side's additive share of a * b
+fn mta_respond(
+    key: &EncryptionKey,
+    setup: &RingPedersen,
+    ciphertext: &Ciphertext,
+    b: &Scalar<SECP256K1>,
+    with_point: bool,
+) -> (Ciphertext, ResponseProof, Scalar<SECP256K1>) {
+    let mut b_value = from_scalar(b);
+    let mut beta_prime = random_below(&order_power(5));
+    let randomness = key.encryption_randomness();
+    let answer = key.add(
+        &key.mul(ciphertext, &b_value),
+        &key.encrypt_with(&beta_prime, &randomness),
+    );
+    let proof = ResponseProof::prove(
+        key,
+        setup,
+        ciphertext,
+        &answer,
+        &b_value,
+        &beta_prime,
+        &randomness,
+        with_point,
+    );
+    let beta = -to_scalar(&beta_prime);
+    wipe(&mut b_value);
+    wipe(&mut beta_prime);
+    (answer, proof, beta)
+}
+
+/// A signer's secrets after the second round
+/// wiped from memory when dropped and never printed
+pub struct Round2Secret {
+    round1: Round1Secret,
+    /// the other signers' commitments to their Γ_j
+    commitments: BTreeMap<u16, [u8; 32]>,
+    /// Σ β_ji and Σ ν_ji, the answering sides of the conversions
+    beta_sum: Scalar<SECP256K1>,
+    nu_sum: Scalar<SECP256K1>,
+}
+
+impl Drop for Round2Secret {
+    fn drop(&mut self) {
+        self.beta_sum.wipe();
+        self.nu_sum.wipe();
+    }
+}
+
+impl fmt::Debug for Round2Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Round2Secret([REDACTED])")
+    }
+}
+
+/// What a signer sends each other signer in the second round
+/// the answers to their Enc_j(k_j) for γ_i and for w_i
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round2Message {
+    gamma_ciphertext: Ciphertext,
+    gamma_proof: ResponseProof,
+    w_ciphertext: Ciphertext,
+    w_proof: ResponseProof,
+}
+
+/// Second signing round, checks the other signers' range proofs and answers
+/// their encrypted k_j with this signer's γ_i and w_i
+pub fn sign_round2(
+    key_share: &KeyShare,
+    secret: Round1Secret,
+    broadcasts: &BTreeMap<u16, Round1Broadcast>,
+    messages: &BTreeMap<u16, Round1Message>,
+) -> Result<(Round2Secret, BTreeMap<u16, Round2Message>), ThresholdEcdsaError> {
+    check_participants(secret.identifier, &secret.signers, broadcasts)?;
+    check_participants(secret.identifier, &secret.signers, messages)?;
+
+    let own_setup = &key_share.own_info().ring_pedersen;
+    let mut beta_sum = Scalar::zero();
+    let mut nu_sum = Scalar::zero();
+    let mut answers = BTreeMap::new();
+    for (participant, broadcast) in broadcasts {
+        let key = &key_share.auxiliary[participant].encryption_key;
+        let setup = &key_share.auxiliary[participant].ring_pedersen;
+        if !messages[participant]
+            .proof
+            .verify(key, own_setup, &broadcast.ciphertext)
+        {
+            return Err(ThresholdEcdsaError::InvalidRangeProof {
+                participant: *participant,
+            });
+        }
+
+        let (gamma_ciphertext, gamma_proof, mut beta) = mta_respond(
+            key,
+            setup,
+            &broadcast.ciphertext,
+            secret.gamma.expose_secret(),
+            false,
+        );
+        let (w_ciphertext, w_proof, mut nu) = mta_respond(
+            key,
+            setup,
+            &broadcast.ciphertext,
+            secret.w.expose_secret(),
+            true,
+        );
+        beta_sum = &beta_sum + &beta;
+        nu_sum = &nu_sum + &nu;
+        beta.wipe();
+        nu.wipe();
+        answers.insert(
+            *participant,
+            Round2Message {
+                gamma_ciphertext,
+                gamma_proof,
+                w_ciphertext,
+                w_proof,
+            },
+        );
+    }
+
+    let commitments = broadcasts
+        .iter()
+        .map(|(participant, broadcast)| (*participant, broadcast.commitment))
+        .collect();
+    let secret = Round2Secret {
+        round1: secret,
+        commitments,
+        beta_sum,
+        nu_sum,
+    };
+    Ok((secret, answers))
+}
+
+/// A signer's secrets after the third round
+/// wiped from memory when dropped and never printed
+pub struct Round3Secret {
+    identifier: u16,
+    signers: Vec<u16>,
+    k: Nonce<SECP256K1>,
+    /// σ_i, the additive share of k * x
+    sigma: PrivateKey,
+    commitments: BTreeMap<u16, [u8; 32]>,
+    broadcast: Round3Broadcast,
+}
+
+impl fmt::Debug for Round3Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Round3Secret([REDACTED])")
+    }
+}
+
+/// What a signer broadcasts in the third round
+/// δ_i, the opening of the commitment to Γ_i and a proof of knowledge of γ_i
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round3Broadcast {
+    delta: Scalar<SECP256K1>,
+    gamma_point: Point<SECP256K1>,
+    blind: [u8; 32],
+    proof_r: Point<SECP256K1>,
+    proof_mu: Scalar<SECP256K1>,
+}
+
+/// Third signing round, checks and decrypts the answers to this signer's
+/// Enc_i(k_i) and returns the shares δ_i of k * γ and σ_i of k * x
+pub fn sign_round3(
+    key_share: &KeyShare,
+    secret: Round2Secret,
+    messages: &BTreeMap<u16, Round2Message>,
+) -> Result<(Round3Secret, Round3Broadcast), ThresholdEcdsaError> {
+    let round1 = &secret.round1;
+    check_participants(round1.identifier, &round1.signers, messages)?;
+
+    let info = key_share.own_info();
+    let (key, setup) = (&info.encryption_key, &info.ring_pedersen);
+    let k = round1.k.expose_secret();
+    let mut delta = &(k * round1.gamma.expose_secret()) + &secret.beta_sum;
+    let mut sigma = &(k * round1.w.expose_secret()) + &secret.nu_sum;
+    for (participant, message) in messages {
+        let w_point = key_share.additive_verifying_share(*participant, &round1.signers);
+        if !message.gamma_proof.verify(
+            key,
+            setup,
+            &round1.ciphertext,
+            &message.gamma_ciphertext,
+            None,
+        ) || !message.w_proof.verify(
+            key,
+            setup,
+            &round1.ciphertext,
+            &message.w_ciphertext,
+            Some(&w_point),
+        ) {
+            delta.wipe();
+            sigma.wipe();
+            return Err(ThresholdEcdsaError::InvalidRangeProof {
+                participant: *participant,
+            });
+        }
+
+        let mut alpha = key_share.decryption_key.decrypt(&message.gamma_ciphertext);
+        let mut mu = key_share.decryption_key.decrypt(&message.w_ciphertext);
+        delta = &delta + &to_scalar(&alpha);
+        sigma = &sigma + &to_scalar(&mu);
+        wipe(&mut alpha);
+        wipe(&mut mu);
+    }
+
+    // mu = t + c * γ_i, for R = t * G
+    let gamma = round1.gamma.expose_secret();
+    let gamma_point = SECP256K1::public_key(gamma);
+    let t = Nonce::<SECP256K1>::random();
+    let proof_r = SECP256K1::public_key(t.expose_secret());
+    let c = gamma_challenge(round1.identifier, &gamma_point, &proof_r);
+    let proof_mu = t.expose_secret() + &(&c * gamma);
+
+    let broadcast = Round3Broadcast {
+        delta: delta.clone(),
+        gamma_point,
+        blind: round1.blind,
+        proof_r,
+        proof_mu,
+    };
+    let state = Round3Secret {
+        identifier: round1.identifier,
+        signers: round1.signers.clone(),
+        k: Nonce::new(k.value()),
+        sigma: PrivateKey::new(sigma.value()),
+        commitments: secret.commitments.clone(),
+        broadcast: broadcast.clone(),
+    };
+    sigma.wipe();
+    delta.wipe();
+    Ok((state, broadcast))
+}
+
+/// A signer's part of the signature, r and s_i
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureShare {
+    r: Scalar<SECP256K1>,
+    s: Scalar<SECP256K1>,
+}
+
+/// Fourth signing round, checks the other signers' Γ_j and returns this
+/// signer's share of the signature of the message
+pub fn sign_round4(
+    secret: Round3Secret,
+    message: &str,
+    broadcasts: &BTreeMap<u16, Round3Broadcast>,
+) -> Result<SignatureShare, ThresholdEcdsaError> {
+    check_participants(secret.identifier, &secret.signers, broadcasts)?;
+
+    // mu * G = R + c * Γ_j, and Γ_j has to be the point committed to in the first round
+    for (participant, broadcast) in broadcasts {
+        let point = &broadcast.gamma_point;
+        let c = gamma_challenge(*participant, point, &broadcast.proof_r);
+        let expected = multi_scalar_multiplication(
+            &[Scalar::one(), c],
+            &[broadcast.proof_r.clone(), point.clone()],
+        );
+        if point.is_zero_point()
+            || !SECP256K1::is_on_curve(point)
+            || gamma_commitment(*participant, point, &broadcast.blind)
+                != secret.commitments[participant]
+            || SECP256K1::public_key(&broadcast.proof_mu) != expected
+        {
+            return Err(ThresholdEcdsaError::InvalidCommitment {
+                participant: *participant,
+            });
+        }
+    }
+
+    // R = Γ / δ = (γ * G) / (k * γ) = k^-1 * G
+    let all = broadcasts.values().chain([&secret.broadcast]);
+    let delta = all
+        .clone()
+        .fold(Scalar::zero(), |sum, broadcast| &sum + &broadcast.delta);
+    if delta.is_zero() {
+        return Err(ThresholdEcdsaError::InvalidNonce);
+    }
+    let gamma_points: Vec<Point<SECP256K1>> =
+        all.map(|broadcast| broadcast.gamma_point.clone()).collect();
+    let gamma_sum =
+        multi_scalar_multiplication(&vec![Scalar::one(); gamma_points.len()], &gamma_points);
+    let point = SECP256K1::scalar_multiplication(&delta.invert(), &gamma_sum);
+    let r = Scalar::reduce(point.x.value());
+    if point.is_zero_point() || r.is_zero() {
+        return Err(ThresholdEcdsaError::InvalidNonce);
+    }
+
+    // s_i = m * k_i + r * σ_i
+    let digest = SECP256K1::HASH.digest(message.as_bytes());
+    let hash = digest_to_scalar::<SECP256K1>(&digest);
+    let s = &(&hash * secret.k.expose_secret()) + &(&r * secret.sigma.expose_secret());
+    Ok(SignatureShare { r, s })
+}
+
+/// Sum the signature shares into the ECDSA signature of the message
+/// s is normalized to the lower half like libsecp256k1 does
+pub fn aggregate(
+    public_key_package: &PublicKeyPackage,
+    message: &str,
+    shares: &BTreeMap<u16, SignatureShare>,
+) -> Result<Signature, ThresholdEcdsaError> {
+    let r = match shares.values().next() {
+        Some(share) => share.r.clone(),
+        None => return Err(ThresholdEcdsaError::NotEnoughSigners),
+    };
+    if shares.len() < public_key_package.min_signers as usize {
+        return Err(ThresholdEcdsaError::NotEnoughSigners);
+    }
+    if shares.values().any(|share| share.r != r) {
+        return Err(ThresholdEcdsaError::InvalidSignature);
+    }
+
+    let s = shares
+        .values()
+        .fold(Scalar::zero(), |sum, share| &sum + &share.s);
+    let s = if s.value() > &SECP256K1::n().shr(1) {
+        -s
+    } else {
+        s
+    };
+    let signature =
+        Signature::new(r.value(), s.value()).ok_or(ThresholdEcdsaError::InvalidSignature)?;
+    if !verify_message(message, &public_key_package.group_public_key, &signature) {
+        return Err(ThresholdEcdsaError::InvalidSignature);
+    }
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecdsa::{verify_message, Signature};
+    use crate::frost::tests::dkg;
+    use crate::frost::PublicKeyPackage;
+    use crate::paillier::BigUint;
+    use crate::ruint::ModInt;
+    use crate::threshold_ecdsa::{
+        aggregate, setup_part1, setup_part2, setup_part3, sign_round1, sign_round2, sign_round3,
+        sign_round4, AuxiliaryInfo, KeyShare, ThresholdEcdsaError,
+    };
+    use std::collections::BTreeMap;
+
+    /// DKG and Paillier setup for participants 1 to max_signers
+    fn setup(max_signers: u16, min_signers: u16) -> (Vec<KeyShare>, PublicKeyPackage) {
+        let (key_packages, public_key_package) = dkg(max_signers, min_signers);
+        let (secrets, infos): (Vec<_>, BTreeMap<u16, AuxiliaryInfo>) = key_packages
+            .iter()
+            .map(|key| {
+                let (secret, info) = setup_part1();
+                (secret, (key.identifier(), info))
+            })
+            .unzip();
+        let sent: BTreeMap<u16, _> = key_packages
+            .iter()
+            .zip(&secrets)
+            .map(|(key, secret)| {
+                let other_infos = others(&infos, key.identifier());
+                let proofs = setup_part2(secret, key, &public_key_package, &other_infos).unwrap();
+                (key.identifier(), proofs)
+            })
+            .collect();
+        let mut received = route(&sent);
+        let key_shares = key_packages
+            .into_iter()
+            .zip(secrets)
+            .map(|(key, secret)| {
+                let identifier = key.identifier();
+                let other_infos = others(&infos, identifier);
+                let proofs = received.remove(&identifier).unwrap();
+                setup_part3(
+                    secret,
+                    key,
+                    public_key_package.clone(),
+                    &other_infos,
+                    &proofs,
+                )
+                .unwrap()
+            })
+            .collect();
+        (key_shares, public_key_package)
+    }
+
+    /// Every receiver's messages from a round, by sender
+    fn route<T: Clone>(sent: &BTreeMap<u16, BTreeMap<u16, T>>) -> BTreeMap<u16, BTreeMap<u16, T>> {
+        let mut received: BTreeMap<u16, BTreeMap<u16, T>> = BTreeMap::new();
+        for (sender, messages) in sent {
+            for (receiver, message) in messages {
+                received
+                    .entry(*receiver)
+                    .or_default()
+                    .insert(*sender, message.clone());
+            }
+        }
+        received
+    }
+
+    /// A broadcast as every receiver sees it, without their own
+    fn others<T: Clone>(broadcasts: &BTreeMap<u16, T>, identifier: u16) -> BTreeMap<u16, T> {
+        broadcasts
+            .iter()
+            .filter(|(other, _)| **other != identifier)
+            .map(|(other, broadcast)| (*other, broadcast.clone()))
+            .collect()
+    }
+
+    /// Run all signing rounds with the given signers, and aggregate
+    /// a tampering signer sends an Enc(k_i) that doesn't match its range proofs
+    fn sign_with(
+        key_shares: &[KeyShare],
+        public_key_package: &PublicKeyPackage,
+        signers: &[u16],
+        message: &str,
+        tamper: Option<u16>,
+    ) -> Result<Signature, ThresholdEcdsaError> {
+        let key_shares: BTreeMap<u16, &KeyShare> = key_shares
+            .iter()
+            .filter(|key| signers.contains(&key.identifier()))
+            .map(|key| (key.identifier(), key))
+            .collect();
+
+        let mut secrets = BTreeMap::new();
+        let mut broadcasts = BTreeMap::new();
+        let mut sent = BTreeMap::new();
+        for (identifier, key) in &key_shares {
+            let (secret, mut broadcast, messages) = sign_round1(key, signers)?;
+            if tamper == Some(*identifier) {
+                let key = &key.own_info().encryption_key;
+                broadcast.ciphertext = key.encrypt(&BigUint::from_u64(1));
+            }
+            secrets.insert(*identifier, secret);
+            broadcasts.insert(*identifier, broadcast);
+            sent.insert(*identifier, messages);
+        }
+        let mut received = route(&sent);
+
+        let mut secrets2 = BTreeMap::new();
+        let mut sent = BTreeMap::new();
+        for (identifier, key) in &key_shares {
+            let (secret, messages) = sign_round2(
+                key,
+                secrets.remove(identifier).unwrap(),
+                &others(&broadcasts, *identifier),
+                &received.remove(identifier).unwrap(),
+            )?;
+            secrets2.insert(*identifier, secret);
+            sent.insert(*identifier, messages);
+        }
+        let mut received = route(&sent);
+
+        let mut secrets3 = BTreeMap::new();
+        let mut broadcasts = BTreeMap::new();
+        for (identifier, key) in &key_shares {
+            let (secret, broadcast) = sign_round3(
+                key,
+                secrets2.remove(identifier).unwrap(),
+                &received.remove(identifier).unwrap(),
+            )?;
+            secrets3.insert(*identifier, secret);
+            broadcasts.insert(*identifier, broadcast);
+        }
+
+        let mut shares = BTreeMap::new();
+        for identifier in key_shares.keys() {
+            let share = sign_round4(
+                secrets3.remove(identifier).unwrap(),
+                message,
+                &others(&broadcasts, *identifier),
+            )?;
+            shares.insert(*identifier, share);
+        }
+        aggregate(public_key_package, message, &shares)
+    }
+
+    #[test]
+    fn threshold_ecdsa_two_party() {
+        let (key_shares, public_key_package) = setup(2, 2);
+        let public_key = key_shares[0].public_key();
+        assert_eq!(key_shares[1].public_key(), public_key);
+
+        let message = "withdraw 1 BTC from cold storage";
+        let signature =
+            sign_with(&key_shares, &public_key_package, &[1, 2], message, None).unwrap();
+        assert!(verify_message(message, &public_key, &signature));
+        assert!(!verify_message("withdraw 2 BTC", &public_key, &signature));
+
+        // nobody signs alone
+        assert_eq!(
+            sign_with(&key_shares, &public_key_package, &[1], message, None).err(),
+            Some(ThresholdEcdsaError::NotEnoughSigners)
+        );
+    }
+
+    #[test]
+    fn threshold_ecdsa_t_of_n() {
+        let (key_shares, public_key_package) = setup(3, 2);
+        let public_key = key_shares[0].public_key();
+
+        // any 2 of the 3 sign for the same key
+        let message = "rotate the hot wallet";
+        for signers in [[1, 3], [2, 3]] {
+            let signature =
+                sign_with(&key_shares, &public_key_package, &signers, message, None).unwrap();
+            assert!(verify_message(message, &public_key, &signature));
+        }
+
+        // a ciphertext that doesn't match its range proof names the sender
+        assert_eq!(
+            sign_with(&key_shares, &public_key_package, &[1, 2], message, Some(2)).err(),
+            Some(ThresholdEcdsaError::InvalidRangeProof { participant: 2 })
+        );
+    }
+
+    #[test]
+    fn threshold_ecdsa_setup_proofs() {
+        let (mut key_packages, public_key_package) = dkg(2, 2);
+        let (secret1, info1) = setup_part1();
+        let (secret2, info2) = setup_part1();
+
+        // proofs copied from another participant don't hold for the sender's key
+        let mut copied = info2.clone();
+        copied.modulus_proof = info1.modulus_proof.clone();
+        let infos = BTreeMap::from([(2, copied)]);
+        assert_eq!(
+            setup_part2(&secret1, &key_packages[0], &public_key_package, &infos).err(),
+            Some(ThresholdEcdsaError::InvalidSetupProof { participant: 2 })
+        );
+        let mut copied = info2.clone();
+        copied.ring_pedersen_proof = info1.ring_pedersen_proof.clone();
+        let infos = BTreeMap::from([(2, copied)]);
+        assert_eq!(
+            setup_part2(&secret1, &key_packages[0], &public_key_package, &infos).err(),
+            Some(ThresholdEcdsaError::InvalidSetupProof { participant: 2 })
+        );
+
+        // a factor proof only holds for the parameters it was made with
+        let infos = BTreeMap::from([(2, info2.clone())]);
+        assert!(setup_part2(&secret1, &key_packages[0], &public_key_package, &infos).is_ok());
+        let own_setup = secret2.decryption_key.factor_proof(&info2.ring_pedersen);
+        let proofs = BTreeMap::from([(2, own_setup)]);
+        let key = key_packages.remove(0);
+        assert_eq!(
+            setup_part3(secret1, key, public_key_package, &infos, &proofs).err(),
+            Some(ThresholdEcdsaError::InvalidSetupProof { participant: 2 })
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use crate::curve::{Curve, JacobianPoint, Point};
+use crate::field::Scalar;
+use crate::ruint::ModInt;
+use crate::secret::{wipe, PrivateKey};
+
+// key tweaks, the same operations as libsecp256k1's secp256k1_ec_*_tweak_*
+// a tweak t moves a key pair (d, P) to (d + t, P + t * G) or (d * t, t * P),
+// and both sides stay a matching pair without the private key ever being
+// shared, which is what BIP-32 children and Taproot outputs are made of
+//
+// tweaks are big endian numbers of the scalar size and must be below n,
+// like in libsecp256k1 a result of zero or the identity is an error rather
+// than a key, the caller decides what to do instead
+
+/// Errors of the tweak operations
+#[derive(Debug, PartialEq, Eq)]
+pub enum TweakError {
+    /// The tweak has the wrong length, is not below n, or is zero for a multiplication
+    InvalidTweak,
+    /// The tweaked key is zero or the identity, or there were no keys to combine
+    InvalidResult,
+}
+
+/// Tweak bytes as a scalar, if they are a number below n
+fn parse_tweak<C: Curve>(bytes: &[u8]) -> Result<Scalar<C>, TweakError> {
+    if bytes.len() != C::n().bits().div_ceil(8) {
+        return Err(TweakError::InvalidTweak);
+    }
+    let mut value = C::Uint::from_bytes(bytes);
+    let tweak = if value < C::n() {
+        Ok(Scalar::reduce(&value))
+    } else {
+        Err(TweakError::InvalidTweak)
+    };
+    wipe(&mut value);
+    tweak
+}
+
+impl<C: Curve> PrivateKey<C> {
+    /// Private key + tweak mod n
+    pub fn tweak_add(&self, tweak: &[u8]) -> Result<Self, TweakError> {
+        let mut tweak = parse_tweak::<C>(tweak)?;
+        let mut key = self.expose_secret() + &tweak;
+        let result = if key.is_zero() {
+            Err(TweakError::InvalidResult)
+        } else {
+            Ok(Self::new(key.value()))
+        };
+        tweak.wipe();
+        key.wipe();
+        result
+    }
+
+    /// Private key * tweak mod n, the tweak must not be zero
+    pub fn tweak_mul(&self, tweak: &[u8]) -> Result<Self, TweakError> {
+        let mut tweak = parse_tweak::<C>(tweak)?;
+        // a non-zero tweak times a non-zero key is never zero, n is prime
+        let result = if tweak.is_zero() {
+            Err(TweakError::InvalidTweak)
+        } else {
+            let mut key = self.expose_secret() * &tweak;
+            let private_key = Self::new(key.value());
+            key.wipe();
+            Ok(private_key)
+        };
+        tweak.wipe();
+        result
+    }
+
+    /// n - private key, the private key of the negated public key
+    pub fn negate(&self) -> Self {
+        let mut key = -self.expose_secret();
+        let private_key = Self::new(key.value());
+        key.wipe();
+        private_key
+    }
+}
+
+impl<C: Curve> Point<C> {
+    /// Public key + tweak * G
+    pub fn tweak_add(&self, tweak: &[u8]) -> Result<Self, TweakError> {
+        let tweak = parse_tweak::<C>(tweak)?;
+        Self::combine(&[self.clone(), C::public_key(&tweak)])
+    }
+
+    /// tweak * public key, the tweak must not be zero
+    pub fn tweak_mul(&self, tweak: &[u8]) -> Result<Self, TweakError> {
+        let tweak = parse_tweak::<C>(tweak)?;
+        if tweak.is_zero() {
+            return Err(TweakError::InvalidTweak);
+        }
+        Ok(C::scalar_multiplication(&tweak, self))
+    }
+
+    /// -public key, the public key of the negated private key
+    pub fn negate(&self) -> Self {
+        Point {
+            x: self.x.clone(),
+            y: -&self.y,
+        }
+    }
+
+    /// Sum of public keys, the public key of the sum of their private keys
+    pub fn combine(points: &[Self]) -> Result<Self, TweakError> {
+        let sum = points
+            .iter()
+            .map(JacobianPoint::from)
+            .reduce(|sum, point| C::add_jacobian_points(&sum, &point))
+            .ok_or(TweakError::InvalidResult)?;
+        if sum.is_zero_point() {
+            return Err(TweakError::InvalidResult);
+        }
+        Ok(C::to_affine(&sum))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::curve::{Curve, Point};
+    use crate::p256::P256;
+    use crate::secp256k1::SECP256K1;
+    use crate::secret::PrivateKey;
+    use crate::tweak::TweakError;
+    use std::str::FromStr;
+
+    #[test]
+    fn tweaks_keep_key_pairs_matching() {
+        let public_key =
+            |private_key: &PrivateKey| SECP256K1::public_key(private_key.expose_secret());
+
+        let private_key: PrivateKey = PrivateKey::from_str(
+            "b7e151628aed2a6abf7158809cf4f3c762e7160f38b4da56a784d9045190cfef",
+        )
+        .unwrap();
+        let point = public_key(&private_key);
+        let tweak = hex::decode("6af9e28dbf9d6aaf027696e2598a1a7f9f1fd5f1c0d5cd7a7a0d2b1ae0abcf41")
+            .unwrap();
+
+        let added = private_key.tweak_add(&tweak).unwrap();
+        assert_eq!(public_key(&added), point.tweak_add(&tweak).unwrap());
+        // d + t computed by hand
+        assert_eq!(
+            added.expose_secret().to_hex(),
+            "22db33f04a8a9519c1e7ef62f67f0e4847580f1a4a42079561bfa59262065def"
+        );
+
+        let multiplied = private_key.tweak_mul(&tweak).unwrap();
+        assert_eq!(public_key(&multiplied), point.tweak_mul(&tweak).unwrap());
+
+        let negated = private_key.negate();
+        assert_eq!(public_key(&negated), point.negate());
+        assert_eq!(
+            Point::combine(&[point.clone(), point.negate()]),
+            Err(TweakError::InvalidResult)
+        );
+        // P + P + (d + t)G = (3d + t)G
+        let d = private_key.expose_secret().to_bytes();
+        let sum = added.tweak_add(&d).unwrap().tweak_add(&d).unwrap();
+        assert_eq!(
+            Point::combine(&[point.clone(), point.clone(), public_key(&added)]).unwrap(),
+            public_key(&sum)
+        );
+
+        // the tweak that sends the key to zero, and tweaks out of range
+        let minus_key = negated.expose_secret().to_bytes();
+        assert_eq!(
+            private_key.tweak_add(&minus_key).unwrap_err(),
+            TweakError::InvalidResult
+        );
+        assert_eq!(point.tweak_add(&minus_key), Err(TweakError::InvalidResult));
+        let mut n = [0u8; 32];
+        SECP256K1::n().to_bytes(&mut n);
+        assert_eq!(
+            private_key.tweak_add(&n).unwrap_err(),
+            TweakError::InvalidTweak
+        );
+        assert_eq!(point.tweak_mul(&[0; 32]), Err(TweakError::InvalidTweak));
+        assert_eq!(point.tweak_add(&tweak[1..]), Err(TweakError::InvalidTweak));
+        assert_eq!(
+            Point::<SECP256K1>::combine(&[]),
+            Err(TweakError::InvalidResult)
+        );
+
+        // any other curve works the same
+        let private_key: PrivateKey<P256> = PrivateKey::from_str("3424").unwrap();
+        let point = P256::public_key(private_key.expose_secret());
+        assert_eq!(
+            P256::public_key(private_key.tweak_mul(&tweak).unwrap().expose_secret()),
+            point.tweak_mul(&tweak).unwrap()
+        );
+    }
+}